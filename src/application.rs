@@ -8,7 +8,7 @@ use once_cell::unsync::OnceCell;
 use crate::{
     config::{APP_ID, VERSION},
     backend::PwvucontrolManager,
-    ui::PwvucontrolWindow,
+    ui::{PwvucontrolPreferencesWindow, PwvucontrolWindow},
 };
 
 mod imp {
@@ -44,6 +44,10 @@ mod imp {
             let obj = self.obj();
             obj.setup_gactions();
             obj.set_accels_for_action("app.quit", &["<primary>q"]);
+            obj.set_accels_for_action("app.preferences", &["<primary>comma"]);
+            obj.set_accels_for_action("win.search", &["<primary>f"]);
+            obj.set_accels_for_action("win.undo", &["<primary>z"]);
+            obj.set_accels_for_action("win.redo", &["<primary><shift>z"]);
         }
     }
 
@@ -70,6 +74,11 @@ mod imp {
                 .set(window)
                 .expect("Failed to initialize application window");
         }
+
+        fn shutdown(&self) {
+            self.manager.shutdown();
+            self.parent_shutdown();
+        }
     }
 
     impl GtkApplicationImpl for PwvucontrolApplication {}
@@ -102,7 +111,18 @@ impl PwvucontrolApplication {
         let about_action = gio::ActionEntry::builder("about")
             .activate(move |app: &Self, _, _| app.show_about())
             .build();
-        self.add_action_entries([quit_action, about_action])
+        let preferences_action = gio::ActionEntry::builder("preferences")
+            .activate(move |app: &Self, _, _| app.show_preferences())
+            .build();
+        self.add_action_entries([quit_action, about_action, preferences_action]);
+        self.setup_notification_actions();
+    }
+
+    fn show_preferences(&self) {
+        let window = self.active_window().unwrap();
+        let preferences = PwvucontrolPreferencesWindow::new(&window);
+
+        preferences.present();
     }
 
     fn show_about(&self) {
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fs, io, path::PathBuf};
+
+use crate::macros::*;
+
+const HEADER: &str = "# Generated by pwvucontrol. Manual changes will be overwritten.\n";
+
+/// Path of a generated config fragment, e.g. `~/.config/pipewire/client.conf.d/pwvucontrol-latency.conf`.
+pub(crate) fn conf_fragment_path(dir: &str, name: &str, extension: &str) -> PathBuf {
+    glib::user_config_dir().join(dir).join(format!("pwvucontrol-{name}.{extension}"))
+}
+
+/// Writes a generated config fragment below the user config directory.
+/// Passing `None` removes the fragment.
+pub(crate) fn write_conf_fragment(dir: &str, name: &str, extension: &str, contents: Option<&str>) -> io::Result<()> {
+    let path = conf_fragment_path(dir, name, extension);
+
+    let Some(contents) = contents else {
+        pwvucontrol_info!("Removing config fragment {}", path.display());
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        };
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    pwvucontrol_info!("Writing config fragment {}", path.display());
+    fs::write(&path, format!("{HEADER}{contents}"))
+}
+
+/// Quotes a string for use in SPA-JSON or Lua config files.
+pub(crate) fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A WirePlumber monitor rule updating the properties of the device or node whose
+/// `match_key` equals `name`.
+pub(crate) struct MonitorRule {
+    pub match_key: &'static str,
+    pub name: String,
+    /// Property names with literal values, strings already quoted.
+    pub props: Vec<(&'static str, String)>,
+}
+
+impl MonitorRule {
+    fn monitor(&self) -> (&'static str, &'static str) {
+        if self.name.starts_with("bluez_") {
+            ("bluez_monitor", "monitor.bluez.rules")
+        } else {
+            ("alsa_monitor", "monitor.alsa.rules")
+        }
+    }
+
+    fn lua(&self) -> String {
+        let (monitor, _) = self.monitor();
+        let props: Vec<String> = self.props.iter().map(|(key, value)| format!("[\"{key}\"] = {value}")).collect();
+
+        format!(
+            "if {monitor} ~= nil and {monitor}.rules ~= nil then\n  table.insert({monitor}.rules, {{\n    matches = {{ {{ {{ \"{}\", \"equals\", {} }} }} }},\n    apply_properties = {{ {} }},\n  }})\nend\n",
+            self.match_key,
+            quote(&self.name),
+            props.join(", ")
+        )
+    }
+
+    fn conf(&self) -> String {
+        let props: Vec<String> = self.props.iter().map(|(key, value)| format!("{key} = {value}")).collect();
+
+        format!(
+            "  {{\n    matches = [ {{ {} = {} }} ]\n    actions = {{ update-props = {{ {} }} }}\n  }}\n",
+            self.match_key,
+            quote(&self.name),
+            props.join(" ")
+        )
+    }
+}
+
+/// Writes monitor rules as fragments for both WirePlumber 0.4 (Lua) and 0.5 (SPA-JSON).
+/// Each version ignores the other's. No rules removes the fragments.
+pub(crate) fn write_monitor_rules(name: &str, rules: &[MonitorRule]) {
+    let (lua, conf) = if rules.is_empty() {
+        (None, None)
+    } else {
+        let lua: String = rules.iter().map(MonitorRule::lua).collect();
+
+        let section = |key: &str| -> String { rules.iter().filter(|rule| rule.monitor().1 == key).map(MonitorRule::conf).collect() };
+        let conf = ["monitor.alsa.rules", "monitor.bluez.rules"]
+            .iter()
+            .map(|key| format!("{key} = [\n{}]\n", section(key)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        (Some(lua), Some(conf))
+    };
+
+    if let Err(e) = write_conf_fragment("wireplumber/main.lua.d", name, "lua", lua.as_deref()) {
+        pwvucontrol_warning!("Cannot write WirePlumber Lua fragment: {e}");
+    }
+    if let Err(e) = write_conf_fragment("wireplumber/wireplumber.conf.d", name, "conf", conf.as_deref()) {
+        pwvucontrol_warning!("Cannot write WirePlumber config fragment: {e}");
+    }
+}
+
+/// Writes PipeWire modules, given as module name and SPA-JSON arguments, as a `context.modules`
+/// fragment the daemon loads on start. No modules removes the fragment.
+pub(crate) fn write_context_modules(name: &str, modules: &[(&str, String)]) {
+    let contents = if modules.is_empty() {
+        None
+    } else {
+        let modules: String = modules
+            .iter()
+            .map(|(module, args)| format!("  {{ name = {module}\n    args = {args}\n    flags = [ nofail ]\n  }}\n"))
+            .collect();
+        Some(format!("context.modules = [\n{modules}]\n"))
+    };
+
+    if let Err(e) = write_conf_fragment("pipewire/pipewire.conf.d", name, "conf", contents.as_deref()) {
+        pwvucontrol_warning!("Cannot write PipeWire config fragment: {e}");
+    }
+}
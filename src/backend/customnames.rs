@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use gtk::{gio, prelude::*};
+
+use crate::{backend::conffragment::{self, MonitorRule}, config::APP_ID, macros::*};
+
+/// Objects that can be given a custom description, keyed by `device.name` or `node.name`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum CustomNameKind {
+    Device,
+    Node,
+}
+
+impl CustomNameKind {
+    fn settings_key(&self) -> &'static str {
+        match self {
+            CustomNameKind::Device => "device-descriptions",
+            CustomNameKind::Node => "node-descriptions",
+        }
+    }
+
+    /// Monitor rule giving the object named `name` a custom description.
+    fn rule(&self, name: String, description: &str) -> MonitorRule {
+        let (match_key, description_key) = match self {
+            CustomNameKind::Device => ("device.name", "device.description"),
+            CustomNameKind::Node => ("node.name", "node.description"),
+        };
+        MonitorRule { match_key, name, props: vec![(description_key, conffragment::quote(description))] }
+    }
+}
+
+fn descriptions(settings: &gio::Settings, kind: CustomNameKind) -> BTreeMap<String, String> {
+    settings.get(kind.settings_key())
+}
+
+pub(crate) fn custom_description(kind: CustomNameKind, name: &str) -> Option<String> {
+    descriptions(&gio::Settings::new(APP_ID), kind).remove(name)
+}
+
+/// Description to create a node of one of our modules with. Module nodes do not come from a
+/// monitor, so instead of the monitor rules their module arguments carry the custom description.
+pub(crate) fn node_description(node_name: &str, description: String) -> String {
+    custom_description(CustomNameKind::Node, node_name).unwrap_or(description)
+}
+
+/// Stores a custom description and regenerates the WirePlumber fragments so the session manager
+/// applies it too. Passing `None` reverts to the original description.
+pub(crate) fn set_custom_description(kind: CustomNameKind, name: &str, description: Option<&str>) {
+    let settings = gio::Settings::new(APP_ID);
+
+    let mut map = descriptions(&settings, kind);
+    match description {
+        Some(description) => map.insert(name.to_string(), description.to_string()),
+        None => map.remove(name),
+    };
+
+    if let Err(e) = settings.set(kind.settings_key(), map) {
+        pwvucontrol_warning!("Cannot store {}: {e}", kind.settings_key());
+        return;
+    }
+
+    write_fragments(&settings);
+}
+
+fn write_fragments(settings: &gio::Settings) {
+    let rules: Vec<MonitorRule> = [CustomNameKind::Device, CustomNameKind::Node]
+        .into_iter()
+        .flat_map(|kind| descriptions(settings, kind).into_iter().map(move |(name, description)| kind.rule(name, &description)))
+        .collect();
+
+    conffragment::write_monitor_rules("names", &rules);
+}
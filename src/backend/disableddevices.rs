@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use gtk::{gio, prelude::*};
+
+use super::{PwDeviceObject, PwProfileObject, PwvucontrolManager};
+use crate::{
+    backend::conffragment::{self, MonitorRule},
+    config::APP_ID,
+    macros::*,
+};
+
+const KEY: &str = "disabled-devices";
+
+/// Disabled devices by `device.name`, with the description and profile index they had.
+pub(crate) fn disabled_devices() -> BTreeMap<String, (String, i32)> {
+    gio::Settings::new(APP_ID).get(KEY)
+}
+
+fn store(map: BTreeMap<String, (String, i32)>) {
+    let rules: Vec<MonitorRule> = map
+        .keys()
+        .map(|name| MonitorRule {
+            match_key: "device.name",
+            name: name.clone(),
+            props: vec![("device.disabled", "true".to_string())],
+        })
+        .collect();
+
+    if let Err(e) = gio::Settings::new(APP_ID).set(KEY, map) {
+        pwvucontrol_warning!("Cannot store {KEY}: {e}");
+        return;
+    }
+
+    conffragment::write_monitor_rules("disabled", &rules);
+}
+
+/// Re-enables a device. A device the session manager already skipped reappears after it restarts.
+pub(crate) fn enable_device(name: &str) {
+    let mut map = disabled_devices();
+    let Some((_, profile_index)) = map.remove(name) else {
+        return;
+    };
+    store(map);
+
+    let device = PwvucontrolManager::default()
+        .device_model()
+        .iter::<PwDeviceObject>()
+        .map_while(Result::ok)
+        .find(|device| device.device_name().as_deref() == Some(name));
+
+    if let Some(device) = device {
+        device.set_profile(profile_index);
+    }
+}
+
+impl PwDeviceObject {
+    /// Turns the device off by selecting its `off` profile and keeps the session manager
+    /// from creating it again.
+    pub(crate) fn disable(&self) {
+        let Some(name) = self.device_name() else {
+            return;
+        };
+
+        let mut map = disabled_devices();
+        map.insert(name, (self.name().unwrap_or_default(), self.profile_index() as i32));
+        store(map);
+
+        let off = self
+            .profilemodel()
+            .iter::<PwProfileObject>()
+            .map_while(Result::ok)
+            .find(|profile| profile.name() == "off");
+        if let Some(off) = off {
+            self.set_profile(off.index() as i32);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gettextrs::gettext;
+use gtk::{prelude::*, subclass::prelude::*};
+use wireplumber as wp;
+use wp::pw::PipewireObjectExt2;
+
+use super::{conffragment, NodeType, PwNodeObject, PwvucontrolManager};
+use crate::macros::*;
+
+/// Prefix of the names of all nodes created for monitoring sources.
+pub(crate) const NODE_PREFIX: &str = "pwvucontrol.loopback.";
+
+/// Latency of a new loopback in milliseconds.
+pub(crate) const DEFAULT_LATENCY: u32 = 50;
+
+/// A loopback playing a source through a sink, like "Listen to this device".
+/// It only runs while pwvucontrol does.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Loopback {
+    /// `node.name` of the monitored source.
+    pub source: String,
+    pub description: String,
+    /// `node.name` of the sink to play through, empty for the default sink.
+    pub sink: String,
+    /// Delay in milliseconds.
+    pub latency: u32,
+}
+
+impl Loopback {
+    pub(crate) fn new(source: &str, description: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            description: description.to_string(),
+            sink: String::new(),
+            latency: DEFAULT_LATENCY,
+        }
+    }
+
+    /// Name of the playback stream. The capture stream uses it as prefix.
+    pub(crate) fn node_name(&self) -> String {
+        format!("{NODE_PREFIX}{}", self.source)
+    }
+
+    /// Module arguments in SPA-JSON.
+    fn module_args(&self) -> String {
+        let name = self.node_name();
+        let description = conffragment::quote(&gettext("{} (Monitor)").replace("{}", &self.description));
+        let source = conffragment::quote(&self.source);
+        let sink = if self.sink.is_empty() {
+            String::new()
+        } else {
+            format!("target.object = {} node.dont-reconnect = true", conffragment::quote(&self.sink))
+        };
+
+        format!(
+            "{{ node.description = {description} media.name = {description} target.delay.sec = {:.3} \
+            capture.props = {{ node.name = \"{name}.capture\" target.object = {source} node.passive = true node.dont-reconnect = true }} \
+            playback.props = {{ node.name = \"{name}\" {sink} }} }}",
+            f64::from(self.latency) / 1000.0
+        )
+    }
+}
+
+/// Whether a node belongs to a loopback.
+pub(crate) fn is_loopback_node(node_name: &str) -> bool {
+    node_name.starts_with(NODE_PREFIX)
+}
+
+impl PwvucontrolManager {
+    /// The loopback monitoring a source.
+    pub(crate) fn loopback(&self, source: &str) -> Option<Loopback> {
+        self.imp().loopbacks.borrow().get(source).cloned()
+    }
+
+    /// Creates or replaces the loopback monitoring a source.
+    pub(crate) fn set_loopback(&self, loopback: Loopback) {
+        let node_name = loopback.node_name();
+        let old = self.imp().loopbacks.borrow_mut().insert(loopback.source.clone(), loopback.clone());
+        if old.is_some() {
+            self.unload_module(&node_name);
+        }
+
+        pwvucontrol_info!("Monitoring {} with {} ms latency", loopback.source, loopback.latency);
+        self.load_module(&node_name, "libpipewire-module-loopback", &loopback.module_args());
+    }
+
+    /// Stops monitoring a source.
+    pub(crate) fn remove_loopback(&self, source: &str) {
+        let Some(loopback) = self.imp().loopbacks.borrow_mut().remove(source) else {
+            return;
+        };
+
+        pwvucontrol_info!("Stopped monitoring {source}");
+        self.unload_module(&loopback.node_name());
+    }
+
+    /// The playback stream of the loopback monitoring a source, once it appeared.
+    pub(crate) fn loopback_node(&self, source: &str) -> Option<PwNodeObject> {
+        let node_name = self.loopback(source)?.node_name();
+        self.imp()
+            .node_model
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .filter(|node| node.nodetype() == NodeType::StreamOutput)
+            .find(|node| node.wpnode().pw_property::<String>("node.name").is_ok_and(|x| x == node_name))
+    }
+}
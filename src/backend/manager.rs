@@ -2,24 +2,59 @@
 
 use crate::macros::*;
 use crate::{
-    backend::NodeType, backend::PwDeviceObject, backend::PwNodeFilterModel, backend::PwNodeObject, ui::PwvucontrolWindow,
+    backend::MetadataExtFix, backend::NodeType, backend::PwDeviceObject, backend::PwNodeFilterModel, backend::PwNodeObject, backend::PwRuleObject,
+    backend::loopbacks::{self, Loopback}, backend::micfilters::MicFilter, backend::rules::{self, RuleTarget}, backend::{EventCategory, PwEventObject}, backend::{PwDucking, PwHistory, PwSleepTimer}, backend::RuleTrigger, ui::PwvucontrolWindow,
     ui::PwvucontrolWindowView, PwvucontrolApplication,
 };
+use gettextrs::gettext;
 use gtk::{
     gio,
-    glib::{self, clone, Properties},
+    glib::{self, clone, subclass::Signal, Properties},
     prelude::*,
     subclass::prelude::*,
 };
-use once_cell::unsync::OnceCell;
-use std::cell::RefCell;
+use once_cell::{sync::Lazy, unsync::OnceCell};
+use std::{cell::{Cell, RefCell}, collections::{BTreeMap, HashMap}};
 use wireplumber as wp;
 use wp::{
     plugin::{PluginFeatures, *},
-    pw::{MetadataExt, PipewireObjectExt2, ProxyExt},
+    pw::{GlobalProxyExt, MetadataExt, PipewireObjectExt2, ProxyExt},
     registry::{Constraint, ConstraintType, Interest, ObjectManager},
 };
 
+/// Number of entries the event log keeps.
+const MAX_EVENTS: u32 = 2000;
+
+/// Event log message for a node that appeared or went away, with `{}` for its name.
+fn node_event_message(nodetype: NodeType, added: bool) -> String {
+    match (nodetype, added) {
+        (NodeType::Sink, true) => gettext("Output device added: {}"),
+        (NodeType::Sink, false) => gettext("Output device removed: {}"),
+        (NodeType::Source, true) => gettext("Input device added: {}"),
+        (NodeType::Source, false) => gettext("Input device removed: {}"),
+        (NodeType::StreamOutput, true) => gettext("Playback stream added: {}"),
+        (NodeType::StreamOutput, false) => gettext("Playback stream removed: {}"),
+        (NodeType::StreamInput, true) => gettext("Recording stream added: {}"),
+        (NodeType::StreamInput, false) => gettext("Recording stream removed: {}"),
+        (NodeType::Undefined, true) => gettext("Node added: {}"),
+        (NodeType::Undefined, false) => gettext("Node removed: {}"),
+    }
+}
+
+/// Whether `name` is the node named `node_name` a module was loaded for, or one of its internal streams.
+pub(crate) fn is_module_node(name: &str, node_name: &str) -> bool {
+    name == node_name || name.strip_prefix(node_name).is_some_and(|suffix| matches!(suffix, ".capture" | ".playback"))
+}
+
+/// Media class the default nodes API knows sinks or sources by.
+fn default_node_type_name(nodetype: NodeType) -> &'static str {
+    match nodetype {
+        NodeType::Sink => "Audio/Sink",
+        NodeType::Source => "Audio/Source",
+        _ => unreachable!(),
+    }
+}
+
 mod imp {
     use super::*;
 
@@ -48,9 +83,40 @@ mod imp {
         #[property(get)]
         pub(crate) device_model: gio::ListStore,
 
+        #[property(get)]
+        pub(crate) rule_model: gio::ListStore,
+
+        /// Logged events, newest first.
+        #[property(get)]
+        pub(crate) event_model: gio::ListStore,
+        /// Undo and redo of changes made from the UI.
+        #[property(get)]
+        pub(crate) history: PwHistory,
+        #[property(get)]
+        pub(crate) sleep_timer: PwSleepTimer,
+        /// Lowers playback streams during calls.
+        #[property(get)]
+        pub(crate) ducking: PwDucking,
+        /// Last seen default sink and source ids, for logging changes.
+        pub(crate) default_node_ids: Cell<(u32, u32)>,
+        /// Rules only react to objects appearing after the initial enumeration.
+        pub(crate) rules_armed: Cell<bool>,
+        /// Device rules waiting for the sinks and sources of their device, by device id.
+        pub(crate) pending_device_rules: RefCell<Vec<(u32, PwRuleObject)>>,
+        /// Modules loaded into this process, by the name of the node they create.
+        pub(crate) loaded_modules: RefCell<HashMap<String, wp::local::ImplModule>>,
+        /// Loopbacks monitoring sources, by the `node.name` of the source.
+        pub(crate) loopbacks: RefCell<HashMap<String, Loopback>>,
+        /// Microphone filters by the `node.name` of the filtered source, loaded on first use.
+        pub(crate) mic_filters: RefCell<Option<BTreeMap<String, MicFilter>>>,
+        /// Smallest `node.latency` in seconds requested by the nodes of each driver, by driver id.
+        pub(crate) driver_latencies: RefCell<HashMap<u32, Option<f64>>>,
+
         pub metadata_om: OnceCell<wp::registry::ObjectManager>,
         #[property(get)]
         pub metadata: RefCell<Option<wp::pw::Metadata>>,
+        #[property(get)]
+        pub settings_metadata: RefCell<Option<wp::pw::Metadata>>,
 
         #[property(get)]
         pub default_nodes_api: OnceCell<Plugin>,
@@ -72,8 +138,21 @@ mod imp {
                 source_model: PwNodeFilterModel::new(NodeType::Source, None::<gio::ListModel>),
                 sink_model: PwNodeFilterModel::new(NodeType::Sink, None::<gio::ListModel>),
                 device_model: gio::ListStore::new::<PwDeviceObject>(),
+                rule_model: gio::ListStore::new::<PwRuleObject>(),
+                event_model: gio::ListStore::new::<PwEventObject>(),
+                history: PwHistory::new(),
+                sleep_timer: PwSleepTimer::new(),
+                ducking: PwDucking::new(),
+                default_node_ids: Cell::new((u32::MAX, u32::MAX)),
+                rules_armed: Default::default(),
+                pending_device_rules: Default::default(),
+                loaded_modules: Default::default(),
+                loopbacks: Default::default(),
+                mic_filters: Default::default(),
+                driver_latencies: Default::default(),
                 metadata_om: Default::default(),
                 metadata: Default::default(),
+                settings_metadata: Default::default(),
                 default_nodes_api: Default::default(),
                 mixer_api: Default::default(),
                 application: Default::default(),
@@ -89,6 +168,19 @@ mod imp {
 
     #[glib::derived_properties]
     impl ObjectImpl for PwvucontrolManager {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+                vec![
+                    // A key of the default metadata changed, such as a stream target or a default node.
+                    Signal::builder("metadata-changed").param_types([u32::static_type(), String::static_type()]).build(),
+                    // A node was given a custom description, or got its original one back. Passes its `node.name`.
+                    Signal::builder("node-renamed").param_types([String::static_type()]).build(),
+                ]
+            });
+
+            SIGNALS.as_ref()
+        }
+
         fn constructed(&self) {
             self.parent_constructed();
 
@@ -97,6 +189,11 @@ mod imp {
             self.sink_model.set_model(Some(self.node_model.clone()));
             self.source_model.set_model(Some(self.node_model.clone()));
 
+            self.rule_model.extend_from_slice(&rules::load_rules());
+            self.ducking.watch(&self.node_model);
+            self.obj().watch_mic_filter_names();
+            self.obj().watch_network_session_names();
+
             self.setup_wp_connection();
             self.setup_metadata_om();
         }
@@ -179,6 +276,16 @@ mod imp {
                         hidden = true;
                     }
 
+                    // Hide the A/V sync calibration clicks.
+                    if node.name().unwrap_or_default() == "pwvucontrol-clap-test" {
+                        hidden = true;
+                    }
+
+                    // Hide the speaker test signals.
+                    if node.name().unwrap_or_default() == "pwvucontrol-speaker-test" {
+                        hidden = true;
+                    }
+
                     // Hide any playback from pavucontrol (mainly volume control notification sound).
                     if node.name().unwrap_or_default() == "pavucontrol" {
                         hidden = true;
@@ -199,26 +306,64 @@ mod imp {
                         hidden = true;
                     }
 
+                    // Hide the internal streams of microphone filters.
+                    if node.pw_property::<String>("media.class").is_ok_and(|x| x.starts_with("Stream/"))
+                        && node.pw_property::<String>("node.name").is_ok_and(|x| imp.obj().is_mic_filter_node(&x)) {
+                        hidden = true;
+                    }
+
+                    // Hide the streams of loopbacks monitoring sources, they are controlled from the source.
+                    if node.pw_property::<String>("node.name").is_ok_and(|x| loopbacks::is_loopback_node(&x)) {
+                        hidden = true;
+                    }
+
                     pwvucontrol_info!("Got node: {} bound id {}", node.name().unwrap_or_default(), node.bound_id());
                     let pwobj = PwNodeObject::new(node);
                     pwobj.set_hidden(hidden);
                     let model = &imp.node_model;
                     model.append(&pwobj);
+
+                    if !hidden {
+                        imp.obj().log_event(EventCategory::Node, &node_event_message(pwobj.nodetype(), true).replace("{}", &pwobj.name()));
+                        imp.obj().apply_rules(RuleTrigger::NodeAdded, RuleTarget::Node(&pwobj));
+                        imp.obj().apply_pending_device_rules(&pwobj);
+                    }
                 } else if let Some(device) = object.dynamic_cast_ref::<wp::pw::Device>() {
                     let n: String = device.pw_property("device.name").unwrap();
                     pwvucontrol_info!("Got device: {n} bound id {}", device.bound_id());
-                    devicemodel.append(&PwDeviceObject::new(device));
+                    let pwobj = PwDeviceObject::new(device);
+                    devicemodel.append(&pwobj);
+
+                    imp.obj().log_event(EventCategory::Device, &gettext("Device added: {}").replace("{}", &pwobj.name().unwrap_or(n)));
+                    if imp.rules_armed.get() {
+                        PwvucontrolApplication::default().notify_device_added(&pwobj);
+                    }
+
+                    imp.obj().apply_rules(RuleTrigger::DeviceAdded, RuleTarget::Device(&pwobj));
                 } else {
                     unreachable!("Object must be one of the above, but is {:?} instead", object.type_());
                 }
             }));
 
+            wp_om.connect_local("installed", false, clone!(@weak self as imp => @default-return None, move |_| {
+                imp.rules_armed.set(true);
+                imp.obj().load_mic_filters();
+                imp.obj().load_network_sessions();
+
+                None
+            }));
+
             wp_om.connect_object_removed(clone!(@weak self as imp => move |_, object| {
                 if let Some(node) = object.dynamic_cast_ref::<wp::pw::Node>() {
                     pwvucontrol_info!("removed: {:?} id: {}", node.name(), node.bound_id());
                     imp.obj().remove_node_by_id(node.bound_id());
+                    // A loopback does not follow its source to another one.
+                    if let Ok(node_name) = node.pw_property::<String>("node.name") {
+                        imp.obj().remove_loopback(&node_name);
+                    }
                 } else if let Some(device) = object.dynamic_cast_ref::<wp::pw::Device>() {
                     imp.obj().remove_device_by_id(device.bound_id());
+                    imp.obj().drop_pending_device_rules(device.bound_id());
                 } else {
                     pwvucontrol_info!("Object must be one of the above, but is {:?} instead", object.type_());
                 }
@@ -238,6 +383,7 @@ mod imp {
                             pwvucontrol_info!("Activated plugin {plugin_name}");
                             count += 1;
                             if count == plugin_names.len() {
+                                manager.obj().setup_default_nodes_log();
                                 core.install_object_manager(&om);
                             }
                         }
@@ -261,18 +407,42 @@ mod imp {
 
             let wp_core = self.wp_core.get().expect("wp_core to be set");
 
-            metadata_om.add_interest(
-                [Constraint::compare(ConstraintType::PwGlobalProperty, "metadata.name", "default", true)]
-                    .iter()
-                    .collect::<Interest<wp::pw::Metadata>>(),
-            );
+            for name in ["default", "settings"] {
+                metadata_om.add_interest(
+                    [Constraint::compare(ConstraintType::PwGlobalProperty, "metadata.name", name, true)]
+                        .iter()
+                        .collect::<Interest<wp::pw::Metadata>>(),
+                );
+            }
 
             metadata_om.request_object_features(wp::pw::GlobalProxy::static_type(), wp::core::ObjectFeatures::ALL);
 
             metadata_om.connect_object_added(clone!(@weak self as imp, @weak wp_core as core => move |_, object| {
                 if let Some(metadataobj) = object.dynamic_cast_ref::<wp::pw::Metadata>() {
-                    pwvucontrol_info!("added metadata object: {:?}", metadataobj.bound_id());
-                    imp.metadata.replace(Some(metadataobj.clone()));
+                    let name = metadataobj
+                        .global_properties()
+                        .and_then(|props| props.get("metadata.name"))
+                        .unwrap_or_default();
+                    pwvucontrol_info!("added metadata object: {name} {:?}", metadataobj.bound_id());
+                    match name.as_str() {
+                        "settings" => {
+                            imp.settings_metadata.replace(Some(metadataobj.clone()));
+                            imp.obj().notify_settings_metadata();
+                        },
+                        _ => {
+                            imp.metadata.replace(Some(metadataobj.clone()));
+                        },
+                    }
+                    if name == "default" {
+                        metadataobj.connect_local("changed", false, clone!(@weak imp => @default-return None, move |args| {
+                            let subject = args[1].get::<u32>().unwrap_or_default();
+                            let key = args[2].get::<Option<String>>().ok().flatten().unwrap_or_default();
+                            let value = args[4].get::<Option<String>>().ok().flatten();
+                            imp.obj().log_target_change(subject, &key, value.as_deref());
+                            imp.obj().emit_by_name::<()>("metadata-changed", &[&subject, &key]);
+                            None
+                        }));
+                    }
                     for a in metadataobj.new_iterator(u32::MAX).expect("iterator") {
                         let (s, k, t, v) = wp::pw::Metadata::iterator_item_extract(&a);
                         pwvucontrol_info!("Metadata value: {s}, {k:?}, {t:?}, {v:?}");
@@ -321,6 +491,7 @@ impl PwvucontrolManager {
         for (i, item) in (0..).zip(devicemodel.iter::<PwDeviceObject>()) {
             if let Ok(item) = item {
                 if item.wpdevice().bound_id() == id {
+                    self.log_event(EventCategory::Device, &gettext("Device removed: {}").replace("{}", &item.name().unwrap_or_default()));
                     devicemodel.remove(i);
                     break;
                 }
@@ -350,6 +521,15 @@ impl PwvucontrolManager {
         for (i, item) in (0..).zip(nodemodel.iter::<PwNodeObject>()) {
             if let Ok(item) = item {
                 if item.wpnode().bound_id() == id {
+                    if !item.hidden() {
+                        self.log_event(EventCategory::Node, &node_event_message(item.nodetype(), false).replace("{}", &item.name()));
+                    }
+                    if matches!(item.nodetype(), NodeType::Sink | NodeType::Source) {
+                        PwvucontrolApplication::default().notify_streams_moved(&item);
+                    }
+                    if let Some(driver) = item.driver_id() {
+                        self.forget_driver_latency(driver);
+                    }
                     nodemodel.remove(i);
                     break;
                 }
@@ -359,6 +539,201 @@ impl PwvucontrolManager {
         }
     }
 
+    pub(crate) fn get_nodes_for_device(&self, device: &PwDeviceObject) -> Vec<PwNodeObject> {
+        let device_id = device.wpdevice().bound_id();
+
+        self.imp()
+            .node_model
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .filter(|node| matches!(node.wpnode().device_id(), Ok(Some(id)) if id == device_id))
+            .collect()
+    }
+
+    /// The configured default sink or source, empty when unset.
+    pub(crate) fn default_configured_node_name(&self, nodetype: NodeType) -> String {
+        let type_name = default_node_type_name(nodetype);
+        let name: Option<String> = self.default_nodes_api().emit_by_name("get-default-configured-node-name", &[&type_name]);
+        name.unwrap_or_default()
+    }
+
+    /// Sets the configured default sink or source. An empty name unsets it.
+    pub(crate) fn set_default_configured_node_name(&self, nodetype: NodeType, node_name: &str) {
+        let type_name = default_node_type_name(nodetype);
+
+        let defaultnodesapi = self.default_nodes_api();
+        let result: bool = defaultnodesapi.emit_by_name("set-default-configured-node-name", &[&type_name, &node_name]);
+        wp::info!("set-default-configured-node-name result: {result:?}");
+    }
+
+    /// Loads a PipeWire module into this process. It runs until the app quits, after that the daemon
+    /// takes over if the module was also written to its config. `node_name` identifies the module later.
+    /// Returns whether the module loaded.
+    pub(crate) fn load_module(&self, node_name: &str, module_name: &str, args: &str) -> bool {
+        match wp::local::ImplModule::load(&self.wp_core(), module_name, Some(args), None) {
+            Some(module) => {
+                self.imp().loaded_modules.borrow_mut().insert(node_name.to_string(), module);
+                true
+            },
+            None => {
+                pwvucontrol_warning!("Cannot load {module_name} for {node_name}");
+                false
+            },
+        }
+    }
+
+    /// Unloads a module loaded with `load_module` and destroys its nodes, see `is_module_node`.
+    pub(crate) fn unload_module(&self, node_name: &str) {
+        // Dropping the module unloads it, if it runs in this process.
+        self.imp().loaded_modules.borrow_mut().remove(node_name);
+
+        // Modules loaded by the daemon unload themselves once their streams are gone.
+        for node in self.imp().node_model.iter::<PwNodeObject>().map_while(Result::ok) {
+            if node.wpnode().pw_property::<String>("node.name").is_ok_and(|x| is_module_node(&x, node_name)) {
+                node.wpnode().request_destroy();
+            }
+        }
+    }
+
+    /// Finds the node with the `node.name` property `node_name`.
+    pub(crate) fn node_by_name(&self, node_name: &str) -> Option<PwNodeObject> {
+        self.imp()
+            .node_model
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .find(|node| node.wpnode().pw_property::<String>("node.name").is_ok_and(|x| x == node_name))
+    }
+
+    /// Whether a node named `node_name` exists.
+    pub(crate) fn has_node_named(&self, node_name: &str) -> bool {
+        self.node_by_name(node_name).is_some()
+    }
+
+    /// Smallest `node.latency` in seconds requested by the nodes `driver` drives. Cached until
+    /// `forget_driver_latency` is called for the driver.
+    pub(crate) fn driver_latency(&self, driver: u32) -> Option<f64> {
+        if let Some(latency) = self.imp().driver_latencies.borrow().get(&driver) {
+            return *latency;
+        }
+
+        let latency = self
+            .imp()
+            .node_model
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .filter(|node| node.driver_id() == Some(driver))
+            .filter_map(|node| node.node_latency())
+            .reduce(f64::min);
+        self.imp().driver_latencies.borrow_mut().insert(driver, latency);
+        latency
+    }
+
+    /// Drops the cached latency of `driver`, when a node it drives changed or went away.
+    pub(crate) fn forget_driver_latency(&self, driver: u32) {
+        self.imp().driver_latencies.borrow_mut().remove(&driver);
+    }
+
+    /// Adds an entry to the event log, dropping the oldest ones beyond `MAX_EVENTS`.
+    pub(crate) fn log_event(&self, category: EventCategory, message: &str) {
+        let model = &self.imp().event_model;
+        model.insert(0, &PwEventObject::new(category, message));
+        if model.n_items() > MAX_EVENTS {
+            model.splice(MAX_EVENTS, model.n_items() - MAX_EVENTS, &[] as &[glib::Object]);
+        }
+    }
+
+    fn setup_default_nodes_log(&self) {
+        let defaultnodesapi = self.default_nodes_api();
+        defaultnodesapi.connect_local("changed", false, clone!(@weak self as manager => @default-return None, move |_| {
+            manager.log_default_nodes();
+            None
+        }));
+        self.log_default_nodes();
+    }
+
+    /// Bound id of the sink or source currently used by default, `u32::MAX` if there is none.
+    pub(crate) fn default_node_id(&self, nodetype: NodeType) -> u32 {
+        let media_class = match nodetype {
+            NodeType::Sink => "Audio/Sink",
+            _ => "Audio/Source",
+        };
+        self.default_nodes_api().emit_by_name("get-default-node", &[&media_class])
+    }
+
+    fn log_default_nodes(&self) {
+        let sink = self.default_node_id(NodeType::Sink);
+        let source = self.default_node_id(NodeType::Source);
+
+        let (old_sink, old_source) = self.imp().default_node_ids.replace((sink, source));
+        let name = |id: u32| self.get_node_by_id(id).map(|node| node.name()).unwrap_or_else(|| id.to_string());
+
+        let application = PwvucontrolApplication::default();
+        for (nodetype, old, new) in [(NodeType::Sink, old_sink, sink), (NodeType::Source, old_source, source)] {
+            if old == u32::MAX || old == new {
+                continue;
+            }
+            let message = match nodetype {
+                NodeType::Sink => gettext("Default output changed to {}"),
+                _ => gettext("Default input changed to {}"),
+            }
+            .replace("{}", &name(new));
+            self.log_event(EventCategory::Default, &message);
+            if let Some(node) = self.get_node_by_id(new) {
+                application.notify_default_changed(nodetype, self.get_node_by_id(old).as_ref(), &node);
+            }
+        }
+    }
+
+    fn log_target_change(&self, subject: u32, key: &str, value: Option<&str>) {
+        if !matches!(key, "target.object" | "target.node") {
+            return;
+        }
+
+        let stream = self.get_node_by_id(subject).map(|node| node.name()).unwrap_or_else(|| subject.to_string());
+        let message = match value {
+            Some(target) => {
+                let target = target
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(|id| self.get_node_by_id(id))
+                    .map(|node| node.name())
+                    .unwrap_or_else(|| target.to_string());
+                gettext("{stream} moved to {target}").replace("{stream}", &stream).replace("{target}", &target)
+            },
+            None => gettext("{} follows the default again").replace("{}", &stream),
+        };
+        self.log_event(EventCategory::Target, &message);
+    }
+
+    /// Looks up a global setting such as `clock.rate` in the `settings` metadata.
+    pub(crate) fn setting(&self, key: &str) -> Option<String> {
+        let metadata = self.settings_metadata()?;
+        metadata.find_notype(0, key).map(|value| value.to_string())
+    }
+
+    /// Sets a global setting in the `settings` metadata. Passing `None` removes the key.
+    pub(crate) fn set_setting(&self, key: &str, value: Option<&str>) {
+        if let Some(metadata) = self.settings_metadata() {
+            metadata.set(0, Some(key), None, value);
+        } else {
+            pwvucontrol_warning!("Cannot get settings metadata object");
+        }
+    }
+
+    /// Puts back the volumes lowered on our behalf before the app quits, so they are not left behind.
+    pub(crate) fn shutdown(&self) {
+        self.ducking().stop();
+        self.sleep_timer().cancel();
+
+        // Let the volume changes reach the daemon while the connection is still up.
+        let context = glib::MainContext::default();
+        for _ in 0..100 {
+            if !context.iteration(false) {
+                break;
+            }
+        }
+    }
+
     pub fn get_model_for_nodetype(&self, nodetype: NodeType) -> PwNodeFilterModel {
         match nodetype {
             NodeType::Sink => self.sink_model(),
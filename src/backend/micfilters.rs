@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use gettextrs::gettext;
+use gtk::{gio, prelude::*};
+
+use super::{conffragment, customnames, manager, NodeType, PwvucontrolManager};
+use crate::{config::APP_ID, macros::*};
+
+const KEY: &str = "mic-filters";
+
+/// LADSPA plugin doing the noise suppression.
+const RNNOISE_PLUGIN: &str = "librnnoise_ladspa.so";
+
+/// Prefix of the names of all nodes created for microphone filters.
+pub(crate) const NODE_PREFIX: &str = "pwvucontrol.filter.";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum MicFilterKind {
+    EchoCancel,
+    NoiseSuppression,
+}
+
+impl MicFilterKind {
+    pub(crate) fn key(&self) -> &'static str {
+        match self {
+            MicFilterKind::EchoCancel => "echo-cancel",
+            MicFilterKind::NoiseSuppression => "noise-suppression",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "echo-cancel" => Some(MicFilterKind::EchoCancel),
+            "noise-suppression" => Some(MicFilterKind::NoiseSuppression),
+            _ => None,
+        }
+    }
+
+    /// Whether what the filter needs is installed.
+    pub(crate) fn is_available(&self) -> bool {
+        match self {
+            MicFilterKind::EchoCancel => true,
+            MicFilterKind::NoiseSuppression => ladspa_dirs().iter().any(|dir| dir.join(RNNOISE_PLUGIN).is_file()),
+        }
+    }
+
+    pub(crate) fn title(&self) -> String {
+        match self {
+            MicFilterKind::EchoCancel => gettext("Echo Cancelled"),
+            MicFilterKind::NoiseSuppression => gettext("Noise Suppressed"),
+        }
+    }
+}
+
+/// An echo-cancel node or noise suppression filter-chain in front of a source.
+#[derive(Debug, Clone)]
+pub(crate) struct MicFilter {
+    pub kind: MicFilterKind,
+    /// `node.name` of the filtered source.
+    pub source: String,
+    pub description: String,
+    pub gain_control: bool,
+    pub noise_suppression: bool,
+    pub high_pass_filter: bool,
+    /// Voice activity threshold of rnnoise in percent.
+    pub vad_threshold: f64,
+}
+
+impl MicFilter {
+    pub(crate) fn new(kind: MicFilterKind, source: &str, description: &str) -> Self {
+        Self {
+            kind,
+            source: source.to_string(),
+            description: description.to_string(),
+            gain_control: true,
+            noise_suppression: true,
+            high_pass_filter: true,
+            vad_threshold: 50.0,
+        }
+    }
+
+    /// Name of the virtual source. Internal streams use it as prefix.
+    pub(crate) fn node_name(&self) -> String {
+        format!("{NODE_PREFIX}{}.{}", self.kind.key(), self.source)
+    }
+
+    fn module_name(&self) -> &'static str {
+        match self.kind {
+            MicFilterKind::EchoCancel => "libpipewire-module-echo-cancel",
+            MicFilterKind::NoiseSuppression => "libpipewire-module-filter-chain",
+        }
+    }
+
+    /// Module arguments in SPA-JSON.
+    fn module_args(&self) -> String {
+        let name = self.node_name();
+        let description = customnames::node_description(&name, format!("{} ({})", self.description, self.kind.title()));
+        let description = conffragment::quote(&description);
+        let target = conffragment::quote(&self.source);
+
+        match self.kind {
+            MicFilterKind::EchoCancel => format!(
+                "{{ library.name = aec/libspa-aec-webrtc monitor.mode = true \
+                aec.args = {{ webrtc.gain_control = {} webrtc.noise_suppression = {} webrtc.high_pass_filter = {} }} \
+                capture.props = {{ node.name = \"{name}.capture\" target.object = {target} node.passive = true }} \
+                source.props = {{ node.name = \"{name}\" node.description = {description} }} }}",
+                self.gain_control, self.noise_suppression, self.high_pass_filter
+            ),
+            MicFilterKind::NoiseSuppression => format!(
+                "{{ node.description = {description} media.name = {description} \
+                filter.graph = {{ nodes = [ {{ type = ladspa name = rnnoise plugin = librnnoise_ladspa label = noise_suppressor_mono \
+                control = {{ \"VAD Threshold (%)\" = {:.1} }} }} ] }} \
+                capture.props = {{ node.name = \"{name}.capture\" target.object = {target} node.passive = true audio.rate = 48000 }} \
+                playback.props = {{ node.name = \"{name}\" media.class = Audio/Source audio.rate = 48000 }} }}",
+                self.vad_threshold
+            ),
+        }
+    }
+}
+
+/// Directories PipeWire looks for LADSPA plugins in: `LADSPA_PATH`, or the usual library directories.
+fn ladspa_dirs() -> Vec<PathBuf> {
+    if let Some(path) = std::env::var_os("LADSPA_PATH") {
+        return std::env::split_paths(&path).collect();
+    }
+
+    let mut dirs: Vec<PathBuf> = ["/usr/lib/ladspa", "/usr/lib64/ladspa", "/usr/local/lib/ladspa", "/usr/local/lib64/ladspa"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    // Multiarch directories like /usr/lib/x86_64-linux-gnu/ladspa.
+    if let Ok(entries) = std::fs::read_dir("/usr/lib") {
+        dirs.extend(
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_name().to_string_lossy().ends_with("-linux-gnu"))
+                .map(|entry| entry.path().join("ladspa")),
+        );
+    }
+    dirs
+}
+
+/// Filters by the `node.name` of the filtered source, as stored in the settings.
+fn load() -> BTreeMap<String, MicFilter> {
+    let stored: BTreeMap<String, (String, String, bool, bool, bool, f64)> = gio::Settings::new(APP_ID).get(KEY);
+
+    stored
+        .into_iter()
+        .filter_map(|(source, (kind, description, gain_control, noise_suppression, high_pass_filter, vad_threshold))| {
+            let filter = MicFilter {
+                kind: MicFilterKind::from_key(&kind)?,
+                source: source.clone(),
+                description,
+                gain_control,
+                noise_suppression,
+                high_pass_filter,
+                vad_threshold,
+            };
+            Some((source, filter))
+        })
+        .collect()
+}
+
+/// Stores the filters and writes them as config fragment, so the daemon loads them from now on.
+fn store(filters: &BTreeMap<String, MicFilter>) {
+    let stored: BTreeMap<String, (String, String, bool, bool, bool, f64)> = filters
+        .iter()
+        .map(|(source, filter)| {
+            let value = (
+                filter.kind.key().to_string(),
+                filter.description.clone(),
+                filter.gain_control,
+                filter.noise_suppression,
+                filter.high_pass_filter,
+                filter.vad_threshold,
+            );
+            (source.clone(), value)
+        })
+        .collect();
+
+    if let Err(e) = gio::Settings::new(APP_ID).set(KEY, stored) {
+        pwvucontrol_warning!("Cannot store {KEY}: {e}");
+        return;
+    }
+
+    write_fragment(filters);
+}
+
+/// Writes the filters as PipeWire config fragment.
+fn write_fragment(filters: &BTreeMap<String, MicFilter>) {
+    let modules: Vec<(&str, String)> = filters.values().map(|filter| (filter.module_name(), filter.module_args())).collect();
+    conffragment::write_context_modules("filters", &modules);
+}
+
+impl PwvucontrolManager {
+    /// Filters by the `node.name` of the filtered source. Read from the settings once, then kept up to date.
+    pub(crate) fn mic_filters(&self) -> BTreeMap<String, MicFilter> {
+        self.imp().mic_filters.borrow_mut().get_or_insert_with(load).clone()
+    }
+
+    /// Whether a node belongs to a microphone filter.
+    pub(crate) fn is_mic_filter_node(&self, node_name: &str) -> bool {
+        node_name.starts_with(NODE_PREFIX)
+            && self
+                .imp()
+                .mic_filters
+                .borrow_mut()
+                .get_or_insert_with(load)
+                .values()
+                .any(|filter| manager::is_module_node(node_name, &filter.node_name()))
+    }
+
+    /// Filter nodes take their description from the module arguments, so the fragment is written
+    /// again when one of them is renamed.
+    pub(crate) fn watch_mic_filter_names(&self) {
+        self.connect_local("node-renamed", false, |values| {
+            let manager: PwvucontrolManager = values[0].get().expect("manager");
+            let node_name: String = values[1].get().expect("node name");
+            if manager.is_mic_filter_node(&node_name) {
+                write_fragment(&manager.mic_filters());
+            }
+            None
+        });
+    }
+
+    fn store_mic_filters(&self, filters: &BTreeMap<String, MicFilter>) {
+        store(filters);
+        self.imp().mic_filters.replace(Some(filters.clone()));
+    }
+
+    /// Creates or replaces the filter in front of a source, optionally making it the default source.
+    /// When the filter cannot be loaded, the previous one is put back.
+    pub(crate) fn set_mic_filter(&self, filter: MicFilter, make_default: bool) {
+        let previous = self.mic_filters();
+        let mut filters = previous.clone();
+        let old = filters.remove(&filter.source);
+        if let Some(old) = &old {
+            self.unload_mic_filter(old);
+        }
+
+        filters.insert(filter.source.clone(), filter.clone());
+        self.store_mic_filters(&filters);
+
+        if !self.load_mic_filter(&filter) {
+            pwvucontrol_warning!("Keeping the previous filter of {}", filter.source);
+            self.store_mic_filters(&previous);
+            if let Some(old) = &old {
+                self.load_mic_filter(old);
+            }
+            return;
+        }
+
+        if make_default {
+            self.set_default_configured_node_name(NodeType::Source, &filter.node_name());
+        }
+    }
+
+    /// Tears down the filter in front of a source.
+    pub(crate) fn remove_mic_filter(&self, source: &str) {
+        let mut filters = self.mic_filters();
+        let Some(filter) = filters.remove(source) else {
+            return;
+        };
+        self.store_mic_filters(&filters);
+
+        self.unload_mic_filter(&filter);
+    }
+
+    /// Loads filters the daemon does not run yet, because it was not restarted since they were set up.
+    pub(crate) fn load_mic_filters(&self) {
+        for filter in self.mic_filters().values() {
+            if !self.has_node_named(&filter.node_name()) {
+                self.load_mic_filter(filter);
+            }
+        }
+    }
+
+    fn load_mic_filter(&self, filter: &MicFilter) -> bool {
+        self.load_module(&filter.node_name(), filter.module_name(), &filter.module_args())
+    }
+
+    fn unload_mic_filter(&self, filter: &MicFilter) {
+        self.unload_module(&filter.node_name());
+    }
+}
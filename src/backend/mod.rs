@@ -1,21 +1,55 @@
 mod paramavailability;
+mod conffragment;
+mod customnames;
+pub(crate) mod micfilters;
+pub(crate) mod loopbacks;
+pub(crate) mod networksessions;
+pub(crate) mod disableddevices;
 mod pwchannelobject;
 mod manager;
 mod pwdeviceobject;
 mod pwprofileobject;
+mod profilegroup;
 mod pwnodeobject;
 mod pwrouteobject;
 mod routedirection;
 mod pwroutefiltermodel;
 mod pwnodefiltermodel;
+mod pwnodesearchfilter;
+mod pwappgroupobject;
+mod pwappgroupmodel;
+mod pwformatobject;
+mod pwpropertyobject;
+mod pwpropinfoobject;
+mod pweventobject;
+mod pwruleobject;
+mod pwhistory;
+mod pwsleeptimer;
+mod pwducking;
+pub(crate) mod rules;
 
 pub use paramavailability::ParamAvailability;
+pub(crate) use customnames::CustomNameKind;
 pub use pwchannelobject::PwChannelObject;
 pub use manager::PwvucontrolManager;
 pub use pwdeviceobject::PwDeviceObject;
 pub use pwprofileobject::PwProfileObject;
-pub use pwnodeobject::{PwNodeObject, NodeType};
+pub use profilegroup::ProfileGroup;
+pub use pwnodeobject::{PwNodeObject, NodeType, MetadataExtFix};
 pub use pwrouteobject::PwRouteObject;
 pub use routedirection::RouteDirection;
 pub use pwroutefiltermodel::PwRouteFilterModel;
 pub use pwnodefiltermodel::PwNodeFilterModel;
+pub use pwnodesearchfilter::PwNodeSearchFilter;
+pub use pwappgroupobject::PwAppGroupObject;
+pub use pwappgroupmodel::PwAppGroupModel;
+pub use pwformatobject::PwFormatObject;
+pub(crate) use pwformatobject::channel_names;
+pub use pwpropertyobject::PwPropertyObject;
+pub use pwpropinfoobject::{PwPropInfoObject, PropValueType};
+pub use pweventobject::{PwEventObject, EventCategory};
+pub use pwruleobject::{PwRuleObject, RuleTrigger, RuleAction};
+pub use pwhistory::PwHistory;
+pub(crate) use pwhistory::{HistoryChange, NodeControl};
+pub use pwsleeptimer::PwSleepTimer;
+pub use pwducking::PwDucking;
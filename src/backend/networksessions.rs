@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use gettextrs::gettext;
+use gtk::{gio, prelude::*};
+
+use super::{conffragment, customnames, PwvucontrolManager};
+use crate::{config::APP_ID, macros::*};
+
+const KEY: &str = "network-sessions";
+
+/// Prefix of the names of nodes created for network sessions.
+pub(crate) const NODE_PREFIX: &str = "pwvucontrol.network.";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum NetworkSessionKind {
+    RtpSend,
+    RtpReceive,
+    RocSend,
+    RocReceive,
+    TunnelSink,
+    TunnelSource,
+}
+
+impl NetworkSessionKind {
+    pub(crate) const ALL: [NetworkSessionKind; 6] = [
+        NetworkSessionKind::RtpSend,
+        NetworkSessionKind::RtpReceive,
+        NetworkSessionKind::RocSend,
+        NetworkSessionKind::RocReceive,
+        NetworkSessionKind::TunnelSink,
+        NetworkSessionKind::TunnelSource,
+    ];
+
+    fn key(&self) -> &'static str {
+        match self {
+            NetworkSessionKind::RtpSend => "rtp-send",
+            NetworkSessionKind::RtpReceive => "rtp-receive",
+            NetworkSessionKind::RocSend => "roc-send",
+            NetworkSessionKind::RocReceive => "roc-receive",
+            NetworkSessionKind::TunnelSink => "tunnel-sink",
+            NetworkSessionKind::TunnelSource => "tunnel-source",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+
+    pub(crate) fn title(&self) -> String {
+        match self {
+            NetworkSessionKind::RtpSend => gettext("RTP sender (output)"),
+            NetworkSessionKind::RtpReceive => gettext("RTP receiver (input)"),
+            NetworkSessionKind::RocSend => gettext("ROC sender (output)"),
+            NetworkSessionKind::RocReceive => gettext("ROC receiver (input)"),
+            NetworkSessionKind::TunnelSink => gettext("PulseAudio tunnel output"),
+            NetworkSessionKind::TunnelSource => gettext("PulseAudio tunnel input"),
+        }
+    }
+
+    /// Whether the address is the remote end. Receivers listen on a local address instead.
+    pub(crate) fn is_sender(&self) -> bool {
+        matches!(self, NetworkSessionKind::RtpSend | NetworkSessionKind::RocSend | NetworkSessionKind::TunnelSink)
+    }
+
+    pub(crate) fn default_port(&self) -> u32 {
+        match self {
+            NetworkSessionKind::RtpSend | NetworkSessionKind::RtpReceive => 46000,
+            NetworkSessionKind::RocSend | NetworkSessionKind::RocReceive => 10001,
+            NetworkSessionKind::TunnelSink | NetworkSessionKind::TunnelSource => 4713,
+        }
+    }
+
+    fn module_name(&self) -> &'static str {
+        match self {
+            NetworkSessionKind::RtpSend => "libpipewire-module-rtp-sink",
+            NetworkSessionKind::RtpReceive => "libpipewire-module-rtp-source",
+            NetworkSessionKind::RocSend => "libpipewire-module-roc-sink",
+            NetworkSessionKind::RocReceive => "libpipewire-module-roc-source",
+            NetworkSessionKind::TunnelSink | NetworkSessionKind::TunnelSource => "libpipewire-module-pulse-tunnel",
+        }
+    }
+}
+
+/// A network sink or source backed by a PipeWire module.
+#[derive(Debug, Clone)]
+pub(crate) struct NetworkSession {
+    pub id: String,
+    pub kind: NetworkSessionKind,
+    pub label: String,
+    pub address: String,
+    pub port: u32,
+}
+
+impl NetworkSession {
+    pub(crate) fn new(kind: NetworkSessionKind, label: &str, address: &str, port: u32) -> Self {
+        let id: String = glib::uuid_string_random().chars().take(8).collect();
+
+        Self {
+            id,
+            kind,
+            label: label.to_string(),
+            address: address.to_string(),
+            port,
+        }
+    }
+
+    pub(crate) fn node_name(&self) -> String {
+        format!("{NODE_PREFIX}{}", self.id)
+    }
+
+    /// Module arguments in SPA-JSON.
+    fn module_args(&self) -> String {
+        let name = self.node_name();
+        let description = conffragment::quote(&customnames::node_description(&name, self.label.clone()));
+        let address = conffragment::quote(&self.address);
+        let port = self.port;
+
+        match self.kind {
+            NetworkSessionKind::RtpSend => format!(
+                "{{ destination.ip = {address} destination.port = {port} sess.name = {description} \
+                stream.props = {{ node.name = \"{name}\" node.description = {description} media.class = Audio/Sink }} }}"
+            ),
+            NetworkSessionKind::RtpReceive => format!(
+                "{{ source.ip = {address} source.port = {port} sess.ignore-ssrc = true \
+                stream.props = {{ node.name = \"{name}\" node.description = {description} media.class = Audio/Source }} }}"
+            ),
+            NetworkSessionKind::RocSend => format!(
+                "{{ fec.code = rs8m remote.ip = {address} remote.source.port = {port} remote.repair.port = {} \
+                sink.name = {description} sink.props = {{ node.name = \"{name}\" node.description = {description} }} }}",
+                port + 1
+            ),
+            NetworkSessionKind::RocReceive => format!(
+                "{{ fec.code = rs8m local.ip = {address} local.source.port = {port} local.repair.port = {} \
+                source.name = {description} source.props = {{ node.name = \"{name}\" node.description = {description} }} }}",
+                port + 1
+            ),
+            NetworkSessionKind::TunnelSink | NetworkSessionKind::TunnelSource => format!(
+                "{{ tunnel.mode = {} pulse.server.address = \"tcp:{}:{port}\" \
+                stream.props = {{ node.name = \"{name}\" node.description = {description} }} }}",
+                if self.kind == NetworkSessionKind::TunnelSink { "sink" } else { "source" },
+                self.address.replace('"', "")
+            ),
+        }
+    }
+}
+
+/// Persisted sessions, by id.
+pub(crate) fn network_sessions() -> BTreeMap<String, NetworkSession> {
+    let stored: BTreeMap<String, (String, String, String, u32)> = gio::Settings::new(APP_ID).get(KEY);
+
+    stored
+        .into_iter()
+        .filter_map(|(id, (kind, label, address, port))| {
+            let session = NetworkSession {
+                id: id.clone(),
+                kind: NetworkSessionKind::from_key(&kind)?,
+                label,
+                address,
+                port,
+            };
+            Some((id, session))
+        })
+        .collect()
+}
+
+/// Stores the sessions and writes them as config fragment, so the daemon loads them from now on.
+fn store(sessions: &BTreeMap<String, NetworkSession>) {
+    let stored: BTreeMap<String, (String, String, String, u32)> = sessions
+        .iter()
+        .map(|(id, session)| (id.clone(), (session.kind.key().to_string(), session.label.clone(), session.address.clone(), session.port)))
+        .collect();
+
+    if let Err(e) = gio::Settings::new(APP_ID).set(KEY, stored) {
+        pwvucontrol_warning!("Cannot store {KEY}: {e}");
+        return;
+    }
+
+    write_fragment(sessions);
+}
+
+/// Writes the sessions as PipeWire config fragment.
+fn write_fragment(sessions: &BTreeMap<String, NetworkSession>) {
+    let modules: Vec<(&str, String)> = sessions.values().map(|session| (session.kind.module_name(), session.module_args())).collect();
+    conffragment::write_context_modules("network", &modules);
+}
+
+impl PwvucontrolManager {
+    pub(crate) fn add_network_session(&self, session: NetworkSession) {
+        let mut sessions = network_sessions();
+        sessions.insert(session.id.clone(), session.clone());
+        store(&sessions);
+
+        self.load_module(&session.node_name(), session.kind.module_name(), &session.module_args());
+    }
+
+    pub(crate) fn remove_network_session(&self, id: &str) {
+        let mut sessions = network_sessions();
+        let Some(session) = sessions.remove(id) else {
+            return;
+        };
+        store(&sessions);
+
+        self.unload_module(&session.node_name());
+    }
+
+    /// Session nodes take their description from the module arguments, so the fragment is written
+    /// again when one of them is renamed.
+    pub(crate) fn watch_network_session_names(&self) {
+        self.connect_local("node-renamed", false, |values| {
+            let node_name: String = values[1].get().expect("node name");
+            if node_name.starts_with(NODE_PREFIX) {
+                write_fragment(&network_sessions());
+            }
+            None
+        });
+    }
+
+    /// Loads sessions the daemon does not run yet, because it was not restarted since they were added.
+    pub(crate) fn load_network_sessions(&self) {
+        for session in network_sessions().values() {
+            if !self.has_node_named(&session.node_name()) {
+                self.load_module(&session.node_name(), session.kind.module_name(), &session.module_args());
+            }
+        }
+    }
+}
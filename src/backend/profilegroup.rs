@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gettextrs::gettext;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, glib::Enum, Default)]
+#[enum_type(name = "ProfileGroup")]
+pub enum ProfileGroup {
+    A2dp,
+    Headset,
+    #[default]
+    Other,
+}
+
+impl ProfileGroup {
+    pub fn title(&self) -> String {
+        match self {
+            ProfileGroup::A2dp => gettext("High Fidelity (A2DP)"),
+            ProfileGroup::Headset => gettext("Headset (HSP/HFP)"),
+            ProfileGroup::Other => gettext("Other"),
+        }
+    }
+}
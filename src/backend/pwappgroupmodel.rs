@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::{clone, Properties};
+use gtk::{gio, prelude::*, subclass::prelude::*};
+use std::cell::OnceCell;
+
+use super::{PwAppGroupObject, PwNodeObject};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Properties)]
+    #[properties(wrapper_type = super::PwAppGroupModel)]
+    pub struct PwAppGroupModel {
+        /// The streams to group.
+        #[property(get, set, construct_only)]
+        pub(super) model: OnceCell<gio::ListModel>,
+
+        /// One `PwAppGroupObject` per application, in the order its first stream appears.
+        #[property(get)]
+        pub(super) groups: gio::ListStore,
+    }
+
+    impl Default for PwAppGroupModel {
+        fn default() -> Self {
+            Self {
+                model: Default::default(),
+                groups: gio::ListStore::new::<PwAppGroupObject>(),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwAppGroupModel {
+        const NAME: &'static str = "PwAppGroupModel";
+        type Type = super::PwAppGroupModel;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwAppGroupModel {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.model().connect_items_changed(clone!(@weak obj => move |_, _, _, _| obj.regroup()));
+            obj.regroup();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Groups streams by application.
+    pub struct PwAppGroupModel(ObjectSubclass<imp::PwAppGroupModel>);
+}
+
+impl PwAppGroupModel {
+    pub(crate) fn new(model: &impl IsA<gio::ListModel>) -> Self {
+        glib::Object::builder().property("model", model.upcast_ref::<gio::ListModel>()).build()
+    }
+
+    /// Updates the groups in place, so rows of groups that stay keep their state.
+    fn regroup(&self) {
+        let mut wanted: Vec<(String, Vec<PwNodeObject>)> = Vec::new();
+        for node in self.model().iter::<PwNodeObject>().map_while(Result::ok) {
+            let key = PwAppGroupObject::group_key(&node);
+            match wanted.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, nodes)) => nodes.push(node),
+                None => wanted.push((key, vec![node])),
+            }
+        }
+
+        let groups = self.groups();
+        for (i, group) in (0..).zip(groups.iter::<PwAppGroupObject>().map_while(Result::ok).collect::<Vec<_>>()).rev() {
+            if !wanted.iter().any(|(key, _)| *key == group.key()) {
+                group.set_streams(&[]);
+                groups.remove(i);
+            }
+        }
+
+        for (key, nodes) in wanted {
+            let existing = groups.iter::<PwAppGroupObject>().map_while(Result::ok).find(|group| group.key() == key);
+            match existing {
+                Some(group) => {
+                    let mut ids: Vec<u32> = nodes.iter().map(|node| node.boundid()).collect();
+                    let mut current = group.stream_ids();
+                    ids.sort_unstable();
+                    current.sort_unstable();
+                    if ids != current {
+                        group.set_streams(&nodes);
+                    }
+                },
+                None => {
+                    let group = PwAppGroupObject::new(&key);
+                    group.set_streams(&nodes);
+                    groups.append(&group);
+                },
+            }
+        }
+    }
+}
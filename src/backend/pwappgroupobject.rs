@@ -0,0 +1,200 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::{clone, Properties, SignalHandlerId};
+use gtk::{gio, prelude::*, subclass::prelude::*};
+use std::{cell::{Cell, RefCell}, collections::HashMap};
+use wireplumber as wp;
+use wp::pw::PipewireObjectExt2;
+
+use super::PwNodeObject;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Properties)]
+    #[properties(wrapper_type = super::PwAppGroupObject)]
+    pub struct PwAppGroupObject {
+        /// Identifies the application, see `group_key`.
+        #[property(get, set, construct_only)]
+        key: RefCell<String>,
+        #[property(get, set)]
+        name: RefCell<String>,
+        #[property(get, set)]
+        iconname: RefCell<String>,
+        /// Loudest member volume. Setting it scales all members proportionally.
+        #[property(get, set = Self::set_volume)]
+        volume: Cell<f32>,
+        /// Whether all members are muted. Setting it mutes or unmutes all members.
+        #[property(get, set = Self::set_mute)]
+        mute: Cell<bool>,
+
+        #[property(get)]
+        pub(super) streams: gio::ListStore,
+
+        pub(super) handlers: RefCell<HashMap<u32, Vec<SignalHandlerId>>>,
+        pub(super) block: Cell<bool>,
+    }
+
+    impl Default for PwAppGroupObject {
+        fn default() -> Self {
+            Self {
+                key: Default::default(),
+                name: Default::default(),
+                iconname: Default::default(),
+                volume: Default::default(),
+                mute: Default::default(),
+                streams: gio::ListStore::new::<PwNodeObject>(),
+                handlers: Default::default(),
+                block: Default::default(),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwAppGroupObject {
+        const NAME: &'static str = "PwAppGroupObject";
+        type Type = super::PwAppGroupObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwAppGroupObject {
+        fn dispose(&self) {
+            for node in self.streams.iter::<PwNodeObject>().map_while(Result::ok) {
+                for handler in self.handlers.borrow_mut().remove(&node.boundid()).unwrap_or_default() {
+                    node.disconnect(handler);
+                }
+            }
+        }
+    }
+
+    impl PwAppGroupObject {
+        fn set_volume(&self, volume: f32) {
+            let old = self.volume.replace(volume);
+            if self.block.get() || old == volume {
+                return;
+            }
+
+            let blocked = self.block.replace(true);
+            for node in self.streams.iter::<PwNodeObject>().map_while(Result::ok) {
+                if old > 0.0 {
+                    node.set_volume(node.volume() * volume / old);
+                } else {
+                    node.set_volume(volume);
+                }
+            }
+            self.block.set(blocked);
+        }
+
+        fn set_mute(&self, mute: bool) {
+            self.mute.set(mute);
+            if self.block.get() {
+                return;
+            }
+
+            let blocked = self.block.replace(true);
+            for node in self.streams.iter::<PwNodeObject>().map_while(Result::ok) {
+                if node.mute() != mute {
+                    node.set_mute(mute);
+                }
+            }
+            self.block.set(blocked);
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The streams of one application, shown as a single row in grouped mode.
+    pub struct PwAppGroupObject(ObjectSubclass<imp::PwAppGroupObject>);
+}
+
+impl PwAppGroupObject {
+    pub(crate) fn new(key: &str) -> Self {
+        glib::Object::builder().property("key", key).build()
+    }
+
+    /// Streams of the same application process share a key. Streams without a process fall back to
+    /// the application name, or stay on their own.
+    pub(crate) fn group_key(node: &PwNodeObject) -> String {
+        let wpnode = node.wpnode();
+        if let Ok(pid) = wpnode.pw_property::<String>("application.process.id") {
+            format!("pid:{pid}")
+        } else if let Ok(name) = wpnode.pw_property::<String>("application.name") {
+            format!("name:{name}")
+        } else {
+            format!("node:{}", node.boundid())
+        }
+    }
+
+    pub(crate) fn stream_ids(&self) -> Vec<u32> {
+        self.nodes().iter().map(|node| node.boundid()).collect()
+    }
+
+    pub(crate) fn nodes(&self) -> Vec<PwNodeObject> {
+        self.streams().iter::<PwNodeObject>().map_while(Result::ok).collect()
+    }
+
+    /// Replaces the members, keeping the ones that stay.
+    pub(crate) fn set_streams(&self, nodes: &[PwNodeObject]) {
+        let imp = self.imp();
+
+        for (i, node) in (0..).zip(self.streams().iter::<PwNodeObject>().map_while(Result::ok).collect::<Vec<_>>()).rev() {
+            if !nodes.contains(&node) {
+                for handler in imp.handlers.borrow_mut().remove(&node.boundid()).unwrap_or_default() {
+                    node.disconnect(handler);
+                }
+                imp.streams.remove(i);
+            }
+        }
+
+        for node in nodes {
+            if imp.handlers.borrow().contains_key(&node.boundid()) {
+                continue;
+            }
+            let handlers = vec![
+                node.connect_volume_notify(clone!(@weak self as group => move |_| group.update())),
+                node.connect_mute_notify(clone!(@weak self as group => move |_| group.update())),
+            ];
+            imp.handlers.borrow_mut().insert(node.boundid(), handlers);
+            imp.streams.append(node);
+        }
+
+        if let Some(first) = nodes.first() {
+            let name = first
+                .app_info()
+                .map(|info| info.display_name().to_string())
+                .or_else(|| first.wpnode().pw_property::<String>("application.name").ok())
+                .unwrap_or_else(|| first.name());
+            self.set_name(name);
+            self.set_iconname(first.iconname());
+        }
+
+        self.update();
+    }
+
+    /// Sends all members to a sink, or back to the default one.
+    pub(crate) fn move_all(&self, target: Option<&PwNodeObject>) {
+        for node in self.streams().iter::<PwNodeObject>().map_while(Result::ok) {
+            match target {
+                Some(target) => node.set_default_target(target),
+                None => node.unset_default_target(),
+            }
+        }
+    }
+
+    /// Recomputes the aggregate volume and mute from the members.
+    fn update(&self) {
+        let imp = self.imp();
+        if imp.block.get() {
+            return;
+        }
+
+        let nodes: Vec<PwNodeObject> = self.streams().iter::<PwNodeObject>().map_while(Result::ok).collect();
+        let volume = nodes.iter().map(|node| node.volume()).fold(0.0, f32::max);
+        let mute = !nodes.is_empty() && nodes.iter().all(|node| node.mute());
+
+        imp.block.set(true);
+        self.set_volume(volume);
+        self.set_mute(mute);
+        imp.block.set(false);
+    }
+}
@@ -6,6 +6,7 @@ use glib::{
     subclass::{prelude::*, Signal},
     Object, ObjectExt, ParamSpec, Properties, Value,
 };
+use gettextrs::gettext;
 use gtk::{gio, prelude::*};
 use wireplumber as wp;
 use wp::{
@@ -13,10 +14,12 @@ use wp::{
     spa::SpaPodBuilder,
 };
 
-use crate::macros::*;
+use crate::{config::APP_ID, macros::*, PwvucontrolApplication};
 use once_cell::sync::{Lazy, OnceCell};
-use super::{PwRouteFilterModel, PwRouteObject, RouteDirection};
-use std::cell::{Cell, RefCell};
+use super::{customnames::{self, CustomNameKind}, disableddevices, EventCategory, rules::RuleTarget, ParamAvailability, PwRouteFilterModel, PwRouteObject, PwvucontrolManager, RouteDirection, RuleTrigger};
+use std::{cell::{Cell, RefCell}, collections::{BTreeMap, HashMap}};
+
+mod bluetooth;
 
 pub mod imp {
 
@@ -31,9 +34,22 @@ pub mod imp {
         #[property(get, set)]
         icon_name: RefCell<String>,
 
+        /// Whether the name is a custom description set by the user.
+        #[property(get, set)]
+        renamed: Cell<bool>,
+
+        #[property(get, set)]
+        disabled: Cell<bool>,
+
         #[property(get, set)]
         pub(super) profile_index: Cell<u32>,
 
+        /// Whether the session manager is asked to save profile and route choices.
+        #[property(get, set = Self::set_remember)]
+        pub(super) remember: Cell<bool>,
+
+        pub(super) profile_saved: Cell<bool>,
+
         #[property(get, set)]
         pub(super) route_index_input: Cell<u32>,
 
@@ -46,6 +62,15 @@ pub mod imp {
         #[property(get)]
         pub(super) profilemodel: gio::ListStore,
 
+        #[property(get)]
+        pub(super) codecmodel: gio::ListStore,
+
+        #[property(get, set)]
+        pub(super) codec: RefCell<Option<String>>,
+
+        #[property(get, set)]
+        pub(super) battery: Cell<i32>,
+
         #[property(get)]
         pub(super) routemodel_input: PwRouteFilterModel,
 
@@ -53,6 +78,10 @@ pub mod imp {
         pub(super) routemodel_output: PwRouteFilterModel,
 
         pub(super) routemodel: gio::ListStore,
+
+        pub(super) battery_subscription: RefCell<Option<(gio::DBusConnection, gio::SignalSubscriptionId)>>,
+
+        settings: OnceCell<gio::Settings>,
     }
 
     impl Default for PwDeviceObject {
@@ -60,14 +89,23 @@ pub mod imp {
             Self {
                 name: Default::default(),
                 icon_name: Default::default(),
+                renamed: Default::default(),
+                disabled: Default::default(),
                 profile_index: Default::default(),
+                remember: Default::default(),
+                profile_saved: Default::default(),
                 route_index_input: Default::default(),
                 route_index_output: Default::default(),
                 wpdevice: Default::default(),
                 profilemodel: gio::ListStore::new::<PwProfileObject>(),
+                codecmodel: gio::ListStore::new::<PwProfileObject>(),
+                codec: Default::default(),
+                battery: Cell::new(-1),
                 routemodel_input: PwRouteFilterModel::new(RouteDirection::Input, gio::ListModel::NONE),
                 routemodel_output: PwRouteFilterModel::new(RouteDirection::Output, gio::ListModel::NONE),
                 routemodel: gio::ListStore::new::<PwRouteObject>(),
+                battery_subscription: Default::default(),
+                settings: Default::default(),
             }
         }
     }
@@ -99,6 +137,8 @@ pub mod imp {
                     Signal::builder("post-update-profile").build(),
                     Signal::builder("pre-update-route").build(),
                     Signal::builder("post-update-route").build(),
+                    Signal::builder("pre-update-codec").build(),
+                    Signal::builder("post-update-codec").build(),
                 ]
             });
 
@@ -115,12 +155,29 @@ pub mod imp {
 
             obj.label_set_name();
             obj.update_icon_name();
+            obj.update_remember();
+            obj.update_disabled();
             obj.update_profiles();
 
             self.obj().update_current_profile_index();
 
             obj.update_routes();
 
+            if obj.is_bluetooth() {
+                obj.setup_battery();
+            }
+
+            let settings = gio::Settings::new(APP_ID);
+            settings.connect_changed(None, clone!(@weak obj => move |_, key| {
+                match key {
+                    "save-device-choices" | "device-save-overrides" => obj.update_remember(),
+                    "device-descriptions" => obj.label_set_name(),
+                    "disabled-devices" => obj.update_disabled(),
+                    _ => {},
+                }
+            }));
+            self.settings.set(settings).expect("settings set once");
+
             obj.wpdevice().connect_properties_notify(clone!(@weak obj => move |device| {
                 pwvucontrol_debug!("properties changed! id: {}", device.object_id().unwrap());
 
@@ -135,22 +192,56 @@ pub mod imp {
                         obj.update_profiles();
                     },
                     "Profile" => {
+                        let old = obj.profile_index();
                         obj.update_current_profile_index();
+                        if obj.profile_index() != old {
+                            obj.log_profile_change();
+                        }
                     },
                     "EnumRoute" => {
                         obj.update_routes();
                     },
                     "Route" => {
+                        let old = [RouteDirection::Output, RouteDirection::Input].map(|direction| obj.active_route(direction));
                         obj.update_current_route_index();
+                        obj.log_route_changes(old);
                     },
                     _ => {},
                 }
 
             }));
         }
+
+        fn dispose(&self) {
+            if let Some((connection, id)) = self.battery_subscription.take() {
+                connection.signal_unsubscribe(id);
+            }
+        }
     }
 
-    impl PwDeviceObject {}
+    impl PwDeviceObject {
+        fn set_remember(&self, remember: bool) {
+            self.remember.set(remember);
+
+            let Some(name) = self.obj().device_name() else {
+                return;
+            };
+
+            let settings = gio::Settings::new(APP_ID);
+            let mut overrides: BTreeMap<String, bool> = settings.get("device-save-overrides");
+            let changed = if remember == settings.boolean("save-device-choices") {
+                overrides.remove(&name).is_some()
+            } else {
+                overrides.insert(name, remember) != Some(remember)
+            };
+
+            if changed {
+                if let Err(e) = settings.set("device-save-overrides", overrides) {
+                    pwvucontrol_warning!("Cannot store device-save-overrides: {e}");
+                }
+            }
+        }
+    }
 }
 
 glib::wrapper! {
@@ -172,6 +263,7 @@ impl PwDeviceObject {
             clone!(@weak self as widget => move |res| {
                 let keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Profile").expect("id table");
                 let index_key = keys.find_value_from_short_name("index").expect("index key");
+                let name_key = keys.find_value_from_short_name("name").expect("name key");
                 let description_key = keys.find_value_from_short_name("description").expect("decription key");
                 let available_key = keys.find_value_from_short_name("available").expect("available key");
 
@@ -185,14 +277,17 @@ impl PwDeviceObject {
                         }
 
                         let index = pod.find_spa_property(&index_key).expect("Index!").int().expect("Int");
+                        let name = pod.find_spa_property(&name_key).and_then(|x| x.string()).unwrap_or_default();
                         let description = pod.find_spa_property(&description_key).expect("Format!").string().expect("String");
                         let available = pod.find_spa_property(&available_key).expect("Availability!").id().expect("Id");
 
-                        profiles.push(PwProfileObject::new(index as u32, &description, available));
+                        profiles.push(PwProfileObject::new(index as u32, &name, &description, available));
                     }
                     widget.emit_by_name::<()>("pre-update-profile", &[]);
                     widget.profilemodel().splice(0, widget.profilemodel().n_items(), &profiles);
                     widget.emit_by_name::<()>("post-update-profile", &[]);
+                    widget.update_profile_saved();
+                    widget.update_codecs();
                 } else if let Err(e) = res {
                     dbg!(e);
                 }
@@ -206,6 +301,7 @@ impl PwDeviceObject {
         let keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Profile").expect("id table");
         let index_key = keys.find_value_from_short_name("index").expect("index key");
         let description_key = keys.find_value_from_short_name("description").expect("decription key");
+        let save_key = keys.find_value_from_short_name("save").expect("save key");
 
         if let Some(params) = device.enum_params_sync("Profile", None) {
             for a in params {
@@ -219,8 +315,14 @@ impl PwDeviceObject {
                 pwvucontrol_info!("Current profile #{} {}", index, description);
 
                 self.set_profile_index(index as u32);
+
+                let saved = pod.find_spa_property(&save_key).and_then(|x| x.boolean()).unwrap_or_default();
+                self.imp().profile_saved.set(saved);
             }
         }
+
+        self.update_profile_saved();
+        self.update_codecs();
     }
 
     pub(crate) fn set_profile(&self, index: i32) {
@@ -230,12 +332,57 @@ impl PwDeviceObject {
 
         podbuilder.add_property("index");
         podbuilder.add_int(index);
+        if self.remember() {
+            podbuilder.add_property("save");
+            podbuilder.add_boolean(true);
+        }
 
         if let Some(pod) = podbuilder.end() {
             device.set_param("Profile", 0, pod);
         }
     }
 
+    fn update_profile_saved(&self) {
+        let saved = self.imp().profile_saved.get();
+
+        for profile in self.profilemodel().iter::<PwProfileObject>().map_while(Result::ok) {
+            let profile_saved = saved && profile.index() == self.profile_index();
+            if profile.saved() != profile_saved {
+                profile.set_saved(profile_saved);
+            }
+        }
+    }
+
+    /// The per-device override wins over the global setting.
+    fn update_remember(&self) {
+        let settings = gio::Settings::new(APP_ID);
+        let overrides: BTreeMap<String, bool> = settings.get("device-save-overrides");
+
+        let remember = self
+            .device_name()
+            .and_then(|name| overrides.get(&name).copied())
+            .unwrap_or_else(|| settings.boolean("save-device-choices"));
+
+        if self.remember() != remember {
+            self.imp().remember.set(remember);
+            self.notify_remember();
+        }
+    }
+
+    fn update_disabled(&self) {
+        let disabled = self
+            .device_name()
+            .is_some_and(|name| disableddevices::disabled_devices().contains_key(&name));
+
+        if self.disabled() != disabled {
+            self.set_disabled(disabled);
+        }
+    }
+
+    pub(crate) fn device_name(&self) -> Option<String> {
+        self.wpdevice().pw_property("device.name").ok()
+    }
+
     pub(crate) fn update_routes(&self) {
         let device = self.wpdevice();
 
@@ -246,6 +393,7 @@ impl PwDeviceObject {
             clone!(@weak self as widget => move |res| {
                 let keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Route").expect("id table");
                 let index_key = keys.find_value_from_short_name("index").expect("index key");
+                let name_key = keys.find_value_from_short_name("name").expect("name key");
                 let description_key = keys.find_value_from_short_name("description").expect("decription key");
                 let available_key = keys.find_value_from_short_name("available").expect("available key");
                 let direction_key = keys.find_value_from_short_name("direction").expect("direction key");
@@ -253,6 +401,13 @@ impl PwDeviceObject {
 
                 if let Ok(Some(iter)) = res {
                     let removed = widget.imp().routemodel.n_items();
+                    let previous: HashMap<u32, ParamAvailability> = widget
+                        .imp()
+                        .routemodel
+                        .iter::<PwRouteObject>()
+                        .map_while(Result::ok)
+                        .map(|route| (route.index(), route.availability()))
+                        .collect();
 
                     let mut routes: Vec<PwRouteObject> = Vec::new();
 
@@ -270,13 +425,32 @@ impl PwDeviceObject {
                         assert!(profiles.is_array());
                         let profiles_vec: Vec<u32> =  profiles.array_iterator::<i32>().map(|x| x as u32).collect();
 
-                        routes.push(PwRouteObject::new(index as u32, &description, available, direction, &profiles_vec));
+                        let route = PwRouteObject::new(index as u32, &description, available, direction, &profiles_vec);
+                        if let Some(name) = pod.find_spa_property(&name_key).and_then(|x| x.string()) {
+                            route.set_name(name);
+                        }
+                        routes.push(route);
                     }
                     // Notify update of list model
                     widget.emit_by_name::<()>("pre-update-route", &[]);
                     widget.imp().routemodel.splice(0, removed as u32, &routes);
                     widget.update_current_route_index();
                     widget.emit_by_name::<()>("post-update-route", &[]);
+
+                    let manager = PwvucontrolManager::default();
+                    for route in routes.iter() {
+                        let trigger = match (previous.get(&route.index()), route.availability()) {
+                            (Some(ParamAvailability::Yes), ParamAvailability::Yes) => continue,
+                            (Some(ParamAvailability::No), ParamAvailability::No) => continue,
+                            (Some(_), ParamAvailability::Yes) => RuleTrigger::RouteAvailable,
+                            (Some(_), ParamAvailability::No) => RuleTrigger::RouteUnavailable,
+                            _ => continue,
+                        };
+                        manager.apply_rules(trigger, RuleTarget::Route(&widget, route));
+                        if route.is_headphones() {
+                            PwvucontrolApplication::default().notify_headphones(&widget, route);
+                        }
+                    }
                 } else {
                     if let Err(e) = res {
                         dbg!(e);
@@ -298,6 +472,21 @@ impl PwDeviceObject {
         let index_key = keys.find_value_from_short_name("index").expect("index key");
         let description_key = keys.find_value_from_short_name("description").expect("decription key");
         let direction_key = keys.find_value_from_short_name("direction").expect("direction key");
+        let device_key = keys.find_value_from_short_name("device").expect("device key");
+        let props_key = keys.find_value_from_short_name("props").expect("props key");
+
+        let props_keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Props").expect("id table");
+        let latency_offset_key = props_keys.find_value_from_short_name("latencyOffsetNsec").expect("latencyOffsetNsec key");
+        let volume_key = props_keys.find_value_from_short_name("volume").expect("volume key");
+        let mute_key = props_keys.find_value_from_short_name("mute").expect("mute key");
+        let channelvolumes_key = props_keys.find_value_from_short_name("channelVolumes").expect("channelVolumes key");
+        let save_key = keys.find_value_from_short_name("save").expect("save key");
+
+        for route in self.imp().routemodel.iter::<PwRouteObject>().map_while(Result::ok) {
+            if route.direction() == direction && route.saved() {
+                route.set_saved(false);
+            }
+        }
 
         if let Some(params) = device.enum_params_sync("Route", None) {
             for a in params {
@@ -323,6 +512,43 @@ impl PwDeviceObject {
                 }
                 pwvucontrol_debug!("Current route #{} {}", index, description);
 
+                if let Some(routeobject) = self.get_route_by_index(index as u32) {
+                    if let Some(device) = pod.find_spa_property(&device_key).and_then(|x| x.int()) {
+                        routeobject.set_device(device);
+                    }
+                    routeobject.set_saved(pod.find_spa_property(&save_key).and_then(|x| x.boolean()).unwrap_or_default());
+
+                    if let Some(props) = pod.find_spa_property(&props_key) {
+                        if let Some(offset) = props.find_spa_property(&latency_offset_key).and_then(|x| x.long()) {
+                            routeobject.set_latencyoffset(offset as f64 / 1_000_000.0);
+                        }
+
+                        let channel_volumes: Vec<f32> = props
+                            .find_spa_property(&channelvolumes_key)
+                            .filter(|x| x.is_array())
+                            .map(|x| x.array_iterator::<f32>().collect())
+                            .unwrap_or_default();
+                        let volume = channel_volumes
+                            .iter()
+                            .copied()
+                            .max_by(f32::total_cmp)
+                            .or_else(|| props.find_spa_property(&volume_key).and_then(|x| x.float()));
+
+                        routeobject.set_channel_volumes(&channel_volumes);
+                        routeobject.set_hasvolume(volume.is_some());
+                        if let Some(volume) = volume {
+                            if routeobject.volume() != volume {
+                                routeobject.set_volume(volume);
+                            }
+                        }
+                        if let Some(mute) = props.find_spa_property(&mute_key).and_then(|x| x.boolean()) {
+                            if routeobject.mute() != mute {
+                                routeobject.set_mute(mute);
+                            }
+                        }
+                    }
+                }
+
                 if let Some(modelindex) = self.get_model_index_from_route_index(direction, index) {
                     match direction {
                         RouteDirection::Input => if self.route_index_input() != modelindex { self.set_route_index_input(modelindex) },
@@ -353,6 +579,71 @@ impl PwDeviceObject {
     }
 
     pub(crate) fn set_route(&self, index: u32, device_index: i32) {
+        self.set_route_with_props(index, device_index, None, self.remember());
+    }
+
+    /// Sets the volume of the active route, keeping the balance between its channels.
+    pub(crate) fn set_route_volume(&self, direction: RouteDirection, volume: f32) {
+        let Some(route) = self.active_route(direction) else {
+            return;
+        };
+
+        let channel_volumes = route.channel_volumes();
+        let max = channel_volumes.iter().copied().fold(0.0, f32::max);
+        let channel_volumes: Vec<f32> = if max > 0.0 {
+            channel_volumes.iter().map(|v| v * volume / max).collect()
+        } else {
+            vec![volume; channel_volumes.len()]
+        };
+
+        route.set_channel_volumes(&channel_volumes);
+        route.set_volume(volume);
+
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Props", "Route");
+
+        if channel_volumes.is_empty() {
+            podbuilder.add_property("volume");
+            podbuilder.add_float(volume);
+        } else {
+            let array = SpaPodBuilder::new_array();
+            for v in channel_volumes {
+                array.add_float(v);
+            }
+            if let Some(arraypod) = array.end() {
+                podbuilder.add_property("channelVolumes");
+                podbuilder.add_pod(&arraypod);
+            }
+        }
+
+        self.set_route_with_props(route.index(), route.device(), podbuilder.end().as_ref(), true);
+    }
+
+    pub(crate) fn set_route_mute(&self, direction: RouteDirection, mute: bool) {
+        let Some(route) = self.active_route(direction) else {
+            return;
+        };
+
+        route.set_mute(mute);
+
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Props", "Route");
+
+        podbuilder.add_property("mute");
+        podbuilder.add_boolean(mute);
+
+        self.set_route_with_props(route.index(), route.device(), podbuilder.end().as_ref(), true);
+    }
+
+    /// Sets the latency offset on a route. The session manager forwards it to the node.
+    pub(crate) fn set_route_latency_offset(&self, index: u32, device_index: i32, offset_ns: i64) {
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Props", "Route");
+
+        podbuilder.add_property("latencyOffsetNsec");
+        podbuilder.add_long(offset_ns);
+
+        self.set_route_with_props(index, device_index, podbuilder.end().as_ref(), false);
+    }
+
+    fn set_route_with_props(&self, index: u32, device_index: i32, props: Option<&wp::spa::SpaPod>, save: bool) {
         let device = self.wpdevice();
 
         let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Route", "Route");
@@ -361,14 +652,69 @@ impl PwDeviceObject {
         podbuilder.add_int(index as i32);
         podbuilder.add_property("device");
         podbuilder.add_int(device_index);
-        // podbuilder.add_property("save");
-        // podbuilder.add_boolean(true);
+        if let Some(props) = props {
+            podbuilder.add_property("props");
+            podbuilder.add_pod(props);
+        }
+        if save {
+            podbuilder.add_property("save");
+            podbuilder.add_boolean(true);
+        }
 
         if let Some(pod) = podbuilder.end() {
             device.set_param("Route", 0, pod);
         }
     }
 
+    /// Returns the route currently selected for the given direction.
+    pub(crate) fn active_route(&self, direction: RouteDirection) -> Option<PwRouteObject> {
+        let index = match direction {
+            RouteDirection::Input => self.route_index_input(),
+            RouteDirection::Output => self.route_index_output(),
+            _ => return None,
+        };
+        self.get_route_model_for_direction(direction).item(index).and_downcast()
+    }
+
+    fn log_profile_change(&self) {
+        let index = self.profile_index();
+        let Some(profile) = self.profilemodel().iter::<PwProfileObject>().map_while(Result::ok).find(|profile| profile.index() == index) else {
+            return;
+        };
+
+        let message = gettext("{device}: profile changed to {profile}")
+            .replace("{device}", &self.name().unwrap_or_default())
+            .replace("{profile}", &profile.description());
+        PwvucontrolManager::default().log_event(EventCategory::Profile, &message);
+    }
+
+    /// Logs the directions whose active route differs from the one given for it, in output, input order.
+    fn log_route_changes(&self, old: [Option<PwRouteObject>; 2]) {
+        for (direction, old) in [RouteDirection::Output, RouteDirection::Input].into_iter().zip(old) {
+            let Some(route) = self.active_route(direction) else {
+                continue;
+            };
+            if old.is_some_and(|old| old == route) {
+                continue;
+            }
+
+            let message = match direction {
+                RouteDirection::Output => gettext("{device}: output port changed to {port}"),
+                _ => gettext("{device}: input port changed to {port}"),
+            };
+            let message = message.replace("{device}", &self.name().unwrap_or_default()).replace("{port}", &route.description());
+            PwvucontrolManager::default().log_event(EventCategory::Route, &message);
+        }
+    }
+
+    fn get_route_by_index(&self, index: u32) -> Option<PwRouteObject> {
+        self.imp()
+            .routemodel
+            .iter::<PwRouteObject>()
+            .map_while(Result::ok)
+            .find(|route| route.index() == index)
+    }
+
     fn get_route_model_for_direction(&self, direction: RouteDirection) -> PwRouteFilterModel {
         match direction {
             RouteDirection::Input => self.routemodel_input(),
@@ -378,10 +724,23 @@ impl PwDeviceObject {
     }
 
     fn label_set_name(&self) {
-        let description: String = self.wpdevice().pw_property("device.description").expect("device description");
+        let custom = self.device_name().and_then(|name| customnames::custom_description(CustomNameKind::Device, &name));
+        self.set_renamed(custom.is_some());
+
+        let description: String = custom.unwrap_or_else(|| self.wpdevice().pw_property("device.description").expect("device description"));
         self.set_name(description);
     }
 
+    /// Gives the device a persistent custom description. `None` restores the original one.
+    pub(crate) fn rename(&self, description: Option<&str>) {
+        let Some(name) = self.device_name() else {
+            return;
+        };
+
+        customnames::set_custom_description(CustomNameKind::Device, &name, description);
+        self.label_set_name();
+    }
+
     fn update_icon_name(&self) {
         let icon_name: String = self
             .wpdevice()
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use super::*;
+
+const BLUEZ_BUS_NAME: &str = "org.bluez";
+
+impl PwDeviceObject {
+    pub(crate) fn is_bluetooth(&self) -> bool {
+        self.wpdevice().pw_property::<String>("device.api").is_ok_and(|api| api == "bluez5")
+    }
+
+    /// Collects the codec variants of the active profile family so the codec can be switched
+    /// without leaving e.g. A2DP. Selecting a codec means selecting its profile.
+    pub(crate) fn update_codecs(&self) {
+        let profiles: Vec<PwProfileObject> = self.profilemodel().iter::<PwProfileObject>().map_while(Result::ok).collect();
+        let current = profiles.iter().find(|profile| profile.index() == self.profile_index());
+
+        let codecs: Vec<PwProfileObject> = match current {
+            Some(current) => profiles
+                .iter()
+                .filter(|profile| profile.family() == current.family() && profile.codec().is_some())
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let codecmodel = self.codecmodel();
+        self.emit_by_name::<()>("pre-update-codec", &[]);
+        codecmodel.splice(0, codecmodel.n_items(), &codecs);
+        self.emit_by_name::<()>("post-update-codec", &[]);
+
+        let codec = current.and_then(|profile| profile.codec()).or_else(|| self.node_codec());
+        if self.codec() != codec {
+            self.set_codec(codec);
+        }
+    }
+
+    /// The plain profile names carry no codec, in which case the nodes of the device tell.
+    fn node_codec(&self) -> Option<String> {
+        PwvucontrolManager::default()
+            .get_nodes_for_device(self)
+            .iter()
+            .find_map(|node| node.wpnode().pw_property::<String>("api.bluez5.codec").ok())
+    }
+
+    /// Watches the battery level BlueZ reports for the device. PipeWire itself does not carry it.
+    pub(super) fn setup_battery(&self) {
+        gio::bus_get(
+            gio::BusType::System,
+            gio::Cancellable::NONE,
+            clone!(@weak self as obj => move |res| {
+                let connection = match res {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        pwvucontrol_warning!("Cannot connect to the system bus: {e}");
+                        return;
+                    }
+                };
+
+                let id = connection.signal_subscribe(
+                    Some(BLUEZ_BUS_NAME),
+                    Some("org.freedesktop.DBus.Properties"),
+                    Some("PropertiesChanged"),
+                    None,
+                    Some("org.bluez.Battery1"),
+                    gio::DBusSignalFlags::NONE,
+                    clone!(@weak obj => move |_, _, _, _, _, _| obj.update_battery()),
+                );
+                obj.imp().battery_subscription.replace(Some((connection, id)));
+
+                obj.update_battery();
+            }),
+        );
+    }
+
+    fn update_battery(&self) {
+        let Some(connection) = self.imp().battery_subscription.borrow().as_ref().map(|(connection, _)| connection.clone()) else {
+            return;
+        };
+        let Ok(address) = self.wpdevice().pw_property::<String>("api.bluez5.address") else {
+            return;
+        };
+
+        connection.call(
+            Some(BLUEZ_BUS_NAME),
+            "/",
+            "org.freedesktop.DBus.ObjectManager",
+            "GetManagedObjects",
+            None,
+            glib::VariantTy::new("(a{oa{sa{sv}}})").ok(),
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+            clone!(@weak self as obj => move |res| {
+                match res {
+                    Ok(reply) => {
+                        let level = battery_level(&reply.child_value(0), &address).map_or(-1, i32::from);
+                        if obj.battery() != level {
+                            obj.set_battery(level);
+                        }
+                    },
+                    Err(e) => {
+                        pwvucontrol_debug!("Cannot query BlueZ objects: {e}");
+                    }
+                }
+            }),
+        );
+    }
+}
+
+fn battery_level(objects: &glib::Variant, address: &str) -> Option<u8> {
+    objects.iter().find_map(|entry| {
+        let interfaces = entry.child_value(1).get::<HashMap<String, HashMap<String, glib::Variant>>>()?;
+
+        let device_address = interfaces.get("org.bluez.Device1")?.get("Address")?.get::<String>()?;
+        if !device_address.eq_ignore_ascii_case(address) {
+            return None;
+        }
+
+        interfaces.get("org.bluez.Battery1")?.get("Percentage")?.get::<u8>()
+    })
+}
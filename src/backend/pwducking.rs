@@ -0,0 +1,244 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::{clone, ControlFlow, Properties};
+use gtk::{gio, prelude::*, subclass::prelude::*};
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    collections::HashMap,
+    time::Duration,
+};
+
+use super::{NodeType, PwNodeObject};
+use crate::{config::APP_ID, macros::*};
+
+/// How often the volume ramp is updated.
+const TICK: Duration = Duration::from_millis(20);
+/// Properties a trigger is matched against.
+const TRIGGER_PROPERTIES: [&str; 4] = ["media.role", "application.name", "application.process.binary", "node.name"];
+
+/// A playback stream lowered by the ducking.
+#[derive(Debug)]
+struct DuckedStream {
+    node: PwNodeObject,
+    /// Volume before ducking.
+    original: f32,
+    /// Volume last set by the ducking.
+    applied: f32,
+}
+
+impl DuckedStream {
+    /// Volume to restore, keeping a change made while ducked relative to the ducking.
+    fn original(&self) -> f32 {
+        if self.applied > 1e-4 {
+            self.node.volume() * self.original / self.applied
+        } else {
+            self.original
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties)]
+    #[properties(wrapper_type = super::PwDucking)]
+    pub struct PwDucking {
+        /// Whether a triggering stream is playing.
+        #[property(get)]
+        pub(super) active: Cell<bool>,
+
+        pub(super) node_model: OnceCell<gio::ListStore>,
+        pub(super) settings: OnceCell<gio::Settings>,
+        /// Current gain on the cubic scale of the volume sliders, 1 when not ducked.
+        pub(super) gain: Cell<f32>,
+        pub(super) ducked: RefCell<HashMap<u32, DuckedStream>>,
+        pub(super) last_tick: Cell<i64>,
+        pub(super) source: RefCell<Option<glib::SourceId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwDucking {
+        const NAME: &'static str = "PwDucking";
+        type Type = super::PwDucking;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwDucking {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.gain.set(1.0);
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Lowers the other playback streams while a communication stream plays.
+    pub struct PwDucking(ObjectSubclass<imp::PwDucking>);
+}
+
+impl PwDucking {
+    pub(crate) fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Starts following the streams of `node_model`.
+    pub(crate) fn watch(&self, node_model: &gio::ListStore) {
+        let imp = self.imp();
+        imp.node_model.set(node_model.clone()).expect("Ducking watched once");
+
+        node_model.connect_items_changed(clone!(@weak self as obj => move |model, position, _removed, added| {
+            for node in (position..position + added).filter_map(|i| model.item(i).and_downcast::<PwNodeObject>()) {
+                node.connect_running_notify(clone!(@weak obj => move |_| obj.update()));
+            }
+            obj.update();
+        }));
+
+        let settings = gio::Settings::new(APP_ID);
+        settings.connect_changed(None, clone!(@weak self as obj => move |_, key| {
+            if key == "ducking-enabled" && !obj.settings().boolean(key) {
+                obj.stop();
+            }
+            if key.starts_with("ducking-") {
+                obj.update();
+            }
+        }));
+        imp.settings.set(settings).expect("Settings set once");
+    }
+
+    fn settings(&self) -> &gio::Settings {
+        self.imp().settings.get().expect("settings")
+    }
+
+    /// Visible playback and recording streams. Event sounds and our own streams are left alone.
+    fn streams(&self) -> impl Iterator<Item = PwNodeObject> + '_ {
+        self.imp()
+            .node_model
+            .get()
+            .into_iter()
+            .flat_map(|model| model.iter::<PwNodeObject>().map_while(Result::ok))
+            .filter(|node| !node.hidden() && matches!(node.nodetype(), NodeType::StreamOutput | NodeType::StreamInput))
+    }
+
+    fn is_trigger(&self, node: &PwNodeObject) -> bool {
+        let triggers = self.settings().strv("ducking-triggers");
+        let wpnode = node.wpnode();
+        TRIGGER_PROPERTIES
+            .iter()
+            .filter_map(|key| wpnode.pw_property::<String>(key).ok())
+            .any(|value| triggers.iter().any(|trigger| trigger.as_str().eq_ignore_ascii_case(&value)))
+    }
+
+    /// Re-evaluates whether to duck, and starts ramping if needed.
+    fn update(&self) {
+        let imp = self.imp();
+        let active = self.settings().boolean("ducking-enabled") && self.streams().any(|node| node.running() && self.is_trigger(&node));
+
+        if imp.active.replace(active) != active {
+            pwvucontrol_info!("{} playback streams", if active { "Ducking" } else { "Restoring" });
+            self.notify_active();
+        }
+
+        if imp.source.borrow().is_some() {
+            return;
+        }
+
+        let gain = imp.gain.get();
+        if gain == if active { self.floor() } else { 1.0 } {
+            // Duck streams that appeared after the ramp finished.
+            if gain < 1.0 {
+                self.apply_gain(gain);
+            }
+            return;
+        }
+
+        imp.last_tick.set(glib::monotonic_time());
+        let source = glib::timeout_add_local(TICK, clone!(@weak self as obj => @default-return ControlFlow::Break, move || obj.tick()));
+        imp.source.replace(Some(source));
+    }
+
+    /// Gain the streams are lowered to.
+    fn floor(&self) -> f32 {
+        1.0 - self.settings().uint("ducking-amount").min(100) as f32 / 100.0
+    }
+
+    fn tick(&self) -> ControlFlow {
+        let imp = self.imp();
+        let now = glib::monotonic_time();
+        let elapsed = (now - imp.last_tick.replace(now)) as f32 / 1000.0;
+
+        let floor = self.floor();
+        let (target, time_key) = if self.active() { (floor, "ducking-attack") } else { (1.0, "ducking-release") };
+        let ramp_time = self.settings().uint(time_key) as f32;
+
+        // Ramps linearly between no ducking and the floor in the attack or release time.
+        let gain = imp.gain.get();
+        let step = if ramp_time > 0.0 { (1.0 - floor).max(f32::EPSILON) * elapsed / ramp_time } else { f32::INFINITY };
+        let gain = if gain > target { (gain - step).max(target) } else { (gain + step).min(target) };
+        imp.gain.set(gain);
+
+        if gain >= 1.0 {
+            self.restore_volumes();
+        } else {
+            self.apply_gain(gain);
+        }
+
+        if gain == target {
+            imp.source.take();
+            return ControlFlow::Break;
+        }
+        ControlFlow::Continue
+    }
+
+    fn apply_gain(&self, gain: f32) {
+        let factor = gain.powi(3);
+        let all_streams: Vec<PwNodeObject> = self.streams().collect();
+        let (streams, others): (Vec<_>, Vec<_>) =
+            all_streams.iter().partition(|node| node.nodetype() == NodeType::StreamOutput && !self.is_trigger(node));
+
+        let mut ducked = self.imp().ducked.borrow_mut();
+        // Streams that became triggers are restored, those that went away are forgotten.
+        ducked.retain(|id, stream| {
+            let keep = streams.iter().any(|node| node.boundid() == *id);
+            if !keep && others.iter().any(|node| node.boundid() == *id) {
+                stream.node.set_volume(stream.original());
+            }
+            keep
+        });
+
+        for node in streams {
+            let stream = ducked.entry(node.boundid()).or_insert_with(|| DuckedStream {
+                node: node.clone(),
+                original: node.volume(),
+                applied: node.volume(),
+            });
+            stream.original = stream.original();
+            stream.applied = stream.original * factor;
+            if node.volume() != stream.applied {
+                node.set_volume(stream.applied);
+            }
+        }
+    }
+
+    /// Ends any ramp and puts the ducked streams back at their volume right away.
+    pub(crate) fn stop(&self) {
+        let imp = self.imp();
+        if let Some(source) = imp.source.take() {
+            source.remove();
+        }
+        imp.gain.set(1.0);
+        self.restore_volumes();
+    }
+
+    fn restore_volumes(&self) {
+        let ducked = self.imp().ducked.take();
+        for stream in ducked.into_values() {
+            stream.node.set_volume(stream.original());
+        }
+    }
+}
+
+impl Default for PwDucking {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::{Cell, RefCell};
+
+use gettextrs::gettext;
+use gtk::{
+    glib::{self, Properties},
+    prelude::*,
+    subclass::prelude::*
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, glib::Enum, Default)]
+#[enum_type(name = "EventCategory")]
+pub enum EventCategory {
+    #[default]
+    Node,
+    Device,
+    Default,
+    Target,
+    Profile,
+    Route,
+    Volume,
+}
+
+impl EventCategory {
+    pub const ALL: [EventCategory; 7] = [Self::Node, Self::Device, Self::Default, Self::Target, Self::Profile, Self::Route, Self::Volume];
+
+    pub fn title(&self) -> String {
+        match self {
+            EventCategory::Node => gettext("Node"),
+            EventCategory::Device => gettext("Device"),
+            EventCategory::Default => gettext("Default"),
+            EventCategory::Target => gettext("Target"),
+            EventCategory::Profile => gettext("Profile"),
+            EventCategory::Route => gettext("Port"),
+            EventCategory::Volume => gettext("Volume"),
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::PwEventObject)]
+    pub struct PwEventObject {
+        /// Local time of the event, `HH:MM:SS.mmm`.
+        #[property(get, set)]
+        time: RefCell<String>,
+        #[property(get, set, builder(EventCategory::Node))]
+        category: Cell<EventCategory>,
+        #[property(get, set)]
+        message: RefCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwEventObject {
+        const NAME: &'static str = "PwEventObject";
+        type Type = super::PwEventObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwEventObject {}
+
+    impl PwEventObject {}
+}
+
+glib::wrapper! {
+    pub struct PwEventObject(ObjectSubclass<imp::PwEventObject>);
+}
+
+impl PwEventObject {
+    pub(crate) fn new(category: EventCategory, message: &str) -> Self {
+        let time = glib::DateTime::now_local()
+            .ok()
+            .and_then(|now| now.format("%H:%M:%S").ok().map(|x| format!("{x}.{:03}", now.microsecond() / 1000)))
+            .unwrap_or_default();
+
+        glib::Object::builder()
+            .property("time", time)
+            .property("category", category)
+            .property("message", message)
+            .build()
+    }
+
+    /// One line of an exported log.
+    pub(crate) fn to_line(&self) -> String {
+        format!("{}\t{}\t{}", self.time(), self.category().title(), self.message())
+    }
+}
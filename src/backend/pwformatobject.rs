@@ -0,0 +1,252 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::{Cell, RefCell};
+
+use gettextrs::gettext;
+use gtk::{
+    glib::{self, Properties},
+    prelude::*,
+    subclass::prelude::*
+};
+use wireplumber as wp;
+use wp::spa::SpaPodBuilder;
+
+/// Rates offered when a node advertises a rate range instead of a list.
+const COMMON_RATES: [i32; 13] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 176400, 192000, 352800, 384000];
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::PwFormatObject)]
+    pub struct PwFormatObject {
+        #[property(get, set)]
+        description: RefCell<String>,
+        /// Short name of the media subtype, `raw` or `iec958`.
+        #[property(get, set)]
+        mediasubtype: RefCell<String>,
+        #[property(get, set)]
+        channels: Cell<u32>,
+
+        pub(super) positions: RefCell<Vec<u32>>,
+        pub(super) formats: RefCell<Vec<u32>>,
+        pub(super) rates: RefCell<Vec<u32>>,
+        pub(super) codecs: RefCell<Vec<u32>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwFormatObject {
+        const NAME: &'static str = "PwFormatObject";
+        type Type = super::PwFormatObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwFormatObject {}
+
+    impl PwFormatObject {}
+}
+
+glib::wrapper! {
+    pub struct PwFormatObject(ObjectSubclass<imp::PwFormatObject>);
+}
+
+impl PwFormatObject {
+    pub(crate) fn new(mediasubtype: &str, channels: u32, positions: &[u32], formats: &[u32], rates: &[u32], codecs: &[u32]) -> Self {
+        let description = if mediasubtype == "iec958" {
+            let t_codec = wp::spa::SpaIdTable::from_name("Spa:Enum:AudioIEC958Codec").expect("iec958 codec type");
+            let names: Vec<String> = codecs
+                .iter()
+                .filter_map(|codec| t_codec.find_value(*codec).and_then(|x| x.short_name()))
+                .map(|x| x.to_string())
+                .collect();
+            gettext("IEC958 passthrough ({})").replace("{}", &names.join(", "))
+        } else {
+            gettext("{channels}ch ({positions})")
+                .replace("{channels}", &channels.to_string())
+                .replace("{positions}", &channel_names(positions).join(","))
+        };
+
+        let new: PwFormatObject = glib::Object::builder()
+            .property("description", description)
+            .property("mediasubtype", mediasubtype)
+            .property("channels", channels)
+            .build();
+
+        let imp = new.imp();
+        imp.positions.replace(positions.to_vec());
+        imp.formats.replace(formats.to_vec());
+        imp.rates.replace(rates.to_vec());
+        imp.codecs.replace(codecs.to_vec());
+
+        new
+    }
+
+    /// Parses one `EnumFormat` param into one entry per channel layout it allows.
+    pub(crate) fn from_enum_format_pod(pod: &wp::spa::SpaPod) -> Vec<PwFormatObject> {
+        let keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Format").expect("id table");
+        let find = |name: &str| {
+            let key = keys.find_value_from_short_name(name).expect("format key");
+            pod.find_spa_property(&key)
+        };
+
+        let t_subtype = wp::spa::SpaIdTable::from_name("Spa:Enum:MediaSubtype").expect("media subtype type");
+        let mediasubtype = find("mediaSubtype")
+            .and_then(|x| pod_maybe_choice(x).id())
+            .and_then(|x| t_subtype.find_value(x))
+            .and_then(|x| x.short_name())
+            .map(|x| x.to_string())
+            .unwrap_or_default();
+
+        let formats: Vec<u32> = find("format").map(|x| choice_values(&x, &[])).unwrap_or_default()
+            .into_iter().map(|x| x as u32).collect();
+        let rates: Vec<u32> = find("rate").map(|x| choice_values(&x, &COMMON_RATES)).unwrap_or_default()
+            .into_iter().map(|x| x as u32).collect();
+        let codecs: Vec<u32> = find("iec958Codec").map(|x| choice_values(&x, &[])).unwrap_or_default()
+            .into_iter().map(|x| x as u32).collect();
+        let positions: Vec<u32> = find("position")
+            .map(|x| pod_maybe_choice(x).array_iterator().map(|x: i32| x as u32).collect())
+            .unwrap_or_default();
+
+        if mediasubtype == "iec958" {
+            return vec![PwFormatObject::new(&mediasubtype, 2, &default_positions(2), &formats, &rates, &codecs)];
+        }
+
+        let all_channels: Vec<i32> = (1..=64).collect();
+        let channels = find("channels").map(|x| choice_values(&x, &all_channels)).unwrap_or_default();
+
+        channels
+            .into_iter()
+            .map(|channels| {
+                let channels = channels as u32;
+                let positions = if positions.len() == channels as usize {
+                    positions.clone()
+                } else {
+                    default_positions(channels)
+                };
+                PwFormatObject::new(&mediasubtype, channels, &positions, &formats, &rates, &codecs)
+            })
+            .collect()
+    }
+
+    pub(crate) fn positions(&self) -> Vec<u32> {
+        self.imp().positions.borrow().clone()
+    }
+
+    pub(crate) fn formats(&self) -> Vec<u32> {
+        self.imp().formats.borrow().clone()
+    }
+
+    pub(crate) fn rates(&self) -> Vec<u32> {
+        self.imp().rates.borrow().clone()
+    }
+
+    /// Builds a `Format` object for use inside a `PortConfig` param.
+    pub(crate) fn build_format_pod(&self, format: Option<u32>, rate: Option<u32>) -> Option<wp::spa::SpaPod> {
+        let t_mediatype = wp::spa::SpaIdTable::from_name("Spa:Enum:MediaType").expect("media type");
+        let t_subtype = wp::spa::SpaIdTable::from_name("Spa:Enum:MediaSubtype").expect("media subtype type");
+
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Format", "Format");
+
+        podbuilder.add_property("mediaType");
+        podbuilder.add_id(t_mediatype.find_value_from_short_name("audio").expect("audio media type").number());
+        podbuilder.add_property("mediaSubtype");
+        podbuilder.add_id(t_subtype.find_value_from_short_name(&self.mediasubtype()).expect("media subtype").number());
+
+        if let Some(format) = format {
+            podbuilder.add_property("format");
+            podbuilder.add_id(format);
+        }
+
+        if let Some(rate) = rate {
+            podbuilder.add_property("rate");
+            podbuilder.add_int(rate as i32);
+        }
+
+        if let Some(codec) = self.imp().codecs.borrow().first() {
+            podbuilder.add_property("iec958Codec");
+            podbuilder.add_id(*codec);
+        }
+
+        podbuilder.add_property("channels");
+        podbuilder.add_int(self.channels() as i32);
+
+        let array = SpaPodBuilder::new_array();
+        for position in self.imp().positions.borrow().iter() {
+            array.add_id(*position);
+        }
+        if let Some(arraypod) = array.end() {
+            podbuilder.add_property("position");
+            podbuilder.add_pod(&arraypod);
+        }
+
+        podbuilder.end()
+    }
+}
+
+fn pod_maybe_choice(pod: wp::spa::SpaPod) -> wp::spa::SpaPod {
+    if pod.is_choice() {
+        pod.choice_child().unwrap()
+    } else {
+        pod
+    }
+}
+
+/// Returns the values a property allows. Ranges are expanded to the `candidates` that fall inside them.
+fn choice_values(pod: &wp::spa::SpaPod, candidates: &[i32]) -> Vec<i32> {
+    if !pod.is_choice() {
+        return pod.int().or_else(|| pod.id().map(|x| x as i32)).into_iter().collect();
+    }
+
+    let values: Vec<i32> = pod.array_iterator().collect();
+    let choice_type = pod.choice_type().and_then(|x| x.short_name());
+
+    let mut result: Vec<i32> = match (choice_type.as_deref(), values.as_slice()) {
+        (Some("Range") | Some("Step"), [_, min, max, ..]) => {
+            candidates.iter().copied().filter(|x| x >= min && x <= max).collect()
+        },
+        (Some("Enum"), [_, alternatives @ ..]) => alternatives.to_vec(),
+        (_, [default, ..]) => vec![*default],
+        _ => vec![],
+    };
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+/// Channel layout used when a node only advertises a channel count.
+fn default_positions(channels: u32) -> Vec<u32> {
+    let names: &[&str] = match channels {
+        1 => &["MONO"],
+        2 => &["FL", "FR"],
+        3 => &["FL", "FR", "LFE"],
+        4 => &["FL", "FR", "RL", "RR"],
+        5 => &["FL", "FR", "FC", "RL", "RR"],
+        6 => &["FL", "FR", "FC", "LFE", "RL", "RR"],
+        8 => &["FL", "FR", "FC", "LFE", "RL", "RR", "SL", "SR"],
+        _ => &[],
+    };
+
+    let t_audiochannel = wp::spa::SpaIdTable::from_name("Spa:Enum:AudioChannel").expect("audio channel type");
+    if names.is_empty() {
+        return (0..channels)
+            .filter_map(|i| t_audiochannel.find_value_from_short_name(&format!("AUX{i}")))
+            .map(|x| x.number())
+            .collect();
+    }
+
+    names
+        .iter()
+        .filter_map(|name| t_audiochannel.find_value_from_short_name(name))
+        .map(|x| x.number())
+        .collect()
+}
+
+/// Short names, like `FL`, of channel positions.
+pub(crate) fn channel_names(positions: &[u32]) -> Vec<String> {
+    let t_audiochannel = wp::spa::SpaIdTable::from_name("Spa:Enum:AudioChannel").expect("audio channel type");
+    positions
+        .iter()
+        .filter_map(|position| t_audiochannel.find_value(*position).and_then(|x| x.short_name()))
+        .map(|x| x.to_string())
+        .collect()
+}
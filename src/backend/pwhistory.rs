@@ -0,0 +1,348 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::{clone, Properties};
+use gtk::{prelude::*, subclass::prelude::*};
+use std::cell::{Cell, RefCell};
+
+use super::{NodeType, PwDeviceObject, PwNodeObject, PwRouteObject, PwvucontrolManager};
+use crate::macros::*;
+
+/// Steps kept for undo.
+const MAX_STEPS: usize = 100;
+/// Changes of the same control closer together than this, in microseconds, form a single step.
+const COALESCE_TIME: i64 = 1_000_000;
+
+/// A change made from the UI, with the state before and after it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum HistoryChange {
+    Volume { node: u32, old: f32, new: f32 },
+    Mute { node: u32, old: bool, new: bool },
+    ChannelVolumes { node: u32, old: Vec<f32>, new: Vec<f32> },
+    /// Explicit target of a stream, `None` for the default node.
+    Target { node: u32, old: Option<u32>, new: Option<u32> },
+    /// Configured default node name, empty when unset.
+    Default { nodetype: NodeType, old: String, new: String },
+    Profile { device: u32, old: i32, new: i32 },
+    Route { device: u32, card_profile_device: i32, old: u32, new: u32 },
+    /// Changes made by a single action, like dragging the slider of an application group.
+    Batch(Vec<HistoryChange>),
+}
+
+/// A node setting a user action can change, for recording it with `PwHistory::record_nodes`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum NodeControl {
+    Volume,
+    Mute,
+    ChannelVolumes,
+    Target,
+}
+
+impl NodeControl {
+    /// A change of the control from and to its current value, to be completed with `change_since`.
+    fn snapshot(self, node: &PwNodeObject) -> HistoryChange {
+        let node_id = node.boundid();
+        match self {
+            NodeControl::Volume => HistoryChange::Volume { node: node_id, old: node.volume(), new: node.volume() },
+            NodeControl::Mute => HistoryChange::Mute { node: node_id, old: node.mute(), new: node.mute() },
+            NodeControl::ChannelVolumes => HistoryChange::ChannelVolumes {
+                node: node_id,
+                old: node.channel_volumes_vec(),
+                new: node.channel_volumes_vec(),
+            },
+            NodeControl::Target => {
+                let target = node.default_target().map(|x| x.boundid());
+                HistoryChange::Target { node: node_id, old: target, new: target }
+            },
+        }
+    }
+
+    /// The change from the value in `snapshot` to the current one.
+    fn change_since(self, node: &PwNodeObject, snapshot: HistoryChange) -> HistoryChange {
+        match (snapshot, self.snapshot(node)) {
+            (HistoryChange::Volume { node, old, .. }, HistoryChange::Volume { new, .. }) => HistoryChange::Volume { node, old, new },
+            (HistoryChange::Mute { node, old, .. }, HistoryChange::Mute { new, .. }) => HistoryChange::Mute { node, old, new },
+            (HistoryChange::ChannelVolumes { node, old, .. }, HistoryChange::ChannelVolumes { new, .. }) => {
+                HistoryChange::ChannelVolumes { node, old, new }
+            },
+            (HistoryChange::Target { node, old, .. }, HistoryChange::Target { new, .. }) => HistoryChange::Target { node, old, new },
+            (snapshot, _) => snapshot,
+        }
+    }
+}
+
+impl HistoryChange {
+    /// Selecting a profile on a device.
+    pub(crate) fn profile(device: &PwDeviceObject, index: i32) -> Self {
+        HistoryChange::Profile {
+            device: device.wpdevice().bound_id(),
+            old: device.profile_index() as i32,
+            new: index,
+        }
+    }
+
+    /// Selecting a port on the card profile device of a device, if another port is active in its direction.
+    pub(crate) fn route(device: &PwDeviceObject, index: u32, card_profile_device: i32) -> Option<Self> {
+        let old = [device.routemodel_output(), device.routemodel_input()]
+            .iter()
+            .flat_map(|model| model.iter::<PwRouteObject>().map_while(Result::ok).collect::<Vec<_>>())
+            .find(|route| route.index() == index)
+            .and_then(|route| device.active_route(route.direction()))?;
+
+        Some(HistoryChange::Route {
+            device: device.wpdevice().bound_id(),
+            card_profile_device,
+            old: old.index(),
+            new: index,
+        })
+    }
+
+    /// Selecting a port through one of the nodes of a device, see `PwNodeObject::set_route`.
+    pub(crate) fn node_route(node: &PwNodeObject, route: &PwRouteObject) -> Option<Self> {
+        let device = node.get_device()?;
+        let card_profile_device = node.wpnode().device_index().ok().flatten()?;
+
+        let mut changes: Vec<HistoryChange> = HistoryChange::route(&device, route.index(), card_profile_device as i32).into_iter().collect();
+        let profiles = route.get_profiles();
+        if !profiles.is_empty() && !profiles.contains(&device.profile_index()) {
+            changes.push(HistoryChange::profile(&device, profiles[0] as i32));
+        }
+
+        Some(HistoryChange::Batch(changes))
+    }
+
+    /// Configuring the default sink or source by node name, empty to unset it.
+    pub(crate) fn default_node(nodetype: NodeType, node_name: &str) -> Self {
+        let old = PwvucontrolManager::default().default_configured_node_name(nodetype);
+        HistoryChange::Default { nodetype, old, new: node_name.to_string() }
+    }
+
+    /// Slider drags are merged into the previous change of the same control.
+    fn coalesce(&mut self, other: &HistoryChange) -> bool {
+        match (self, other) {
+            (HistoryChange::Batch(changes), HistoryChange::Batch(other_changes)) => {
+                let mergeable = changes.len() == other_changes.len()
+                    && changes.iter().zip(other_changes).all(|(change, other)| change.clone().coalesce(other));
+                if mergeable {
+                    for (change, other) in changes.iter_mut().zip(other_changes) {
+                        change.coalesce(other);
+                    }
+                }
+                mergeable
+            },
+            (HistoryChange::Volume { node, new, .. }, HistoryChange::Volume { node: other_node, new: other_new, .. }) if node == other_node => {
+                *new = *other_new;
+                true
+            },
+            (HistoryChange::ChannelVolumes { node, new, .. }, HistoryChange::ChannelVolumes { node: other_node, new: other_new, .. })
+                if node == other_node =>
+            {
+                new.clone_from(other_new);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn is_noop(&self) -> bool {
+        match self {
+            HistoryChange::Volume { old, new, .. } => old == new,
+            HistoryChange::Mute { old, new, .. } => old == new,
+            HistoryChange::ChannelVolumes { old, new, .. } => old == new,
+            HistoryChange::Target { old, new, .. } => old == new,
+            HistoryChange::Default { old, new, .. } => old == new,
+            HistoryChange::Profile { old, new, .. } => old == new,
+            HistoryChange::Route { old, new, .. } => old == new,
+            HistoryChange::Batch(changes) => changes.iter().all(HistoryChange::is_noop),
+        }
+    }
+
+    /// Re-applies the state before (`undo`) or after the change.
+    fn apply(&self, undo: bool) {
+        let manager = PwvucontrolManager::default();
+        let pick = |old, new| if undo { old } else { new };
+
+        match self {
+            HistoryChange::Volume { node, old, new } => {
+                if let Some(node) = manager.get_node_by_id(*node) {
+                    node.set_volume(pick(*old, *new));
+                }
+            },
+            HistoryChange::Mute { node, old, new } => {
+                if let Some(node) = manager.get_node_by_id(*node) {
+                    node.set_mute(pick(*old, *new));
+                }
+            },
+            HistoryChange::ChannelVolumes { node, old, new } => {
+                if let Some(node) = manager.get_node_by_id(*node) {
+                    node.set_channel_volumes_vec(pick(old, new));
+                }
+            },
+            HistoryChange::Target { node, old, new } => {
+                let Some(node) = manager.get_node_by_id(*node) else {
+                    return;
+                };
+                match pick(old, new).and_then(|target| manager.get_node_by_id(target)) {
+                    Some(target) => node.set_default_target(&target),
+                    None => node.unset_default_target(),
+                }
+            },
+            HistoryChange::Default { nodetype, old, new } => {
+                manager.set_default_configured_node_name(*nodetype, pick(old, new));
+            },
+            HistoryChange::Profile { device, old, new } => {
+                if let Some(device) = manager.get_device_by_id(*device) {
+                    device.set_profile(pick(*old, *new));
+                }
+            },
+            HistoryChange::Route { device, card_profile_device, old, new } => {
+                if let Some(device) = manager.get_device_by_id(*device) {
+                    device.set_route(pick(*old, *new), *card_profile_device);
+                }
+            },
+            HistoryChange::Batch(changes) => {
+                if undo {
+                    changes.iter().rev().for_each(|change| change.apply(true));
+                } else {
+                    changes.iter().for_each(|change| change.apply(false));
+                }
+            },
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties)]
+    #[properties(wrapper_type = super::PwHistory)]
+    pub struct PwHistory {
+        #[property(get)]
+        pub(super) can_undo: Cell<bool>,
+        #[property(get)]
+        pub(super) can_redo: Cell<bool>,
+
+        /// Changes with the monotonic time they were last extended, oldest first.
+        pub(super) undo: RefCell<Vec<(HistoryChange, i64)>>,
+        pub(super) redo: RefCell<Vec<HistoryChange>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwHistory {
+        const NAME: &'static str = "PwHistory";
+        type Type = super::PwHistory;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwHistory {}
+}
+
+glib::wrapper! {
+    /// Undo and redo of mixer changes made from the UI.
+    pub struct PwHistory(ObjectSubclass<imp::PwHistory>);
+}
+
+impl PwHistory {
+    pub(crate) fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Records a change the user made as a step. Only changes made by the UI are recorded, the
+    /// setters of the backend objects do not record anything.
+    pub(crate) fn record(&self, change: HistoryChange) {
+        let change = match change {
+            HistoryChange::Batch(changes) => {
+                let mut changes: Vec<HistoryChange> = changes.into_iter().filter(|change| !change.is_noop()).collect();
+                if changes.len() == 1 {
+                    changes.remove(0)
+                } else {
+                    HistoryChange::Batch(changes)
+                }
+            },
+            change => change,
+        };
+        if change.is_noop() {
+            return;
+        }
+
+        let imp = self.imp();
+        let now = glib::monotonic_time();
+        {
+            let mut undo = imp.undo.borrow_mut();
+            match undo.last_mut() {
+                Some((last, time)) if now - *time < COALESCE_TIME && last.coalesce(&change) => {
+                    *time = now;
+                },
+                _ => {
+                    undo.push((change, now));
+                    if undo.len() > MAX_STEPS {
+                        undo.remove(0);
+                    }
+                },
+            }
+        }
+        imp.redo.borrow_mut().clear();
+
+        self.update_state();
+    }
+
+    /// Records what `f` changes of a control on `nodes` as a single step.
+    pub(crate) fn record_nodes(&self, control: NodeControl, nodes: &[PwNodeObject], f: impl FnOnce()) {
+        let snapshots: Vec<HistoryChange> = nodes.iter().map(|node| control.snapshot(node)).collect();
+        f();
+        let changes = nodes.iter().zip(snapshots).map(|(node, snapshot)| control.change_since(node, snapshot)).collect();
+        self.record(HistoryChange::Batch(changes));
+    }
+
+    /// Records what a slider changes of a control on `nodes` as a single step. Call it from the
+    /// `change-value` handler, the change is recorded once the new value reached the nodes.
+    pub(crate) fn record_nodes_later(&self, control: NodeControl, nodes: &[PwNodeObject]) {
+        let snapshots: Vec<(PwNodeObject, HistoryChange)> = nodes.iter().map(|node| (node.clone(), control.snapshot(node))).collect();
+        glib::idle_add_local_once(clone!(@weak self as history => move || {
+            let changes = snapshots.into_iter().map(|(node, snapshot)| control.change_since(&node, snapshot)).collect();
+            history.record(HistoryChange::Batch(changes));
+        }));
+    }
+
+    pub(crate) fn undo(&self) {
+        let Some((change, _)) = self.imp().undo.borrow_mut().pop() else {
+            return;
+        };
+
+        pwvucontrol_info!("Undo {change:?}");
+        change.apply(true);
+        self.imp().redo.borrow_mut().push(change);
+        self.update_state();
+    }
+
+    pub(crate) fn redo(&self) {
+        let Some(change) = self.imp().redo.borrow_mut().pop() else {
+            return;
+        };
+
+        pwvucontrol_info!("Redo {change:?}");
+        change.apply(false);
+        // A redone step is never merged with the next change.
+        self.imp().undo.borrow_mut().push((change, 0));
+        self.update_state();
+    }
+
+    fn update_state(&self) {
+        let imp = self.imp();
+
+        let can_undo = !imp.undo.borrow().is_empty();
+        if imp.can_undo.replace(can_undo) != can_undo {
+            self.notify_can_undo();
+        }
+
+        let can_redo = !imp.redo.borrow().is_empty();
+        if imp.can_redo.replace(can_redo) != can_redo {
+            self.notify_can_redo();
+        }
+    }
+}
+
+impl Default for PwHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
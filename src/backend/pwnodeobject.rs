@@ -6,16 +6,23 @@ use wp::{
     spa::SpaPodBuilder,
     registry::{Constraint, ConstraintType, Interest}
 };
-use std::cell::{Cell, RefCell};
+use std::{cell::{Cell, RefCell}, time::Duration};
 use glib::{self, clone, subclass::{prelude::*, Signal}, ObjectExt, ParamSpec, Properties, Value, CastNone, Cast};
 use once_cell::sync::{Lazy, OnceCell};
 use gtk::{gio, prelude::ListModelExt};
-use super::{PwDeviceObject, PwRouteObject, PwChannelObject, PwvucontrolManager};
+use super::{customnames::{self, CustomNameKind}, networksessions, PwDeviceObject, PwFormatObject, PwPropInfoObject, PwRouteObject, PwChannelObject, PwvucontrolManager, RouteDirection};
 use wp::registry::ObjectManager;
 
 use crate::macros::*;
 
+/// Spin button steps are collected for this long before the latency offset is sent.
+const LATENCY_OFFSET_DELAY: Duration = Duration::from_millis(300);
+
 mod mixerapi;
+mod latency;
+mod props;
+mod volumelog;
+mod appinfo;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default, glib::Enum)]
 #[enum_type(name = "NodeType")]
@@ -64,10 +71,37 @@ pub mod imp {
         monitorvolume: Cell<f32>,
         #[property(get, set)]
         mute: Cell<bool>,
+        /// Whether the node is running, i.e. playing or recording.
+        #[property(get)]
+        running: Cell<bool>,
         #[property(get, set)]
         iconname: RefCell<String>,
         #[property(get, set)]
         hidden: Cell<bool>,
+        /// Whether the name is a custom description set by the user.
+        #[property(get, set)]
+        renamed: Cell<bool>,
+        /// Whether the node sends or receives audio over the network.
+        #[property(get, set)]
+        network: Cell<bool>,
+        /// Sandbox the application runs in, "Flatpak" or "Snap", empty if none.
+        #[property(get, set)]
+        sandbox: RefCell<String>,
+        #[property(get, set)]
+        latencystr: RefCell<String>,
+        /// Effective latency in milliseconds.
+        #[property(get, set)]
+        latency: Cell<f64>,
+        /// Latency added by ProcessLatency, in milliseconds.
+        #[property(get, set)]
+        processlatency: Cell<f64>,
+        /// Latency offset (latencyOffsetNsec) of device nodes, in milliseconds.
+        #[property(get, set)]
+        latencyoffset: Cell<f64>,
+        /// Pending send of the latency offset, see `schedule_latencyoffset`.
+        pub(super) latencyoffset_source: RefCell<Option<glib::SourceId>>,
+        /// Driver and requested `node.latency` in seconds last seen, see `update_driver`.
+        pub(super) scheduling: Cell<(Option<u32>, Option<f64>)>,
 
         pub(super) channel_volumes: RefCell<Vec<f32>>,
         #[property(get, builder(NodeType::Undefined))]
@@ -76,6 +110,13 @@ pub mod imp {
         #[property(get)]
         pub(super) channelmodel: RefCell<gio::ListStore>,
 
+        #[property(get)]
+        pub(super) formatmodel: gio::ListStore,
+
+        /// Props described by `PropInfo` that have no dedicated control.
+        #[property(get)]
+        pub(super) propinfomodel: gio::ListStore,
+
         pub(super) format: Cell<Option<AudioFormat>>,
 
         #[property(get, set)]
@@ -86,6 +127,11 @@ pub mod imp {
 
         pub(super) block: Cell<bool>,
 
+        /// Volume and mute state last written to the event log.
+        pub(super) logged_volume: Cell<(f32, bool)>,
+        /// Pending event log entry with the origin of the change.
+        pub(super) volume_log: RefCell<Option<(glib::SourceId, volumelog::VolumeOrigin)>>,
+
         pub(super) om: RefCell<ObjectManager>,
     }
 
@@ -100,16 +146,30 @@ pub mod imp {
                 volume: Default::default(),
                 monitorvolume: Default::default(),
                 mute: Default::default(),
+                running: Default::default(),
                 iconname: Default::default(),
                 channel_volumes: Default::default(),
                 nodetype: Default::default(),
                 channelmodel: RefCell::new(gio::ListStore::new::<PwChannelObject>()),
+                formatmodel: gio::ListStore::new::<PwFormatObject>(),
+                propinfomodel: gio::ListStore::new::<PwPropInfoObject>(),
                 format: Default::default(),
                 channellock: Default::default(),
                 wpnode: OnceCell::default(),
                 block: Default::default(),
+                logged_volume: Default::default(),
+                volume_log: Default::default(),
                 om: Default::default(),
                 hidden: Default::default(),
+                renamed: Default::default(),
+                network: Default::default(),
+                sandbox: Default::default(),
+                latencystr: Default::default(),
+                latency: Default::default(),
+                processlatency: Default::default(),
+                latencyoffset: Default::default(),
+                latencyoffset_source: Default::default(),
+                scheduling: Default::default(),
             }
         }
     }
@@ -131,11 +191,13 @@ pub mod imp {
                 "volume" => {
                     if !self.block.get() {
                         self.obj().send_volume_using_mixerapi(PropertyChanged::Volume);
+                        self.obj().schedule_volume_log(volumelog::VolumeOrigin::Local);
                     }
                 },
                 "mute" => {
                     if !self.block.get() {
                         self.obj().send_volume_using_mixerapi(PropertyChanged::Mute);
+                        self.obj().schedule_volume_log(volumelog::VolumeOrigin::Local);
                     }
                 },
                 "mainvolume" => {
@@ -148,6 +210,11 @@ pub mod imp {
                         self.obj().send_monitorvolume();
                     }
                 },
+                "latencyoffset" => {
+                    if !self.block.get() {
+                        self.obj().schedule_latencyoffset();
+                    }
+                },
                 _ => {},
             }
         }
@@ -175,11 +242,23 @@ pub mod imp {
 
             self.nodetype.set(get_node_type_for_node(node));
             self.boundid.set(node.bound_id());
+            self.network.set(
+                node.pw_property::<String>("node.network").is_ok_and(|x| x == "true")
+                    || node.pw_property::<String>("node.name").is_ok_and(|x| x.starts_with(networksessions::NODE_PREFIX)),
+            );
 
             node.connect_notify_local(
                 Some("global-properties"),
                 clone!(@weak obj => move  |_, _| {
-                    obj.label_set_name()
+                    obj.label_set_name();
+                    obj.update_latency();
+                }),
+            );
+
+            node.connect_notify_local(
+                Some("state"),
+                clone!(@weak obj => move  |_, _| {
+                    obj.update_running();
                 }),
             );
 
@@ -187,6 +266,8 @@ pub mod imp {
                 Some("properties"),
                 clone!(@weak obj => move  |_, _| {
                     obj.label_set_description();
+                    // The driver of the node is among its properties.
+                    obj.update_latency();
                 }),
             );
 
@@ -195,19 +276,30 @@ pub mod imp {
                 obj.imp().block.set(true);
                 match what {
                     "Props" => obj.update_props(),
+                    "PropInfo" => obj.update_prop_info(),
                     "Format" => obj.update_format(),
+                    "EnumFormat" => obj.update_enum_formats(),
+                    "Latency" | "ProcessLatency" => obj.update_latency(),
                     _ => {},
                 }
                 obj.imp().block.set(false);
             }));
 
             obj.label_set_description();
+            obj.update_running();
             obj.update_props();
+            obj.update_prop_info();
             obj.update_format();
+            obj.update_enum_formats();
+            obj.update_latency();
             obj.label_set_name();
 
             obj.get_mixer_api();
+            self.block.set(true);
             obj.update_volume_using_mixerapi();
+            self.block.set(false);
+            obj.reset_volume_log();
+            obj.update_sandbox();
             obj.update_icon_name();
 
             let om = self.om.borrow();
@@ -276,19 +368,48 @@ impl PwNodeObject {
             _ => {
                 props
                 .get("node.nick")
+                .or_else(|| self.app_info().map(|info| info.display_name()))
                 .or_else(|| props.get("node.description"))
                 .or_else(|| props.get("node.name"))
             }
         };
 
-        let name = name_gstr
-            .as_ref()
-            .map(|name| name.as_str())
+        let custom = match self.nodetype() {
+            NodeType::Sink | NodeType::Source => props
+                .get("node.name")
+                .and_then(|name| customnames::custom_description(CustomNameKind::Node, name.as_str())),
+            _ => None,
+        };
+        self.set_renamed(custom.is_some());
+
+        let name = custom
+            .as_deref()
+            .or_else(|| name_gstr.as_ref().map(|name| name.as_str()))
             .unwrap_or_default();
 
         self.set_name(name);
     }
 
+    /// Gives a sink or source a persistent custom description. `None` restores the original one.
+    pub(crate) fn rename(&self, description: Option<&str>) {
+        let Ok(name) = self.wpnode().pw_property::<String>("node.name") else {
+            return;
+        };
+
+        customnames::set_custom_description(CustomNameKind::Node, &name, description);
+        self.label_set_name();
+
+        PwvucontrolManager::default().emit_by_name::<()>("node-renamed", &[&name]);
+    }
+
+    fn update_running(&self) {
+        let running = self.wpnode().property::<wp::pw::NodeState>("state") == wp::pw::NodeState::Running;
+        if self.running() != running {
+            self.imp().running.set(running);
+            self.notify_running();
+        }
+    }
+
     fn label_set_description(&self) {
         let wp_node = self
             .imp()
@@ -310,13 +431,14 @@ impl PwNodeObject {
     fn update_icon_name(&self) {
         match self.nodetype() {
             NodeType::StreamInput | NodeType::StreamOutput => {
-                self.set_iconname("library-music-symbolic");
                 let icon_props = ["media.icon-name", "window.icon-name", "application.icon-name"];
-                for prop in icon_props {
-                    if let Ok(appid) = self.wpnode().pw_property::<String>(prop) {
-                        self.set_iconname(appid);
-                    }
-                }
+                let icon = icon_props
+                    .iter()
+                    .rev()
+                    .find_map(|prop| self.wpnode().pw_property::<String>(prop).ok())
+                    .or_else(|| self.app_info().as_ref().and_then(appinfo::app_icon_name))
+                    .unwrap_or_else(|| "library-music-symbolic".to_string());
+                self.set_iconname(icon);
             },
             NodeType::Source | NodeType::Sink => {
                 self.set_iconname("soundcard-symbolic");
@@ -388,6 +510,7 @@ impl PwNodeObject {
 
                     // Must be done here since EnumFormat is async
                     widget.update_channelmodel();
+                    widget.update_latency();
                 }
             } else {
                 pwvucontrol_debug!("enum_params async call didn't return anything useful");
@@ -396,6 +519,71 @@ impl PwNodeObject {
         }));
     }
 
+    pub(crate) fn update_enum_formats(&self) {
+        if !matches!(self.nodetype(), NodeType::Sink | NodeType::Source) {
+            return;
+        }
+
+        let node = self.imp().wpnode.get().expect("node");
+
+        node.enum_params(Some("EnumFormat"), None, gtk::gio::Cancellable::NONE, clone!(@weak self as widget => move |res| {
+            if let Ok(Some(iter)) = res {
+                let mut formats: Vec<PwFormatObject> = Vec::new();
+
+                for a in iter {
+                    let pod: wp::spa::SpaPod = a.get().unwrap();
+                    if !pod.is_object() {
+                        continue;
+                    }
+
+                    formats.extend(PwFormatObject::from_enum_format_pod(&pod));
+                }
+
+                let formatmodel = widget.formatmodel();
+                formatmodel.splice(0, formatmodel.n_items(), &formats);
+            } else if let Err(e) = res {
+                pwvucontrol_warning!("Cannot enumerate formats: {e}");
+            }
+        }));
+    }
+
+    /// Reconfigures the ports of a device node, e.g. to switch between DSP and passthrough mode
+    /// or to change the number of channels exposed.
+    pub(crate) fn set_port_config(&self, mode: &str, format: &PwFormatObject, sample_format: Option<u32>, rate: Option<u32>) {
+        let node = self.imp().wpnode.get().expect("WpNode set");
+
+        let direction = match self.nodetype() {
+            NodeType::Sink => "Input",
+            NodeType::Source => "Output",
+            _ => {
+                pwvucontrol_warning!("PortConfig can only be set on device nodes");
+                return;
+            }
+        };
+
+        let t_direction = wp::spa::SpaIdTable::from_name("Spa:Enum:Direction").expect("direction type");
+        let t_mode = wp::spa::SpaIdTable::from_name("Spa:Enum:ParamPortConfigMode").expect("port config mode type");
+
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:PortConfig", "PortConfig");
+
+        podbuilder.add_property("direction");
+        podbuilder.add_id(t_direction.find_value_from_short_name(direction).expect("direction").number());
+        podbuilder.add_property("mode");
+        podbuilder.add_id(t_mode.find_value_from_short_name(mode).expect("port config mode").number());
+        podbuilder.add_property("monitor");
+        podbuilder.add_boolean(self.nodetype() == NodeType::Sink);
+
+        if let Some(formatpod) = format.build_format_pod(sample_format, rate) {
+            podbuilder.add_property("format");
+            podbuilder.add_pod(&formatpod);
+        }
+
+        if let Some(pod) = podbuilder.end() {
+            pwvucontrol_info!("Setting PortConfig mode {mode} with {} on {}", format.description(), self.name());
+            node.set_param("PortConfig", 0, pod);
+        }
+    }
+
     pub(crate) fn update_props(&self) {
         let node = self.imp().wpnode.get().expect("node");
 
@@ -406,10 +594,13 @@ impl PwNodeObject {
         let keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Props").expect("id table");
         let volume_key = keys.find_value_from_short_name("volume").expect("volume key");
         let monitorvolumes_key = keys.find_value_from_short_name("monitorVolumes").expect("monitorVolumes key");
+        let latency_offset_key = keys.find_value_from_short_name("latencyOffsetNsec").expect("latencyOffsetNsec key");
 
         for a in params {
             let pod: wp::spa::SpaPod = a.get().unwrap();
             if pod.is_object() {
+                self.update_prop_values(&pod);
+
                 if let Some(val) = pod.find_spa_property(&volume_key) {
                     if let Some(volume) = val.float() {
                         self.set_mainvolume(volume);
@@ -422,6 +613,14 @@ impl PwNodeObject {
                         self.set_monitorvolume(volume.unwrap_or_default());
                     }
                 }
+
+                if let Some(val) = pod.find_spa_property(&latency_offset_key) {
+                    if let Some(offset) = val.long() {
+                        let blocked = self.imp().block.replace(true);
+                        self.set_latencyoffset(offset as f64 / 1_000_000.0);
+                        self.imp().block.set(blocked);
+                    }
+                }
             }
         }
     }
@@ -440,6 +639,47 @@ impl PwNodeObject {
     }
 
 
+    /// Sends the latency offset once it stopped changing, instead of for every step of the spin button.
+    fn schedule_latencyoffset(&self) {
+        let source = glib::timeout_add_local_once(LATENCY_OFFSET_DELAY, clone!(@weak self as obj => move || {
+            obj.imp().latencyoffset_source.take();
+            obj.send_latencyoffset();
+        }));
+        if let Some(old) = self.imp().latencyoffset_source.replace(Some(source)) {
+            old.remove();
+        }
+    }
+
+    /// Sends the latency offset to the active route if the node belongs to a device, so the
+    /// session manager remembers it for that port, otherwise directly to the node.
+    fn send_latencyoffset(&self) {
+        let offset_ns = (self.latencyoffset() * 1_000_000.0) as i64;
+
+        let direction = match self.nodetype() {
+            NodeType::Sink => RouteDirection::Output,
+            NodeType::Source => RouteDirection::Input,
+            _ => RouteDirection::Unknown,
+        };
+
+        if let (Some(device), Ok(Some(card_profile_device))) = (self.get_device(), self.wpnode().device_index()) {
+            if let Some(route) = device.active_route(direction) {
+                route.set_latencyoffset(self.latencyoffset());
+                device.set_route_latency_offset(route.index(), card_profile_device as i32, offset_ns);
+                return;
+            }
+        }
+
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Props", "Props");
+        let node = self.imp().wpnode.get().expect("WpNode set");
+
+        podbuilder.add_property("latencyOffsetNsec");
+        podbuilder.add_long(offset_ns);
+
+        if let Some(pod) = podbuilder.end() {
+            node.set_param("Props", 0, pod);
+        }
+    }
+
     fn send_monitorvolume(&self) {
         let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Props", "Props");
         let node = self.imp().wpnode.get().expect("WpNode set");
@@ -620,7 +860,7 @@ impl PwNodeObject {
     }
 }
 
-trait MetadataExtFix: 'static {
+pub trait MetadataExtFix: 'static {
     fn find_notype(&self, subject: u32, key: &str) -> Option<glib::GString>;
 }
 
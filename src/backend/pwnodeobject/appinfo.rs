@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{cell::RefCell, collections::HashMap, path::Path};
+
+use gtk::{gio, prelude::*};
+use wireplumber as wp;
+use wp::pw::PipewireObjectExt2;
+
+use super::*;
+
+thread_local! {
+    /// Desktop entry lookups by desktop file id or binary name, including misses.
+    static CACHE: RefCell<HashMap<String, Option<gio::DesktopAppInfo>>> = RefCell::new(HashMap::new());
+}
+
+/// Sandbox names shown in the badge of a stream. Brand names, not translated.
+const FLATPAK: &str = "Flatpak";
+const SNAP: &str = "Snap";
+
+impl PwNodeObject {
+    /// The installed desktop entry of the application owning a stream, if one can be found.
+    pub(crate) fn app_info(&self) -> Option<gio::DesktopAppInfo> {
+        if !matches!(self.nodetype(), NodeType::StreamInput | NodeType::StreamOutput) {
+            return None;
+        }
+
+        let wpnode = self.wpnode();
+        let property = |key: &str| wpnode.pw_property::<String>(key).ok().filter(|x| !x.is_empty());
+
+        // Snap desktop files are named after the snap and the app within it.
+        let snap_id = property("pipewire.snap.id")
+            .map(|snap| format!("{snap}_{}", property("pipewire.snap.app.id").unwrap_or_else(|| snap.clone())));
+
+        let ids = [
+            property("pipewire.access.portal.app_id"),
+            property("application.flatpak.id"),
+            snap_id,
+            property("application.id"),
+        ];
+        if let Some(info) = ids.into_iter().flatten().find_map(|id| lookup_desktop_id(&id)) {
+            return Some(info);
+        }
+
+        property("application.process.binary").and_then(|binary| lookup_binary(&binary))
+    }
+
+    /// Which sandbox the application of a stream runs in, empty when it is not sandboxed.
+    pub(crate) fn update_sandbox(&self) {
+        let wpnode = self.wpnode();
+        let property = |key: &str| wpnode.pw_property::<String>(key).ok();
+
+        let sandbox = if property("pipewire.access").is_some_and(|x| x == "flatpak")
+            || property("pipewire.access.portal.app_id").is_some()
+            || property("application.flatpak.id").is_some()
+        {
+            FLATPAK
+        } else if property("pipewire.snap.id").is_some() {
+            SNAP
+        } else {
+            ""
+        };
+
+        self.set_sandbox(sandbox);
+    }
+}
+
+/// The name of a themed icon of a desktop entry. File icons can't be shown by name.
+pub(crate) fn app_icon_name(info: &gio::DesktopAppInfo) -> Option<String> {
+    info.icon()
+        .and_downcast::<gio::ThemedIcon>()
+        .and_then(|icon| icon.names().first().map(|name| name.to_string()))
+}
+
+fn cached(key: String, lookup: impl FnOnce() -> Option<gio::DesktopAppInfo>) -> Option<gio::DesktopAppInfo> {
+    if let Some(info) = CACHE.with(|cache| cache.borrow().get(&key).cloned()) {
+        return info;
+    }
+
+    let info = lookup();
+    CACHE.with(|cache| cache.borrow_mut().insert(key, info.clone()));
+    info
+}
+
+fn lookup_desktop_id(id: &str) -> Option<gio::DesktopAppInfo> {
+    let id = id.strip_suffix(".desktop").unwrap_or(id);
+    cached(format!("id:{id}"), || {
+        gio::DesktopAppInfo::new(&format!("{id}.desktop"))
+            .or_else(|| gio::DesktopAppInfo::new(&format!("{}.desktop", id.to_lowercase())))
+    })
+}
+
+/// Finds the desktop entry named after a binary, or the one that runs it.
+fn lookup_binary(binary: &str) -> Option<gio::DesktopAppInfo> {
+    let binary = Path::new(binary).file_name()?.to_str()?.to_string();
+    cached(format!("binary:{binary}"), || {
+        lookup_desktop_id(&binary).or_else(|| {
+            gio::AppInfo::all()
+                .into_iter()
+                .filter_map(|info| info.downcast::<gio::DesktopAppInfo>().ok())
+                .find(|info| info.executable().file_name().is_some_and(|name| name.to_str() == Some(binary.as_str())))
+        })
+    })
+}
@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use glib::subclass::types::ObjectSubclassIsExt;
+use gtk::{gio, prelude::SettingsExtManual};
+use wireplumber as wp;
+use wp::pw::{PipewireObjectExt, PipewireObjectExt2};
+
+use super::*;
+use crate::{backend::conffragment, config::APP_ID};
+
+impl PwNodeObject {
+    /// Computes the effective latency from the `Latency` and `ProcessLatency` params
+    /// and the quantum the node is running with.
+    pub(crate) fn update_latency(&self) {
+        let node = self.imp().wpnode.get().expect("node");
+        self.update_driver();
+
+        // Latency towards the device for playback, away from it for capture.
+        let direction = match self.nodetype() {
+            NodeType::Sink | NodeType::StreamOutput => 0,
+            _ => 1,
+        };
+
+        let (quantum, rate) = self.quantum_and_rate();
+
+        let mut latency_ns: i64 = 0;
+
+        let keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Latency").expect("id table");
+        let find_key = |name: &str| keys.find_value_from_short_name(name).expect("latency key");
+        let (direction_key, quantum_key, rate_key, ns_key) = (find_key("direction"), find_key("maxQuantum"), find_key("maxRate"), find_key("maxNs"));
+
+        if let Some(params) = node.enum_params_sync("Latency", None) {
+            for a in params {
+                let pod: wp::spa::SpaPod = a.get().unwrap();
+                if !pod.is_object() {
+                    continue;
+                }
+
+                if pod.find_spa_property(&direction_key).and_then(|x| x.id()) != Some(direction) {
+                    continue;
+                }
+
+                let max_quantum = pod.find_spa_property(&quantum_key).and_then(|x| x.float()).unwrap_or_default();
+                let max_rate = pod.find_spa_property(&rate_key).and_then(|x| x.int()).unwrap_or_default();
+                let max_ns = pod.find_spa_property(&ns_key).and_then(|x| x.long()).unwrap_or_default();
+
+                latency_ns += samples_to_ns((max_quantum as f64 * quantum as f64) as i64 + max_rate as i64, rate) + max_ns;
+            }
+        }
+
+        let keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:ProcessLatency").expect("id table");
+        let find_key = |name: &str| keys.find_value_from_short_name(name).expect("process latency key");
+        let (quantum_key, rate_key, ns_key) = (find_key("quantum"), find_key("rate"), find_key("ns"));
+
+        if let Some(params) = node.enum_params_sync("ProcessLatency", None) {
+            for a in params {
+                let pod: wp::spa::SpaPod = a.get().unwrap();
+                if !pod.is_object() {
+                    continue;
+                }
+
+                let process_quantum = pod.find_spa_property(&quantum_key).and_then(|x| x.float()).unwrap_or_default();
+                let process_rate = pod.find_spa_property(&rate_key).and_then(|x| x.int()).unwrap_or_default();
+                let process_ns = pod.find_spa_property(&ns_key).and_then(|x| x.long()).unwrap_or_default();
+
+                latency_ns += samples_to_ns((process_quantum as f64 * quantum as f64) as i64 + process_rate as i64, rate) + process_ns;
+
+                // Don't echo the value we just read back to the node.
+                let blocked = self.imp().block.replace(true);
+                self.set_processlatency(process_ns as f64 / 1_000_000.0);
+                self.imp().block.set(blocked);
+            }
+        }
+
+        // The node itself always adds one quantum of buffering.
+        latency_ns += samples_to_ns(quantum as i64, rate);
+
+        self.set_latency(latency_ns as f64 / 1_000_000.0);
+        self.set_latencystr(format!("{:.1} ms ({quantum}/{rate})", self.latency()));
+    }
+
+    /// Returns the quantum and rate this node is scheduled with.
+    ///
+    /// The graph runs with the quantum of the driver of the node: a forced quantum, else the
+    /// smallest `node.latency` requested by the nodes it drives, else `clock.quantum`. The
+    /// node's own request is only used as a guess when its driver is not known.
+    pub(crate) fn quantum_and_rate(&self) -> (u32, u32) {
+        let manager = PwvucontrolManager::default();
+        let setting = |key: &str| manager.setting(key).and_then(|x| x.trim().parse::<u32>().ok()).filter(|x| *x > 0);
+
+        let rate = setting("clock.force-rate")
+            .or_else(|| setting("clock.rate"))
+            .or_else(|| self.format().map(|x| x.rate as u32).filter(|x| *x > 0))
+            .unwrap_or(48000);
+
+        let to_quantum = |seconds: f64| {
+            let requested = (seconds * rate as f64).round() as u32;
+            match (setting("clock.min-quantum"), setting("clock.max-quantum")) {
+                (Some(min), Some(max)) if min <= max => requested.clamp(min, max),
+                _ => requested,
+            }
+        };
+
+        let driver_quantum = || {
+            let driver = self.driver_id()?;
+            manager.driver_latency(driver).map(to_quantum).or_else(|| setting("clock.quantum"))
+        };
+
+        let quantum = setting("clock.force-quantum")
+            .or_else(driver_quantum)
+            .or_else(|| self.node_latency().map(to_quantum))
+            .or_else(|| setting("clock.quantum"))
+            .unwrap_or(1024);
+
+        (quantum, rate)
+    }
+
+    /// Id of the node driving the graph the node is in, which is the node itself for drivers.
+    pub(crate) fn driver_id(&self) -> Option<u32> {
+        self.wpnode().pw_property::<String>("node.driver-id").ok()?.parse().ok()
+    }
+
+    /// The `node.latency` the node requests, in seconds.
+    pub(crate) fn node_latency(&self) -> Option<f64> {
+        let (num, denom) = parse_fraction(&self.wpnode().pw_property::<String>("node.latency").ok()?)?;
+        Some(num as f64 / denom as f64)
+    }
+
+    /// Lets the manager work out the quantum of the drivers involved again when the driver or
+    /// the requested latency of the node changed.
+    fn update_driver(&self) {
+        let scheduling = (self.driver_id(), self.node_latency());
+        let (old_driver, old_latency) = self.imp().scheduling.replace(scheduling);
+        if (old_driver, old_latency) == scheduling {
+            return;
+        }
+
+        let manager = PwvucontrolManager::default();
+        for driver in [old_driver, scheduling.0].into_iter().flatten() {
+            manager.forget_driver_latency(driver);
+        }
+    }
+
+    /// Returns the `node.latency` requested for this stream's application, if any.
+    pub(crate) fn requested_latency(&self) -> Option<String> {
+        let application = self.application_name()?;
+        stream_latencies().remove(&application)
+    }
+
+    /// Requests a `node.latency` for all streams of this stream's application.
+    ///
+    /// Stream properties cannot be changed from outside the client, so this is done with stream rules
+    /// in generated PipeWire client and pipewire-pulse config fragments. It takes effect the next time
+    /// the application creates its stream.
+    pub(crate) fn set_requested_latency(&self, latency: Option<u32>) {
+        let Some(application) = self.application_name() else {
+            pwvucontrol_warning!("Stream {} has no application.name, cannot request latency", self.name());
+            return;
+        };
+
+        let (_, rate) = self.quantum_and_rate();
+
+        let mut latencies = stream_latencies();
+        match latency {
+            Some(latency) => latencies.insert(application, format!("{latency}/{rate}")),
+            None => latencies.remove(&application),
+        };
+
+        let settings = gio::Settings::new(APP_ID);
+        if let Err(e) = settings.set("stream-latencies", latencies.clone()) {
+            pwvucontrol_warning!("Cannot save stream latencies: {e}");
+        }
+
+        write_stream_latency_rules(&latencies);
+    }
+
+    fn application_name(&self) -> Option<String> {
+        self.wpnode().pw_property::<String>("application.name").ok()
+    }
+}
+
+fn stream_latencies() -> BTreeMap<String, String> {
+    gio::Settings::new(APP_ID).get("stream-latencies")
+}
+
+fn write_stream_latency_rules(latencies: &BTreeMap<String, String>) {
+    let rules: Vec<String> = latencies
+        .iter()
+        .map(|(application, latency)| {
+            format!(
+                "    {{\n        matches = [ {{ application.name = {} }} ]\n        actions = {{ update-props = {{ node.latency = {} }} }}\n    }}\n",
+                conffragment::quote(application),
+                conffragment::quote(latency)
+            )
+        })
+        .collect();
+
+    for (dir, section) in [("pipewire/client.conf.d", "stream.rules"), ("pipewire/pipewire-pulse.conf.d", "pulse.rules")] {
+        let contents = (!rules.is_empty()).then(|| format!("{section} = [\n{}]\n", rules.concat()));
+        if let Err(e) = conffragment::write_conf_fragment(dir, "latency", "conf", contents.as_deref()) {
+            pwvucontrol_warning!("Cannot write latency rules to {dir}: {e}");
+        }
+    }
+}
+
+fn samples_to_ns(samples: i64, rate: u32) -> i64 {
+    if rate == 0 {
+        return 0;
+    }
+    samples * 1_000_000_000 / rate as i64
+}
+
+/// Parses fractions like `256/48000` as used by `node.latency`.
+fn parse_fraction(value: &str) -> Option<(u32, u32)> {
+    let (num, denom) = value.split_once('/')?;
+    let denom: u32 = denom.trim().parse().ok().filter(|x| *x > 0)?;
+    Some((num.trim().parse().ok()?, denom))
+}
@@ -15,9 +15,13 @@ impl PwNodeObject {
 
         let changed_handler = closure_local!(@watch self as widget => move |_mixerapi: &wp::plugin::Plugin, id: u32|{
             if id == widget.boundid() {
+                let old = (widget.volume(), widget.mute());
                 widget.imp().block.set(true);
                 widget.update_volume_using_mixerapi();
                 widget.imp().block.set(false);
+                if (widget.volume(), widget.mute()) != old {
+                    widget.schedule_volume_log(super::volumelog::VolumeOrigin::External);
+                }
             }
         });
 
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::subclass::types::ObjectSubclassIsExt;
+use wireplumber as wp;
+use wp::pw::{PipewireObjectExt, PipewireObjectExt2};
+
+use super::*;
+use crate::backend::PwPropInfoObject;
+
+/// Props that have dedicated controls elsewhere.
+const HANDLED_PROPS: [&str; 7] = ["volume", "mute", "channelVolumes", "monitorVolumes", "monitorMute", "channelMap", "latencyOffsetNsec"];
+
+impl PwNodeObject {
+    /// Rebuilds the editable props from the `PropInfo` params.
+    pub(crate) fn update_prop_info(&self) {
+        let node = self.imp().wpnode.get().expect("node");
+
+        let infos: Vec<PwPropInfoObject> = node
+            .enum_params_sync("PropInfo", None)
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.get::<wp::spa::SpaPod>().ok())
+            .filter(|pod| pod.is_object())
+            .filter_map(|pod| PwPropInfoObject::from_pod(&pod))
+            .filter(|info| info.params() || !HANDLED_PROPS.contains(&info.key().as_str()))
+            .collect();
+
+        for info in &infos {
+            info.connect_notify_local(None, clone!(@weak self as obj => move |info, pspec| {
+                if matches!(pspec.name(), "boolean" | "number" | "text") && !obj.imp().block.get() {
+                    obj.send_prop(info);
+                }
+            }));
+        }
+
+        let model = self.propinfomodel();
+        model.splice(0, model.n_items(), &infos);
+
+        if let Some(params) = node.enum_params_sync("Props", None) {
+            let blocked = self.imp().block.replace(true);
+            for a in params {
+                let pod: wp::spa::SpaPod = a.get().unwrap();
+                if pod.is_object() {
+                    self.update_prop_values(&pod);
+                }
+            }
+            self.imp().block.set(blocked);
+        }
+    }
+
+    /// Takes over the current values of the editable props from a `Props` object.
+    pub(crate) fn update_prop_values(&self, pod: &wp::spa::SpaPod) {
+        let keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Props").expect("id table");
+
+        let params: Vec<wp::spa::SpaPod> = keys
+            .find_value_from_short_name("params")
+            .and_then(|key| pod.find_spa_property(&key))
+            .filter(|x| x.is_struct())
+            .map(|x| x.new_iterator().into_iter().flatten().filter_map(|x| x.get::<wp::spa::SpaPod>().ok()).collect())
+            .unwrap_or_default();
+
+        for info in self.propinfomodel().iter::<PwPropInfoObject>().map_while(Result::ok) {
+            let value = if info.params() {
+                params
+                    .chunks_exact(2)
+                    .find(|pair| pair[0].string().is_some_and(|name| name.as_str() == info.key()))
+                    .map(|pair| pair[1].clone())
+            } else {
+                keys.find_value_from_short_name(&info.key()).and_then(|key| pod.find_spa_property(&key))
+            };
+
+            if let Some(value) = value {
+                info.set_value_from_pod(&value);
+            }
+        }
+    }
+
+    fn send_prop(&self, info: &PwPropInfoObject) {
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Props", "Props");
+        let node = self.imp().wpnode.get().expect("WpNode set");
+
+        if info.params() {
+            let params = SpaPodBuilder::new_struct();
+            params.add_string(&info.key());
+            info.add_value(&params);
+            if let Some(paramspod) = params.end() {
+                podbuilder.add_property("params");
+                podbuilder.add_pod(&paramspod);
+            }
+        } else {
+            podbuilder.add_property(&info.key());
+            info.add_value(&podbuilder);
+        }
+
+        if let Some(pod) = podbuilder.end() {
+            node.set_param("Props", 0, pod);
+        }
+    }
+}
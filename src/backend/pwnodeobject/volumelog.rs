@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::time::Duration;
+
+use gettextrs::gettext;
+
+use super::*;
+use crate::backend::EventCategory;
+
+/// Changes arriving within this interval are logged as one event.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Who changed the volume.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum VolumeOrigin {
+    Local,
+    External,
+}
+
+impl PwNodeObject {
+    /// Remembers the current volume and mute state as already logged.
+    pub(super) fn reset_volume_log(&self) {
+        self.imp().logged_volume.set((self.volume(), self.mute()));
+    }
+
+    /// Logs the volume and mute state once it settled. Changes made by pwvucontrol win over the
+    /// echo of them coming back through the mixer api.
+    pub(super) fn schedule_volume_log(&self, origin: VolumeOrigin) {
+        let imp = self.imp();
+
+        let origin = match imp.volume_log.take() {
+            Some((source, pending)) => {
+                source.remove();
+                if pending == VolumeOrigin::Local { pending } else { origin }
+            },
+            None => origin,
+        };
+
+        let source = glib::timeout_add_local_once(SETTLE_TIME, clone!(@weak self as obj => move || {
+            if let Some((_, origin)) = obj.imp().volume_log.take() {
+                obj.log_volume_change(origin);
+            }
+        }));
+        imp.volume_log.replace(Some((source, origin)));
+    }
+
+    fn log_volume_change(&self, origin: VolumeOrigin) {
+        let (old_volume, old_mute) = self.imp().logged_volume.get();
+        self.reset_volume_log();
+
+        let percent = |volume: f32| (volume.cbrt() * 100.0).round();
+        let mut changes = Vec::new();
+        if percent(old_volume) != percent(self.volume()) {
+            changes.push(
+                gettext("volume {old}% → {new}%")
+                    .replace("{old}", &percent(old_volume).to_string())
+                    .replace("{new}", &percent(self.volume()).to_string()),
+            );
+        }
+        if old_mute != self.mute() {
+            changes.push(if self.mute() { gettext("muted") } else { gettext("unmuted") });
+        }
+        if changes.is_empty() {
+            return;
+        }
+
+        let message = match origin {
+            VolumeOrigin::Local => gettext("{node}: {changes} by pwvucontrol"),
+            VolumeOrigin::External => gettext("{node}: {changes} by another client"),
+        };
+        let message = message.replace("{node}", &self.name()).replace("{changes}", &changes.join(", "));
+        PwvucontrolManager::default().log_event(EventCategory::Volume, &message);
+    }
+}
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::{clone, Properties};
+use gtk::{gio, prelude::*, subclass::prelude::*};
+use std::cell::{Cell, RefCell};
+use wireplumber as wp;
+use wp::pw::PipewireObjectExt2;
+
+use super::{NodeType, PwNodeObject, PwvucontrolManager};
+
+/// Node properties the search text is matched against, besides the displayed name.
+const SEARCH_PROPERTIES: [&str; 5] = ["node.name", "media.name", "application.name", "application.process.binary", "media.role"];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties)]
+    #[properties(wrapper_type = super::PwNodeSearchFilter)]
+    pub struct PwNodeSearchFilter {
+        #[property(get, set = Self::set_text)]
+        text: RefCell<String>,
+        /// Only match nodes that are running.
+        #[property(get, set = Self::set_playing_only)]
+        playing_only: Cell<bool>,
+        #[property(get, set = Self::set_muted_only)]
+        muted_only: Cell<bool>,
+        /// Only match streams sent somewhere else than the default sink or source.
+        #[property(get, set = Self::set_non_default_target)]
+        non_default_target: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwNodeSearchFilter {
+        const NAME: &'static str = "PwNodeSearchFilter";
+        type Type = super::PwNodeSearchFilter;
+        type ParentType = gtk::Filter;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwNodeSearchFilter {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let manager = PwvucontrolManager::default();
+
+            // Quick filters depend on state that changes while the node is listed.
+            let node_model = manager.node_model();
+            self.watch_nodes(&node_model, 0, node_model.n_items());
+            node_model.connect_items_changed(clone!(@weak self as imp => move |model, position, _, added| {
+                imp.watch_nodes(model, position, added);
+            }));
+
+            manager.connect_local("metadata-changed", false, clone!(@weak obj => @default-return None, move |_| {
+                if obj.non_default_target() {
+                    obj.changed(gtk::FilterChange::Different);
+                }
+                None
+            }));
+        }
+    }
+
+    impl FilterImpl for PwNodeSearchFilter {
+        fn strictness(&self) -> gtk::FilterMatch {
+            if self.text.borrow().is_empty() && !self.playing_only.get() && !self.muted_only.get() && !self.non_default_target.get() {
+                gtk::FilterMatch::All
+            } else {
+                gtk::FilterMatch::Some
+            }
+        }
+
+        fn match_(&self, item: &glib::Object) -> bool {
+            let Some(node) = item.downcast_ref::<PwNodeObject>() else {
+                return false;
+            };
+
+            if self.playing_only.get() && !node.running() {
+                return false;
+            }
+            if self.muted_only.get() && !node.mute() {
+                return false;
+            }
+            if self.non_default_target.get() && !has_non_default_target(node) {
+                return false;
+            }
+
+            let text = self.text.borrow().to_lowercase();
+            if text.is_empty() {
+                return true;
+            }
+
+            let wpnode = node.wpnode();
+            [node.name(), node.description()]
+                .into_iter()
+                .chain(SEARCH_PROPERTIES.iter().filter_map(|key| wpnode.pw_property::<String>(key).ok()))
+                .any(|value| value.to_lowercase().contains(&text))
+        }
+    }
+
+    impl PwNodeSearchFilter {
+        fn watch_nodes(&self, model: &gio::ListStore, position: u32, added: u32) {
+            let obj = self.obj();
+            for node in (position..position + added).filter_map(|i| model.item(i).and_downcast::<PwNodeObject>()) {
+                node.connect_running_notify(clone!(@weak obj => move |_| {
+                    if obj.playing_only() {
+                        obj.changed(gtk::FilterChange::Different);
+                    }
+                }));
+                node.connect_mute_notify(clone!(@weak obj => move |_| {
+                    if obj.muted_only() {
+                        obj.changed(gtk::FilterChange::Different);
+                    }
+                }));
+            }
+        }
+
+        fn set_text(&self, text: String) {
+            let old = self.text.replace(text.clone());
+            let change = if text.contains(&old) {
+                gtk::FilterChange::MoreStrict
+            } else if old.contains(&text) {
+                gtk::FilterChange::LessStrict
+            } else {
+                gtk::FilterChange::Different
+            };
+            self.obj().changed(change);
+        }
+
+        fn set_playing_only(&self, value: bool) {
+            self.set_flag(&self.playing_only, value);
+        }
+
+        fn set_muted_only(&self, value: bool) {
+            self.set_flag(&self.muted_only, value);
+        }
+
+        fn set_non_default_target(&self, value: bool) {
+            self.set_flag(&self.non_default_target, value);
+        }
+
+        fn set_flag(&self, flag: &Cell<bool>, value: bool) {
+            if flag.replace(value) != value {
+                self.obj().changed(if value { gtk::FilterChange::MoreStrict } else { gtk::FilterChange::LessStrict });
+            }
+        }
+    }
+
+    /// Whether a stream is explicitly sent to another node than the default one.
+    fn has_non_default_target(node: &PwNodeObject) -> bool {
+        let media_class = match node.nodetype() {
+            NodeType::StreamOutput => "Audio/Sink",
+            NodeType::StreamInput => "Audio/Source",
+            _ => return false,
+        };
+        let Some(target) = node.default_target() else {
+            return false;
+        };
+
+        let default_id: u32 = PwvucontrolManager::default().default_nodes_api().emit_by_name("get-default-node", &[&media_class]);
+        target.boundid() != default_id
+    }
+}
+
+glib::wrapper! {
+    /// Filters nodes by search text and the quick filters of the search bar.
+    pub struct PwNodeSearchFilter(ObjectSubclass<imp::PwNodeSearchFilter>) @extends gtk::Filter;
+}
+
+impl PwNodeSearchFilter {
+    pub(crate) fn new() -> Self {
+        glib::Object::new()
+    }
+}
@@ -7,7 +7,16 @@ use gtk::{
     prelude::*,
     subclass::prelude::*
 };
-use super::ParamAvailability;
+use super::{ParamAvailability, ProfileGroup};
+
+/// Profile families of bluez5 devices. Codec specific profiles are named `<family>-<codec>`.
+const BLUEZ_FAMILIES: [(&str, ProfileGroup); 5] = [
+    ("a2dp-sink", ProfileGroup::A2dp),
+    ("a2dp-source", ProfileGroup::A2dp),
+    ("a2dp-duplex", ProfileGroup::A2dp),
+    ("headset-head-unit", ProfileGroup::Headset),
+    ("headset-audio-gateway", ProfileGroup::Headset),
+];
 
 mod imp {
 
@@ -19,9 +28,20 @@ mod imp {
         #[property(get, set)]
         index: Cell<u32>,
         #[property(get, set)]
+        name: RefCell<String>,
+        #[property(get, set)]
         description: RefCell<String>,
         #[property(get, set, builder(ParamAvailability::Unknown))]
         availability: Cell<ParamAvailability>,
+        #[property(get, set)]
+        family: RefCell<String>,
+        #[property(get, set)]
+        codec: RefCell<Option<String>>,
+        #[property(get, set, builder(ProfileGroup::Other))]
+        group: Cell<ProfileGroup>,
+        /// Whether this is the active profile and the session manager saved it.
+        #[property(get, set)]
+        saved: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -41,11 +61,32 @@ glib::wrapper! {
 }
 
 impl PwProfileObject {
-    pub(crate) fn new(index: u32, description: &str, availability: u32) -> Self {
+    pub(crate) fn new(index: u32, name: &str, description: &str, availability: u32) -> Self {
+        let (family, codec, group) = split_profile_name(name);
+
         glib::Object::builder()
         .property("index", index)
+        .property("name", name)
         .property("description", description)
         .property("availability", ParamAvailability::from(availability))
+        .property("family", family)
+        .property("codec", codec)
+        .property("group", group)
         .build()
     }
 }
+
+fn split_profile_name(name: &str) -> (&str, Option<&str>, ProfileGroup) {
+    for (family, group) in BLUEZ_FAMILIES {
+        if let Some(rest) = name.strip_prefix(family) {
+            if rest.is_empty() {
+                return (family, None, group);
+            }
+            if let Some(codec) = rest.strip_prefix('-') {
+                return (family, Some(codec), group);
+            }
+        }
+    }
+
+    (name, None, ProfileGroup::Other)
+}
@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::RefCell;
+
+use gtk::{
+    gio,
+    glib::{self, Properties},
+    prelude::*,
+    subclass::prelude::*
+};
+use wireplumber as wp;
+
+/// Enum tables for id values of well known param keys, so they show up by name.
+const ID_TABLES: [(&str, &str); 8] = [
+    ("direction", "Spa:Enum:Direction"),
+    ("available", "Spa:Enum:ParamAvailability"),
+    ("mediaType", "Spa:Enum:MediaType"),
+    ("mediaSubtype", "Spa:Enum:MediaSubtype"),
+    ("format", "Spa:Enum:AudioFormat"),
+    ("position", "Spa:Enum:AudioChannel"),
+    ("iec958Codec", "Spa:Enum:AudioIEC958Codec"),
+    ("iec958Codecs", "Spa:Enum:AudioIEC958Codec"),
+];
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::PwPropertyObject)]
+    pub struct PwPropertyObject {
+        #[property(get, set)]
+        key: RefCell<String>,
+        #[property(get, set)]
+        value: RefCell<String>,
+        /// Child entries of sections, objects, structs and arrays.
+        #[property(get, set)]
+        children: RefCell<Option<gio::ListStore>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwPropertyObject {
+        const NAME: &'static str = "PwPropertyObject";
+        type Type = super::PwPropertyObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwPropertyObject {}
+
+    impl PwPropertyObject {}
+}
+
+glib::wrapper! {
+    pub struct PwPropertyObject(ObjectSubclass<imp::PwPropertyObject>);
+}
+
+impl PwPropertyObject {
+    pub(crate) fn new(key: &str, value: &str) -> Self {
+        glib::Object::builder()
+            .property("key", key)
+            .property("value", value)
+            .build()
+    }
+
+    /// An entry with children, e.g. a section of the inspector.
+    pub(crate) fn new_parent(key: &str, value: &str, children: &[PwPropertyObject]) -> Self {
+        let store = gio::ListStore::new::<PwPropertyObject>();
+        store.extend_from_slice(children);
+
+        glib::Object::builder()
+            .property("key", key)
+            .property("value", value)
+            .property("children", store)
+            .build()
+    }
+
+    /// Replaces the children, keeping the entry itself in place.
+    pub(crate) fn set_child_items(&self, children: &[PwPropertyObject]) {
+        if let Some(store) = self.children() {
+            store.splice(0, store.n_items(), children);
+        }
+    }
+
+    /// Entries for a pipewire properties dictionary, sorted by key.
+    pub(crate) fn from_properties(properties: &wp::pw::Properties) -> Vec<PwPropertyObject> {
+        let mut items: Vec<(String, String)> = properties
+            .new_iterator()
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.get::<wp::pw::PropertiesItem>().ok())
+            .map(|item| (item.key().unwrap_or_default().to_string(), item.value().unwrap_or_default().to_string()))
+            .collect();
+        items.sort();
+
+        items.iter().map(|(key, value)| PwPropertyObject::new(key, value)).collect()
+    }
+
+    /// Decodes a pod into an entry, recursing into objects, structs and arrays.
+    pub(crate) fn from_pod(key: &str, pod: &wp::spa::SpaPod) -> Self {
+        if pod.is_object() {
+            let children: Vec<PwPropertyObject> = pod_children(pod)
+                .filter_map(|property| property.property())
+                .map(|(key, value)| PwPropertyObject::from_pod(&key, &value))
+                .collect();
+            let summary = children
+                .iter()
+                .find(|x| matches!(x.key().as_str(), "description" | "name"))
+                .map(|x| x.value())
+                .unwrap_or_default();
+            return PwPropertyObject::new_parent(key, &summary, &children);
+        }
+
+        if pod.is_struct() {
+            let children: Vec<PwPropertyObject> = pod_children(pod)
+                .enumerate()
+                .map(|(i, value)| PwPropertyObject::from_pod(&i.to_string(), &value))
+                .collect();
+            return PwPropertyObject::new_parent(key, "", &children);
+        }
+
+        if pod.is_choice() {
+            let choice_type = pod.choice_type().and_then(|x| x.short_name()).unwrap_or_default();
+            let item = pod.choice_child().map(|child| PwPropertyObject::from_pod(key, &child)).unwrap_or_else(|| PwPropertyObject::new(key, ""));
+            item.set_value(format!("{} ({choice_type})", item.value()));
+            return item;
+        }
+
+        if pod.is_array() {
+            let values = array_values(key, pod);
+            return PwPropertyObject::new(key, &format!("[ {} ]", values.join(", ")));
+        }
+
+        PwPropertyObject::new(key, &scalar_value(key, pod))
+    }
+}
+
+fn pod_children(pod: &wp::spa::SpaPod) -> impl Iterator<Item = wp::spa::SpaPod> {
+    pod.new_iterator()
+        .into_iter()
+        .flatten()
+        .filter_map(|x| x.get::<wp::spa::SpaPod>().ok())
+}
+
+fn id_name(key: &str, id: u32) -> String {
+    ID_TABLES
+        .iter()
+        .find(|(name, _)| *name == key)
+        .and_then(|(_, table)| wp::spa::SpaIdTable::from_name(table))
+        .and_then(|table| table.find_value(id))
+        .and_then(|value| value.short_name())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| id.to_string())
+}
+
+fn scalar_value(key: &str, pod: &wp::spa::SpaPod) -> String {
+    if let Some(value) = pod.boolean() {
+        value.to_string()
+    } else if let Some(value) = pod.id() {
+        id_name(key, value)
+    } else if let Some(value) = pod.int() {
+        value.to_string()
+    } else if let Some(value) = pod.long() {
+        value.to_string()
+    } else if let Some(value) = pod.float() {
+        value.to_string()
+    } else if let Some(value) = pod.double() {
+        value.to_string()
+    } else if let Some(value) = pod.string() {
+        value.to_string()
+    } else if pod.is_none() {
+        String::from("none")
+    } else {
+        String::from("…")
+    }
+}
+
+fn array_values(key: &str, pod: &wp::spa::SpaPod) -> Vec<String> {
+    let Some(child) = pod.array_child() else {
+        return Vec::new();
+    };
+
+    if child.is_float() {
+        pod.array_iterator::<f32>().map(|x| x.to_string()).collect()
+    } else if child.is_id() {
+        pod.array_iterator::<i32>().map(|x| id_name(key, x as u32)).collect()
+    } else if child.is_int() || child.is_boolean() {
+        pod.array_iterator::<i32>().map(|x| x.to_string()).collect()
+    } else if child.is_long() {
+        pod.array_iterator::<i64>().map(|x| x.to_string()).collect()
+    } else if child.is_double() {
+        pod.array_iterator::<f64>().map(|x| x.to_string()).collect()
+    } else {
+        Vec::new()
+    }
+}
@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::{Cell, RefCell};
+
+use gtk::{
+    glib::{self, Properties},
+    prelude::*,
+    subclass::prelude::*
+};
+use wireplumber as wp;
+use wp::spa::SpaPodBuilder;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, glib::Enum, Default)]
+#[enum_type(name = "PropValueType")]
+pub enum PropValueType {
+    Bool,
+    Id,
+    Int,
+    Long,
+    Float,
+    Double,
+    String,
+    #[default]
+    Unsupported,
+}
+
+impl PropValueType {
+    fn of(pod: &wp::spa::SpaPod) -> Self {
+        if pod.is_boolean() {
+            PropValueType::Bool
+        } else if pod.is_id() {
+            PropValueType::Id
+        } else if pod.is_int() {
+            PropValueType::Int
+        } else if pod.is_long() {
+            PropValueType::Long
+        } else if pod.is_float() {
+            PropValueType::Float
+        } else if pod.is_double() {
+            PropValueType::Double
+        } else if pod.is_string() {
+            PropValueType::String
+        } else {
+            PropValueType::Unsupported
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, PropValueType::Id | PropValueType::Int | PropValueType::Long | PropValueType::Float | PropValueType::Double)
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::PwPropInfoObject)]
+    pub struct PwPropInfoObject {
+        /// Short name of the `Props` key, or the name inside the `params` struct.
+        #[property(get, set)]
+        key: RefCell<String>,
+        #[property(get, set)]
+        description: RefCell<String>,
+        /// Whether the value lives in the `params` struct of `Props` instead of a key of its own.
+        #[property(get, set)]
+        params: Cell<bool>,
+        #[property(get, set, builder(PropValueType::Unsupported))]
+        valuetype: Cell<PropValueType>,
+
+        #[property(get, set)]
+        min: Cell<f64>,
+        #[property(get, set)]
+        max: Cell<f64>,
+        #[property(get, set)]
+        step: Cell<f64>,
+        /// Labels of the allowed values, if the value is an enumeration.
+        #[property(get)]
+        labels: gtk::StringList,
+        pub(super) enum_values: RefCell<Vec<f64>>,
+
+        #[property(get, set)]
+        boolean: Cell<bool>,
+        #[property(get, set)]
+        number: Cell<f64>,
+        #[property(get, set)]
+        text: RefCell<String>,
+        /// Position of `number` in the enumeration, `gtk::INVALID_LIST_POSITION` if not found.
+        #[property(get, set = Self::set_selected)]
+        selected: Cell<u32>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwPropInfoObject {
+        const NAME: &'static str = "PwPropInfoObject";
+        type Type = super::PwPropInfoObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwPropInfoObject {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.connect_number_notify(|obj| {
+                let selected = obj.imp().enum_values.borrow().iter().position(|x| *x == obj.number());
+                let selected = selected.map(|x| x as u32).unwrap_or(gtk::INVALID_LIST_POSITION);
+                if obj.selected() != selected {
+                    obj.imp().selected.set(selected);
+                    obj.notify_selected();
+                }
+            });
+        }
+    }
+
+    impl PwPropInfoObject {
+        fn set_selected(&self, selected: u32) {
+            self.selected.set(selected);
+
+            let value = self.enum_values.borrow().get(selected as usize).copied();
+            if let Some(value) = value {
+                if self.number.get() != value {
+                    self.obj().set_number(value);
+                }
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PwPropInfoObject(ObjectSubclass<imp::PwPropInfoObject>);
+}
+
+impl PwPropInfoObject {
+    /// Parses a `PropInfo` param. Returns `None` for values that cannot be edited.
+    pub(crate) fn from_pod(pod: &wp::spa::SpaPod) -> Option<Self> {
+        let keys = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:PropInfo").expect("id table");
+        let find = |name: &str| keys.find_value_from_short_name(name).and_then(|key| pod.find_spa_property(&key));
+
+        let params = find("params").and_then(|x| x.boolean()).unwrap_or_default();
+        let key = if params {
+            find("name").and_then(|x| x.string())?.to_string()
+        } else {
+            let id = find("id").and_then(|x| x.id())?;
+            let props = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Props").expect("id table");
+            props.find_value(id).and_then(|x| x.short_name())?.to_string()
+        };
+        let description = if params {
+            find("description").and_then(|x| x.string()).map(|x| x.to_string()).unwrap_or_default()
+        } else {
+            find("name").and_then(|x| x.string()).map(|x| x.to_string()).unwrap_or_default()
+        };
+
+        let type_pod = find("type")?;
+        let (choice_type, value_pod) = if type_pod.is_choice() {
+            (type_pod.choice_type().and_then(|x| x.short_name()).map(|x| x.to_string()), type_pod.choice_child()?)
+        } else {
+            (None, type_pod.clone())
+        };
+
+        let valuetype = PropValueType::of(&value_pod);
+        if valuetype == PropValueType::Unsupported {
+            return None;
+        }
+
+        let obj: Self = glib::Object::builder()
+            .property("key", key)
+            .property("description", description)
+            .property("params", params)
+            .property("valuetype", valuetype)
+            .property("selected", gtk::INVALID_LIST_POSITION)
+            .build();
+
+        let values = choice_values(&type_pod, valuetype);
+        match (choice_type.as_deref(), values.as_slice()) {
+            (Some("Range"), [_, min, max, ..]) => obj.set_range(*min, *max, 0.0),
+            (Some("Step"), [_, min, max, step, ..]) => obj.set_range(*min, *max, *step),
+            (Some("Enum"), [_, alternatives @ ..]) => {
+                let labels: Vec<String> = alternatives.iter().map(|x| x.to_string()).collect();
+                obj.set_enum(alternatives, &labels);
+            },
+            _ => {},
+        }
+
+        let labels: Vec<wp::spa::SpaPod> = find("labels")
+            .filter(|x| x.is_struct())
+            .map(|x| x.new_iterator().into_iter().flatten().filter_map(|x| x.get::<wp::spa::SpaPod>().ok()).collect())
+            .unwrap_or_default();
+        if !labels.is_empty() {
+            let (values, names): (Vec<f64>, Vec<String>) = labels
+                .chunks_exact(2)
+                .filter_map(|pair| Some((pod_number(&pair[0])?, pair[1].string()?.to_string())))
+                .unzip();
+            obj.set_enum(&values, &names);
+        }
+
+        if let Some(default) = values.first() {
+            obj.set_number(*default);
+        }
+        obj.set_value_from_pod(&value_pod);
+
+        Some(obj)
+    }
+
+    fn set_range(&self, min: f64, max: f64, step: f64) {
+        self.set_min(min);
+        self.set_max(max);
+        self.set_step(step);
+    }
+
+    fn set_enum(&self, values: &[f64], labels: &[String]) {
+        self.imp().enum_values.replace(values.to_vec());
+        let names: Vec<&str> = labels.iter().map(String::as_str).collect();
+        self.labels().splice(0, self.labels().n_items(), &names);
+    }
+
+    pub(crate) fn is_enum(&self) -> bool {
+        !self.imp().enum_values.borrow().is_empty()
+    }
+
+    pub(crate) fn has_range(&self) -> bool {
+        self.min() < self.max()
+    }
+
+    /// Takes over the current value from a `Props` value.
+    pub(crate) fn set_value_from_pod(&self, pod: &wp::spa::SpaPod) {
+        match self.valuetype() {
+            PropValueType::Bool => {
+                if let Some(value) = pod.boolean() {
+                    if self.boolean() != value {
+                        self.set_boolean(value);
+                    }
+                }
+            },
+            PropValueType::String => {
+                if let Some(value) = pod.string() {
+                    if self.text() != value.as_str() {
+                        self.set_text(value.as_str());
+                    }
+                }
+            },
+            _ => {
+                if let Some(value) = pod_number(pod) {
+                    if self.number() != value {
+                        self.set_number(value);
+                    }
+                }
+            },
+        }
+    }
+
+    /// Adds the current value to a builder, typed like the value advertised in `PropInfo`.
+    pub(crate) fn add_value(&self, builder: &SpaPodBuilder) {
+        match self.valuetype() {
+            PropValueType::Bool => builder.add_boolean(self.boolean()),
+            PropValueType::Id => builder.add_id(self.number() as u32),
+            PropValueType::Int => builder.add_int(self.number() as i32),
+            PropValueType::Long => builder.add_long(self.number() as i64),
+            PropValueType::Float => builder.add_float(self.number() as f32),
+            PropValueType::Double => builder.add_double(self.number()),
+            PropValueType::String => builder.add_string(&self.text()),
+            PropValueType::Unsupported => {},
+        }
+    }
+}
+
+pub(crate) fn pod_number(pod: &wp::spa::SpaPod) -> Option<f64> {
+    pod.int()
+        .map(f64::from)
+        .or_else(|| pod.id().map(f64::from))
+        .or_else(|| pod.long().map(|x| x as f64))
+        .or_else(|| pod.float().map(f64::from))
+        .or_else(|| pod.double())
+}
+
+/// Values of a choice, or the single default value of a plain pod.
+fn choice_values(pod: &wp::spa::SpaPod, valuetype: PropValueType) -> Vec<f64> {
+    if !pod.is_choice() {
+        return pod_number(pod).into_iter().collect();
+    }
+
+    match valuetype {
+        PropValueType::Id | PropValueType::Int => pod.array_iterator::<i32>().map(f64::from).collect(),
+        PropValueType::Long => pod.array_iterator::<i64>().map(|x| x as f64).collect(),
+        PropValueType::Float => pod.array_iterator::<f32>().map(f64::from).collect(),
+        PropValueType::Double => pod.array_iterator::<f64>().collect(),
+        _ => Vec::new(),
+    }
+}
@@ -19,14 +19,34 @@ mod imp {
     pub struct PwRouteObject {
         #[property(get, set)]
         index: Cell<u32>,
+        /// Port name such as `analog-output-headphones`.
+        #[property(get, set)]
+        name: RefCell<String>,
         #[property(get, set)]
         description: RefCell<String>,
         #[property(get, set, builder(ParamAvailability::Unknown))]
         availability: Cell<ParamAvailability>,
         #[property(get, set, builder(RouteDirection::Unknown))]
         direction: Cell<RouteDirection>,
+        /// Latency offset of the route (latencyOffsetNsec), in milliseconds.
+        #[property(get, set)]
+        latencyoffset: Cell<f64>,
+        /// Card profile device the route is active on, -1 when inactive.
+        #[property(get, set)]
+        device: Cell<i32>,
+        /// Whether the active route carries its own volume (hardware mixer without a node mixer).
+        #[property(get, set)]
+        hasvolume: Cell<bool>,
+        #[property(get, set)]
+        volume: Cell<f32>,
+        #[property(get, set)]
+        mute: Cell<bool>,
+        /// Whether this is the active route and the session manager saved it.
+        #[property(get, set)]
+        saved: Cell<bool>,
 
         pub(super) profiles: RefCell<Vec<u32>>,
+        pub(super) channel_volumes: RefCell<Vec<f32>>,
     }
 
     #[glib::object_subclass]
@@ -52,6 +72,7 @@ impl PwRouteObject {
         .property("description", format!("{description} ({index})"))
         .property("availability", ParamAvailability::from(availability))
         .property("direction", RouteDirection::from(direction))
+        .property("device", -1)
         .build();
 
         new.set_profiles(profiles);
@@ -59,6 +80,10 @@ impl PwRouteObject {
         new
     }
 
+    pub(crate) fn is_headphones(&self) -> bool {
+        self.direction() == RouteDirection::Output && self.name().to_lowercase().contains("headphone")
+    }
+
     pub(crate) fn get_profiles(&self) -> Vec<u32> {
         self.imp().profiles.borrow().clone()
     }
@@ -67,4 +92,12 @@ impl PwRouteObject {
         self.imp().profiles.replace(list.to_vec());
     }
 
+    pub(crate) fn channel_volumes(&self) -> Vec<f32> {
+        self.imp().channel_volumes.borrow().clone()
+    }
+
+    pub(crate) fn set_channel_volumes(&self, volumes: &[f32]) {
+        self.imp().channel_volumes.replace(volumes.to_vec());
+    }
+
 }
@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::{Cell, RefCell};
+
+use gettextrs::gettext;
+use gtk::{
+    glib::{self, Properties},
+    prelude::*,
+    subclass::prelude::*
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, glib::Enum, Default)]
+#[enum_type(name = "RuleTrigger")]
+pub enum RuleTrigger {
+    #[default]
+    DeviceAdded,
+    NodeAdded,
+    RouteAvailable,
+    RouteUnavailable,
+}
+
+impl RuleTrigger {
+    pub const ALL: [RuleTrigger; 4] = [Self::DeviceAdded, Self::NodeAdded, Self::RouteAvailable, Self::RouteUnavailable];
+
+    pub fn title(&self) -> String {
+        match self {
+            RuleTrigger::DeviceAdded => gettext("Device connected"),
+            RuleTrigger::NodeAdded => gettext("Input or output appeared"),
+            RuleTrigger::RouteAvailable => gettext("Port became available"),
+            RuleTrigger::RouteUnavailable => gettext("Port became unavailable"),
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            RuleTrigger::DeviceAdded => "device-added",
+            RuleTrigger::NodeAdded => "node-added",
+            RuleTrigger::RouteAvailable => "route-available",
+            RuleTrigger::RouteUnavailable => "route-unavailable",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.key() == key)
+    }
+}
+
+impl From<u32> for RuleTrigger {
+    fn from(value: u32) -> Self {
+        Self::ALL.get(value as usize).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, glib::Enum, Default)]
+#[enum_type(name = "RuleAction")]
+pub enum RuleAction {
+    #[default]
+    SetDefault,
+    SetProfile,
+    SetRoute,
+    SetVolume,
+}
+
+impl RuleAction {
+    pub const ALL: [RuleAction; 4] = [Self::SetDefault, Self::SetProfile, Self::SetRoute, Self::SetVolume];
+
+    pub fn title(&self) -> String {
+        match self {
+            RuleAction::SetDefault => gettext("Make default"),
+            RuleAction::SetProfile => gettext("Select profile"),
+            RuleAction::SetRoute => gettext("Select port"),
+            RuleAction::SetVolume => gettext("Set volume (%)"),
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            RuleAction::SetDefault => "set-default",
+            RuleAction::SetProfile => "set-profile",
+            RuleAction::SetRoute => "set-route",
+            RuleAction::SetVolume => "set-volume",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.key() == key)
+    }
+}
+
+impl From<u32> for RuleAction {
+    fn from(value: u32) -> Self {
+        Self::ALL.get(value as usize).copied().unwrap_or_default()
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::PwRuleObject)]
+    pub struct PwRuleObject {
+        #[property(get, set)]
+        name: RefCell<String>,
+        #[property(get, set)]
+        enabled: Cell<bool>,
+        #[property(get, set, builder(RuleTrigger::DeviceAdded))]
+        trigger: Cell<RuleTrigger>,
+        /// Case-insensitive substring of the name or description of the device, node or port.
+        /// Empty matches everything.
+        #[property(get, set)]
+        pattern: RefCell<String>,
+        #[property(get, set, builder(RuleAction::SetDefault))]
+        action: Cell<RuleAction>,
+        /// Profile or port name, or volume in percent, depending on the action.
+        #[property(get, set)]
+        argument: RefCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwRuleObject {
+        const NAME: &'static str = "PwRuleObject";
+        type Type = super::PwRuleObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwRuleObject {}
+}
+
+glib::wrapper! {
+    pub struct PwRuleObject(ObjectSubclass<imp::PwRuleObject>);
+}
+
+impl PwRuleObject {
+    pub(crate) fn new(name: &str) -> Self {
+        glib::Object::builder()
+            .property("name", name)
+            .property("enabled", true)
+            .build()
+    }
+
+    /// Whether any of the given names or descriptions matches the pattern.
+    pub(crate) fn matches(&self, candidates: &[&str]) -> bool {
+        let pattern = self.pattern().to_lowercase();
+
+        pattern.is_empty() || candidates.iter().any(|x| x.to_lowercase().contains(&pattern))
+    }
+}
@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::{clone, ControlFlow, Properties};
+use gtk::{gio, prelude::*, subclass::prelude::*};
+use std::{cell::{Cell, RefCell}, time::Duration};
+
+use super::{PwNodeObject, PwvucontrolManager};
+use crate::macros::*;
+
+/// How often the countdown and fade are updated.
+const TICK: Duration = Duration::from_millis(100);
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, Properties)]
+    #[properties(wrapper_type = super::PwSleepTimer)]
+    pub struct PwSleepTimer {
+        #[property(get)]
+        pub(super) running: Cell<bool>,
+        /// Seconds until the timer finishes.
+        #[property(get)]
+        pub(super) remaining: Cell<u32>,
+        /// The sink to fade, all playback streams if unset.
+        #[property(get)]
+        pub(super) target: RefCell<Option<PwNodeObject>>,
+
+        /// Monotonic time the timer finishes at, in microseconds.
+        pub(super) end_time: Cell<i64>,
+        /// Length of the fade at the end of the timer, in microseconds.
+        pub(super) fade_time: Cell<i64>,
+        pub(super) pause_players: Cell<bool>,
+        /// Faded nodes with the volume they had before the fade.
+        pub(super) original_volumes: RefCell<Vec<(PwNodeObject, f32)>>,
+        pub(super) source: RefCell<Option<glib::SourceId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwSleepTimer {
+        const NAME: &'static str = "PwSleepTimer";
+        type Type = super::PwSleepTimer;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwSleepTimer {}
+}
+
+glib::wrapper! {
+    /// Fades playback out to silence after a while.
+    pub struct PwSleepTimer(ObjectSubclass<imp::PwSleepTimer>);
+}
+
+impl PwSleepTimer {
+    pub(crate) fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Starts the timer, replacing a running one. The fade takes the last `fade` seconds of `duration`.
+    pub(crate) fn start(&self, target: Option<&PwNodeObject>, duration: u32, fade: u32, pause_players: bool) {
+        self.cancel();
+
+        let imp = self.imp();
+        imp.target.replace(target.cloned());
+        imp.end_time.set(glib::monotonic_time() + i64::from(duration) * 1_000_000);
+        imp.fade_time.set(i64::from(fade.min(duration)) * 1_000_000);
+        imp.pause_players.set(pause_players);
+
+        let source = glib::timeout_add_local(TICK, clone!(@weak self as obj => @default-return ControlFlow::Break, move || obj.tick()));
+        imp.source.replace(Some(source));
+
+        pwvucontrol_info!("Sleep timer started, {duration} s with {fade} s fade");
+        self.set_state(true, duration);
+        self.notify_target();
+    }
+
+    /// Stops the timer, restoring the volumes of a fade in progress.
+    pub(crate) fn cancel(&self) {
+        let imp = self.imp();
+        if let Some(source) = imp.source.take() {
+            source.remove();
+        }
+
+        self.restore_volumes();
+        self.set_state(false, 0);
+    }
+
+    fn tick(&self) -> ControlFlow {
+        let imp = self.imp();
+        let left = imp.end_time.get() - glib::monotonic_time();
+        let fade_time = imp.fade_time.get();
+
+        self.set_state(true, ((left.max(0) + 999_999) / 1_000_000) as u32);
+
+        if left <= 0 {
+            imp.source.take();
+            self.finish();
+            return ControlFlow::Break;
+        }
+
+        if left <= fade_time {
+            if imp.original_volumes.borrow().is_empty() {
+                let originals = self.target_nodes().into_iter().map(|node| {
+                    let volume = node.volume();
+                    (node, volume)
+                });
+                imp.original_volumes.borrow_mut().extend(originals);
+            }
+
+            // A linear ramp on the cubic scale of the volume sliders sounds even.
+            let factor = (left as f32 / fade_time as f32).powi(3);
+            for (node, volume) in imp.original_volumes.borrow().iter() {
+                node.set_volume(volume * factor);
+            }
+        }
+
+        ControlFlow::Continue
+    }
+
+    fn finish(&self) {
+        pwvucontrol_info!("Sleep timer finished");
+
+        let nodes = self.target_nodes();
+        for node in &nodes {
+            node.set_mute(true);
+        }
+
+        if self.imp().pause_players.get() {
+            pause_media_players();
+        }
+
+        self.restore_volumes();
+        self.set_state(false, 0);
+    }
+
+    /// The target sink, or the playback streams.
+    fn target_nodes(&self) -> Vec<PwNodeObject> {
+        if let Some(target) = self.target() {
+            return vec![target];
+        }
+
+        PwvucontrolManager::default()
+            .stream_output_model()
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .collect()
+    }
+
+    fn restore_volumes(&self) {
+        let originals = self.imp().original_volumes.take();
+        for (node, volume) in originals {
+            node.set_volume(volume);
+        }
+    }
+
+    fn set_state(&self, running: bool, remaining: u32) {
+        let imp = self.imp();
+        if imp.remaining.replace(remaining) != remaining {
+            self.notify_remaining();
+        }
+        if imp.running.replace(running) != running {
+            self.notify_running();
+        }
+    }
+}
+
+impl Default for PwSleepTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Asks every MPRIS media player on the session bus to pause.
+fn pause_media_players() {
+    gio::bus_get(gio::BusType::Session, gio::Cancellable::NONE, |connection| {
+        let connection = match connection {
+            Ok(connection) => connection,
+            Err(e) => {
+                pwvucontrol_warning!("Cannot connect to the session bus: {e}");
+                return;
+            }
+        };
+
+        connection.clone().call(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "ListNames",
+            None,
+            glib::VariantTy::new("(as)").ok(),
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+            move |res| {
+                let names = match res {
+                    Ok(reply) => reply.child_value(0).get::<Vec<String>>().unwrap_or_default(),
+                    Err(e) => {
+                        pwvucontrol_warning!("Cannot list media players: {e}");
+                        return;
+                    }
+                };
+
+                for name in names.iter().filter(|name| name.starts_with(MPRIS_PREFIX)) {
+                    pwvucontrol_info!("Pausing {name}");
+                    connection.call(
+                        Some(name),
+                        "/org/mpris/MediaPlayer2",
+                        "org.mpris.MediaPlayer2.Player",
+                        "Pause",
+                        None,
+                        None,
+                        gio::DBusCallFlags::NONE,
+                        -1,
+                        gio::Cancellable::NONE,
+                        |res| {
+                            if let Err(e) = res {
+                                pwvucontrol_debug!("Cannot pause media player: {e}");
+                            }
+                        },
+                    );
+                }
+            },
+        );
+    });
+}
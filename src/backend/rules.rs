@@ -0,0 +1,311 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{cell::RefCell, fs, path::PathBuf, rc::Rc};
+
+use glib::clone;
+use gtk::{gio, prelude::*, subclass::prelude::*};
+use wireplumber as wp;
+use wp::pw::PipewireObjectExt2;
+
+use super::{
+    NodeType, PwDeviceObject, PwNodeObject, PwProfileObject, PwRouteObject, PwRuleObject, PwvucontrolManager, RouteDirection, RuleAction,
+    RuleTrigger,
+};
+use crate::macros::*;
+
+/// What a rule was triggered by.
+pub(crate) enum RuleTarget<'a> {
+    Device(&'a PwDeviceObject),
+    Node(&'a PwNodeObject),
+    Route(&'a PwDeviceObject, &'a PwRouteObject),
+}
+
+impl RuleTarget<'_> {
+    fn candidates(&self) -> Vec<String> {
+        match self {
+            RuleTarget::Device(device) => {
+                let mut candidates = vec![device.name().unwrap_or_default()];
+                candidates.extend(device.wpdevice().pw_property::<String>("device.name").ok());
+                candidates
+            }
+            RuleTarget::Node(node) => {
+                let mut candidates = vec![node.name(), node.description()];
+                candidates.extend(node.wpnode().pw_property::<String>("node.name").ok());
+                candidates
+            }
+            RuleTarget::Route(device, route) => vec![route.description(), device.name().unwrap_or_default()],
+        }
+    }
+
+    fn device(&self) -> Option<PwDeviceObject> {
+        match self {
+            RuleTarget::Device(device) | RuleTarget::Route(device, _) => Some((*device).clone()),
+            RuleTarget::Node(node) => node.get_device(),
+        }
+    }
+
+    fn direction(&self) -> Option<RouteDirection> {
+        match self {
+            RuleTarget::Device(_) => None,
+            RuleTarget::Node(node) => direction_for_nodetype(node.nodetype()),
+            RuleTarget::Route(_, route) => Some(route.direction()),
+        }
+    }
+
+    /// Sinks and sources the action applies to.
+    fn nodes(&self, manager: &PwvucontrolManager) -> Vec<PwNodeObject> {
+        match self {
+            RuleTarget::Node(node) => vec![(*node).clone()],
+            RuleTarget::Device(device) | RuleTarget::Route(device, _) => manager
+                .get_nodes_for_device(device)
+                .into_iter()
+                .filter(|node| {
+                    let direction = direction_for_nodetype(node.nodetype());
+                    direction.is_some() && self.direction().map_or(true, |x| Some(x) == direction)
+                })
+                .collect(),
+        }
+    }
+}
+
+fn direction_for_nodetype(nodetype: NodeType) -> Option<RouteDirection> {
+    match nodetype {
+        NodeType::Sink => Some(RouteDirection::Output),
+        NodeType::Source => Some(RouteDirection::Input),
+        _ => None,
+    }
+}
+
+fn rules_path() -> PathBuf {
+    glib::user_config_dir().join("pwvucontrol").join("rules.ini")
+}
+
+pub(crate) fn load_rules() -> Vec<PwRuleObject> {
+    let keyfile = glib::KeyFile::new();
+
+    if let Err(e) = keyfile.load_from_file(rules_path(), glib::KeyFileFlags::NONE) {
+        if !e.matches(glib::FileError::Noent) {
+            pwvucontrol_warning!("Cannot load rules: {e}");
+        }
+        return Vec::new();
+    }
+
+    keyfile
+        .groups()
+        .iter()
+        .map(|group| {
+            let group = group.as_str();
+            let string = |key: &str| keyfile.string(group, key).map(String::from).unwrap_or_default();
+
+            let rule = PwRuleObject::new(&string("name"));
+            rule.set_enabled(keyfile.boolean(group, "enabled").unwrap_or(true));
+            rule.set_trigger(RuleTrigger::from_key(&string("trigger")).unwrap_or_default());
+            rule.set_pattern(string("match"));
+            rule.set_action(RuleAction::from_key(&string("action")).unwrap_or_default());
+            rule.set_argument(string("argument"));
+            rule
+        })
+        .collect()
+}
+
+pub(crate) fn save_rules(rules: &gio::ListStore) -> anyhow::Result<()> {
+    let keyfile = glib::KeyFile::new();
+
+    for (i, rule) in rules.iter::<PwRuleObject>().map_while(Result::ok).enumerate() {
+        let group = format!("Rule {i}");
+        keyfile.set_string(&group, "name", &rule.name());
+        keyfile.set_boolean(&group, "enabled", rule.enabled());
+        keyfile.set_string(&group, "trigger", rule.trigger().key());
+        keyfile.set_string(&group, "match", &rule.pattern());
+        keyfile.set_string(&group, "action", rule.action().key());
+        keyfile.set_string(&group, "argument", &rule.argument());
+    }
+
+    let path = rules_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    keyfile.save_to_file(path)?;
+
+    Ok(())
+}
+
+impl PwvucontrolManager {
+    pub(crate) fn save_rules(&self) {
+        if let Err(e) = save_rules(&self.rule_model()) {
+            pwvucontrol_warning!("Cannot save rules: {e}");
+        }
+    }
+
+    pub(crate) fn apply_rules(&self, trigger: RuleTrigger, target: RuleTarget) {
+        if !self.imp().rules_armed.get() {
+            return;
+        }
+
+        let candidates = target.candidates();
+        let candidates: Vec<&str> = candidates.iter().map(String::as_str).collect();
+
+        for rule in self.rule_model().iter::<PwRuleObject>().map_while(Result::ok) {
+            if !rule.enabled() || rule.trigger() != trigger || !rule.matches(&candidates) {
+                continue;
+            }
+
+            pwvucontrol_info!("Applying rule \"{}\"", rule.name());
+            if !self.apply_rule_action(&rule, &target) {
+                // A device appears before its sinks and sources, the rule runs again once they do.
+                if let RuleTarget::Device(device) = target {
+                    pwvucontrol_info!("Rule \"{}\" waits for the nodes of {}", rule.name(), device.name().unwrap_or_default());
+                    self.imp().pending_device_rules.borrow_mut().push((device.wpdevice().bound_id(), rule));
+                }
+            }
+        }
+    }
+
+    /// Runs the device rules that waited for the nodes of the device `node` belongs to.
+    pub(crate) fn apply_pending_device_rules(&self, node: &PwNodeObject) {
+        let Some(device) = node.get_device() else {
+            return;
+        };
+        let device_id = device.wpdevice().bound_id();
+
+        let pending = self.imp().pending_device_rules.take();
+        let (rules, mut pending): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(id, _)| *id == device_id);
+        for (id, rule) in rules {
+            if !self.apply_rule_action(&rule, &RuleTarget::Device(&device)) {
+                pending.push((id, rule));
+            }
+        }
+        self.imp().pending_device_rules.borrow_mut().extend(pending);
+    }
+
+    /// Forgets the rules waiting for a device that went away.
+    pub(crate) fn drop_pending_device_rules(&self, device_id: u32) {
+        self.imp().pending_device_rules.borrow_mut().retain(|(id, _)| *id != device_id);
+    }
+
+    /// Applies the action of a rule. Returns false if the sinks and sources it acts on do not exist yet.
+    fn apply_rule_action(&self, rule: &PwRuleObject, target: &RuleTarget) -> bool {
+        let argument = rule.argument().to_lowercase();
+
+        match rule.action() {
+            RuleAction::SetDefault => {
+                let nodes = target.nodes(self);
+                if nodes.is_empty() {
+                    return false;
+                }
+                for node in nodes {
+                    if let Ok(node_name) = node.wpnode().pw_property::<String>("node.name") {
+                        self.set_default_configured_node_name(node.nodetype(), &node_name);
+                    }
+                }
+            }
+            RuleAction::SetVolume => {
+                let Ok(percent) = argument.trim().trim_end_matches('%').parse::<f32>() else {
+                    pwvucontrol_warning!("Rule \"{}\": invalid volume {argument}", rule.name());
+                    return true;
+                };
+                let nodes = target.nodes(self);
+                if nodes.is_empty() {
+                    return false;
+                }
+                // Percentages are on the cubic scale the volume sliders use.
+                let volume = (percent / 100.0).max(0.0).powi(3);
+                for node in nodes {
+                    node.set_volume(volume);
+                }
+            }
+            RuleAction::SetProfile => {
+                let Some(device) = target.device() else {
+                    return true;
+                };
+                when_profiles_ready(&device, move |device| {
+                    let profile = device
+                        .profilemodel()
+                        .iter::<PwProfileObject>()
+                        .map_while(Result::ok)
+                        .find(|profile| profile.name().to_lowercase() == argument || profile.description().to_lowercase().contains(&argument));
+
+                    if let Some(profile) = profile {
+                        device.set_profile(profile.index() as i32);
+                    } else {
+                        pwvucontrol_warning!("No profile matching {argument} on {}", device.name().unwrap_or_default());
+                    }
+                });
+            }
+            RuleAction::SetRoute => {
+                let Some(device) = target.device() else {
+                    return true;
+                };
+
+                let route = match target {
+                    RuleTarget::Route(_, route) if argument.is_empty() => Some((*route).clone()),
+                    _ => [device.routemodel_output(), device.routemodel_input()]
+                        .iter()
+                        .flat_map(|model| model.iter::<PwRouteObject>().map_while(Result::ok).collect::<Vec<_>>())
+                        .filter(|route| target.direction().map_or(true, |x| x == route.direction()))
+                        .find(|route| route.description().to_lowercase().contains(&argument)),
+                };
+                let Some(route) = route else {
+                    if target.nodes(self).is_empty() {
+                        return false;
+                    }
+                    pwvucontrol_warning!("No port matching {argument} on {}", device.name().unwrap_or_default());
+                    return true;
+                };
+
+                if self.card_profile_device(&device, route.direction()).is_none() {
+                    return false;
+                }
+                self.select_route(&device, &route);
+            }
+        }
+
+        true
+    }
+
+    /// Selects a port of a device. It is selected on the card profile device of the matching node.
+    pub(crate) fn select_route(&self, device: &PwDeviceObject, route: &PwRouteObject) {
+        if let Some(card_profile_device) = self.card_profile_device(device, route.direction()) {
+            device.set_route(route.index(), card_profile_device);
+        } else {
+            pwvucontrol_warning!("Cannot select port {} without a matching node", route.description());
+        }
+    }
+
+    /// Card profile device of the sink or source of a device in the given direction.
+    pub(crate) fn card_profile_device(&self, device: &PwDeviceObject, direction: RouteDirection) -> Option<i32> {
+        self.get_nodes_for_device(device)
+            .iter()
+            .filter(|node| direction_for_nodetype(node.nodetype()) == Some(direction))
+            .find_map(|node| node.wpnode().device_index().ok().flatten())
+            .map(|x| x as i32)
+    }
+}
+
+/// Runs `f` once the device has enumerated its profiles.
+fn when_profiles_ready(device: &PwDeviceObject, f: impl FnOnce(&PwDeviceObject) + 'static) {
+    if device.profilemodel().n_items() > 0 {
+        f(device);
+        return;
+    }
+
+    let f = RefCell::new(Some(f));
+    let handler: Rc<RefCell<Option<glib::SignalHandlerId>>> = Default::default();
+
+    let id = device.connect_local(
+        "post-update-profile",
+        false,
+        clone!(@strong handler => move |values| {
+            let device = values[0].get::<PwDeviceObject>().expect("PwDeviceObject");
+            if let Some(f) = f.take() {
+                f(&device);
+            }
+            if let Some(id) = handler.take() {
+                device.disconnect(id);
+            }
+
+            None
+        }),
+    );
+    handler.replace(Some(id));
+}
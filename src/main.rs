@@ -28,6 +28,7 @@ mod macros;
 mod backend;
 mod ui;
 mod application;
+mod notifications;
 
 use std::{ffi::{OsStr, OsString}, path::PathBuf};
 
@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gettextrs::gettext;
+use gtk::{gio, glib, prelude::*};
+
+use crate::{
+    application::PwvucontrolApplication,
+    backend::{HistoryChange, MetadataExtFix, NodeType, ParamAvailability, PwDeviceObject, PwNodeObject, PwRouteObject, PwvucontrolManager},
+    config::APP_ID,
+    macros::*,
+};
+
+/// Kinds of events a desktop notification can be sent for, each enabled by its own setting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum NotificationKind {
+    NewDevice,
+    DefaultChanged,
+    Headphones,
+    StreamMoved,
+}
+
+impl NotificationKind {
+    fn settings_key(&self) -> &'static str {
+        match self {
+            NotificationKind::NewDevice => "notify-new-devices",
+            NotificationKind::DefaultChanged => "notify-default-changes",
+            NotificationKind::Headphones => "notify-headphones",
+            NotificationKind::StreamMoved => "notify-stream-moves",
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        gio::Settings::new(APP_ID).boolean(self.settings_key())
+    }
+}
+
+impl PwvucontrolApplication {
+    pub(crate) fn setup_notification_actions(&self) {
+        let make_default_node = gio::ActionEntry::builder("make-default-node")
+            .parameter_type(Some(&String::static_variant_type()))
+            .activate(move |_: &Self, _, parameter| {
+                let Some(node_name) = parameter.and_then(|x| x.get::<String>()) else {
+                    return;
+                };
+                let manager = PwvucontrolManager::default();
+                if let Some(node) = manager.node_by_name(&node_name) {
+                    manager.history().record(HistoryChange::default_node(node.nodetype(), &node_name));
+                    manager.set_default_configured_node_name(node.nodetype(), &node_name);
+                }
+            })
+            .build();
+        let make_default_device = gio::ActionEntry::builder("make-default-device")
+            .parameter_type(Some(&u32::static_variant_type()))
+            .activate(move |_: &Self, _, parameter| {
+                let manager = PwvucontrolManager::default();
+                if let Some(device) = parameter.and_then(|x| x.get::<u32>()).and_then(|id| manager.get_device_by_id(id)) {
+                    make_device_default(&manager, &device);
+                }
+            })
+            .build();
+        let use_route = gio::ActionEntry::builder("use-route")
+            .parameter_type(Some(&<(u32, u32)>::static_variant_type()))
+            .activate(move |_: &Self, _, parameter| {
+                let Some((device_id, route_index)) = parameter.and_then(|x| x.get::<(u32, u32)>()) else {
+                    return;
+                };
+                let manager = PwvucontrolManager::default();
+                let Some(device) = manager.get_device_by_id(device_id) else {
+                    return;
+                };
+                let route = device
+                    .routemodel_output()
+                    .iter::<PwRouteObject>()
+                    .map_while(Result::ok)
+                    .find(|route| route.index() == route_index);
+                if let Some(route) = route {
+                    if let Some(change) = manager.card_profile_device(&device, route.direction()).and_then(|x| HistoryChange::route(&device, route.index(), x)) {
+                        manager.history().record(change);
+                    }
+                    manager.select_route(&device, &route);
+                    make_device_default(&manager, &device);
+                }
+            })
+            .build();
+        let move_streams = gio::ActionEntry::builder("move-streams")
+            .parameter_type(Some(&<(String, Vec<u32>)>::static_variant_type()))
+            .activate(move |_: &Self, _, parameter| {
+                let Some((node_name, stream_ids)) = parameter.and_then(|x| x.get::<(String, Vec<u32>)>()) else {
+                    return;
+                };
+                let manager = PwvucontrolManager::default();
+                // The target has to be back before its streams can return to it.
+                let Some(target) = manager.node_by_name(&node_name) else {
+                    pwvucontrol_info!("Cannot move streams back, {node_name} is gone");
+                    return;
+                };
+                for stream in stream_ids.into_iter().filter_map(|id| manager.get_node_by_id(id)) {
+                    stream.set_default_target(&target);
+                }
+            })
+            .build();
+        self.add_action_entries([make_default_node, make_default_device, use_route, move_streams]);
+    }
+
+    /// A device appeared after startup.
+    pub(crate) fn notify_device_added(&self, device: &PwDeviceObject) {
+        if !NotificationKind::NewDevice.enabled() {
+            return;
+        }
+
+        let notification = gio::Notification::new(&gettext("New audio device"));
+        notification.set_body(Some(&device.name().unwrap_or_default()));
+        notification.set_icon(&gio::ThemedIcon::new(&device.icon_name()));
+        notification.add_button_with_target_value(
+            &gettext("Make default"),
+            "app.make-default-device",
+            Some(&device.wpdevice().bound_id().to_variant()),
+        );
+        self.send(NotificationKind::NewDevice, &notification);
+    }
+
+    /// The default sink or source changed. `old` is the previous default if it still exists.
+    pub(crate) fn notify_default_changed(&self, nodetype: NodeType, old: Option<&PwNodeObject>, new: &PwNodeObject) {
+        if !NotificationKind::DefaultChanged.enabled() {
+            return;
+        }
+
+        let title = match nodetype {
+            NodeType::Sink => gettext("Default output changed"),
+            _ => gettext("Default input changed"),
+        };
+        let notification = gio::Notification::new(&title);
+        notification.set_body(Some(&new.name()));
+        notification.set_icon(&gio::ThemedIcon::new(&new.iconname()));
+        if let Some(old) = old {
+            let old_name: String = old.node_property("node.name");
+            notification.add_button_with_target_value(&gettext("Undo"), "app.make-default-node", Some(&old_name.to_variant()));
+        }
+        let new_name: String = new.node_property("node.name");
+        notification.add_button_with_target_value(&gettext("Make default"), "app.make-default-node", Some(&new_name.to_variant()));
+        self.send(NotificationKind::DefaultChanged, &notification);
+    }
+
+    /// A headphone port of a device became available or unavailable.
+    pub(crate) fn notify_headphones(&self, device: &PwDeviceObject, route: &PwRouteObject) {
+        if !NotificationKind::Headphones.enabled() {
+            return;
+        }
+
+        let available = route.availability() == ParamAvailability::Yes;
+        let title = if available {
+            gettext("Headphones plugged in")
+        } else {
+            gettext("Headphones unplugged")
+        };
+        let notification = gio::Notification::new(&title);
+        notification.set_body(Some(&device.name().unwrap_or_default()));
+        notification.set_icon(&gio::ThemedIcon::new("audio-headphones-symbolic"));
+        if available {
+            notification.add_button_with_target_value(
+                &gettext("Make default"),
+                "app.use-route",
+                Some(&(device.wpdevice().bound_id(), route.index()).to_variant()),
+            );
+        }
+        self.send(NotificationKind::Headphones, &notification);
+    }
+
+    /// Called before a sink or source goes away, for the streams that were explicitly sent to it.
+    pub(crate) fn notify_streams_moved(&self, target: &PwNodeObject) {
+        if !NotificationKind::StreamMoved.enabled() {
+            return;
+        }
+
+        let manager = PwvucontrolManager::default();
+        let Some(metadata) = manager.metadata() else {
+            return;
+        };
+
+        let serial = target.serial().to_string();
+        let id = target.boundid().to_string();
+        let streams: Vec<PwNodeObject> = manager
+            .node_model()
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .filter(|node| matches!(node.nodetype(), NodeType::StreamOutput | NodeType::StreamInput) && !node.hidden())
+            .filter(|node| {
+                metadata.find_notype(node.boundid(), "target.object").is_some_and(|x| x == serial)
+                    || metadata.find_notype(node.boundid(), "target.node").is_some_and(|x| x == id)
+            })
+            .collect();
+        if streams.is_empty() {
+            return;
+        }
+
+        let names: Vec<String> = streams.iter().map(|node| node.name()).collect();
+        pwvucontrol_info!("Streams {names:?} lose their target {}", target.name());
+
+        let notification = gio::Notification::new(&gettext("Streams moved to the default device"));
+        notification.set_body(Some(&format!("{}: {}", target.name(), names.join(", "))));
+        notification.set_icon(&gio::ThemedIcon::new(&target.iconname()));
+
+        let target_name: String = target.node_property("node.name");
+        let stream_ids: Vec<u32> = streams.iter().map(|node| node.boundid()).collect();
+        notification.add_button_with_target_value(&gettext("Move back"), "app.move-streams", Some(&(target_name, stream_ids).to_variant()));
+
+        // Keeps the streams where they went, unless the target itself was the default.
+        let default_id = manager.default_node_id(target.nodetype());
+        if let Some(default) = manager.get_node_by_id(default_id).filter(|node| node != target) {
+            let default_name: String = default.node_property("node.name");
+            notification.add_button_with_target_value(&gettext("Make default"), "app.make-default-node", Some(&default_name.to_variant()));
+        }
+        self.send(NotificationKind::StreamMoved, &notification);
+    }
+
+    fn send(&self, kind: NotificationKind, notification: &gio::Notification) {
+        // A newer notification of the same kind replaces the previous one.
+        self.send_notification(Some(kind.settings_key()), notification);
+    }
+}
+
+/// Makes the sink of a device the default, or its source if it has no sink.
+fn make_device_default(manager: &PwvucontrolManager, device: &PwDeviceObject) {
+    let nodes = manager.get_nodes_for_device(device);
+    let node = nodes
+        .iter()
+        .find(|node| node.nodetype() == NodeType::Sink)
+        .or_else(|| nodes.iter().find(|node| node.nodetype() == NodeType::Source));
+
+    if let Some(node) = node {
+        let node_name: String = node.node_property("node.name");
+        manager.history().record(HistoryChange::default_node(node.nodetype(), &node_name));
+        manager.set_default_configured_node_name(node.nodetype(), &node_name);
+    }
+}
@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{NodeControl, PwAppGroupObject, PwNodeObject, PwvucontrolManager},
+    ui::{PwStreamBox, PwvucontrolWindow, WithDefaultListModel},
+};
+use adw::subclass::prelude::*;
+use gettextrs::ngettext;
+use glib::{clone, closure_local};
+use gtk::prelude::*;
+use std::cell::{Cell, OnceCell, RefCell};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/appgrouprow.ui")]
+    #[properties(wrapper_type = super::PwAppGroupRow)]
+    pub struct PwAppGroupRow {
+        #[property(get, set, construct_only)]
+        pub(super) group: OnceCell<PwAppGroupObject>,
+
+        #[template_child]
+        pub icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub subtitle_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub target_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub mutebtn: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub volume_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub streams_list: TemplateChild<gtk::ListBox>,
+
+        pub(super) dropdown_model: RefCell<Option<WithDefaultListModel>>,
+        pub(super) block_signal: Cell<bool>,
+        /// Handlers on the manager and the settings, which outlive the row.
+        metadata_changed_handler: RefCell<Option<glib::SignalHandlerId>>,
+        overamplification_handler: RefCell<Option<glib::SignalHandlerId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwAppGroupRow {
+        const NAME: &'static str = "PwAppGroupRow";
+        type Type = super::PwAppGroupRow;
+        type ParentType = gtk::ListBoxRow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwAppGroupRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let group = obj.group();
+            let manager = PwvucontrolManager::default();
+
+            group.bind_property("iconname", &self.icon.get(), "icon-name").sync_create().build();
+            group.bind_property("name", &self.title_label.get(), "label").sync_create().build();
+            group.bind_property("mute", &self.mutebtn.get(), "active").sync_create().build();
+            self.mutebtn.connect_clicked(clone!(@weak group => move |button| {
+                let history = PwvucontrolManager::default().history();
+                history.record_nodes(NodeControl::Mute, &group.nodes(), || group.set_mute(button.is_active()));
+            }));
+            group.bind_property("volume", &self.volume_scale.adjustment(), "value")
+                .sync_create()
+                .bidirectional()
+                .transform_to(|_, volume: f32| Some(volume.cbrt() as f64))
+                .transform_from(|_, value: f64| Some((value * value * value) as f32))
+                .build();
+            self.volume_scale.connect_change_value(clone!(@weak group => @default-return glib::Propagation::Proceed, move |_, _, _| {
+                PwvucontrolManager::default().history().record_nodes_later(NodeControl::Volume, &group.nodes());
+                glib::Propagation::Proceed
+            }));
+
+            self.volume_scale.set_format_value_func(|_scale, value| {
+                format!("{:>16}", format!("{:.0}% ({:.2} dB)", value * 100.0, (value * value * value).log10() * 20.0))
+            });
+            let settings = PwvucontrolWindow::default().imp().settings.clone();
+            let update_range = clone!(@weak self as widget => move |settings: &gtk::gio::Settings| {
+                let upper = if settings.boolean("enable-overamplification") { 1.525 } else { 1.0 };
+                widget.volume_scale.set_range(0.0, upper);
+            });
+            update_range(&settings);
+            let handler = settings.connect_changed(Some("enable-overamplification"), move |settings, _| update_range(settings));
+            self.overamplification_handler.replace(Some(handler));
+
+            let streams = group.streams();
+            self.streams_list.bind_model(Some(&streams), |item| {
+                let node = item.downcast_ref::<PwNodeObject>().expect("PwNodeObject");
+                PwStreamBox::new(node).upcast::<gtk::Widget>()
+            });
+            streams.connect_items_changed(clone!(@weak obj => move |_, _, _, _| {
+                obj.update_subtitle();
+                obj.update_target();
+            }));
+            obj.update_subtitle();
+
+            let dropdown_model = WithDefaultListModel::new(Some(&manager.sink_model()));
+            self.target_dropdown.set_expression(Some(gtk::ClosureExpression::new::<Option<String>>(
+                gtk::Expression::NONE,
+                closure_local!(move |item: glib::Object| {
+                    if let Some(item) = item.downcast_ref::<PwNodeObject>() {
+                        Some(item.name())
+                    } else {
+                        item.downcast_ref::<gtk::StringObject>().map(|item| item.string().to_string())
+                    }
+                }),
+            )));
+            self.target_dropdown.set_model(Some(&dropdown_model));
+            self.dropdown_model.replace(Some(dropdown_model));
+
+            self.target_dropdown.connect_selected_notify(clone!(@weak obj => move |dropdown| {
+                if obj.imp().block_signal.get() || dropdown.selected() == gtk::INVALID_LIST_POSITION {
+                    return;
+                }
+                let target = dropdown.selected_item().and_downcast::<PwNodeObject>();
+                let group = obj.group();
+                let history = PwvucontrolManager::default().history();
+                history.record_nodes(NodeControl::Target, &group.nodes(), || group.move_all(target.as_ref()));
+            }));
+
+            let handler = manager.connect_local("metadata-changed", false, clone!(@weak obj => @default-return None, move |_| {
+                obj.update_target();
+                None
+            }));
+            self.metadata_changed_handler.replace(Some(handler));
+            obj.update_target();
+        }
+
+        fn dispose(&self) {
+            if let Some(handler) = self.metadata_changed_handler.take() {
+                PwvucontrolManager::default().disconnect(handler);
+            }
+            if let Some(handler) = self.overamplification_handler.take() {
+                PwvucontrolWindow::default().imp().settings.disconnect(handler);
+            }
+        }
+    }
+    impl WidgetImpl for PwAppGroupRow {}
+    impl ListBoxRowImpl for PwAppGroupRow {}
+}
+
+glib::wrapper! {
+    pub struct PwAppGroupRow(ObjectSubclass<imp::PwAppGroupRow>)
+        @extends gtk::Widget, gtk::ListBoxRow,
+        @implements gtk::Actionable;
+}
+
+impl PwAppGroupRow {
+    pub(crate) fn new(group: &PwAppGroupObject) -> Self {
+        glib::Object::builder().property("group", group).build()
+    }
+
+    fn update_subtitle(&self) {
+        let count = self.group().streams().n_items();
+        self.imp().subtitle_label.set_label(&ngettext("{} stream", "{} streams", count).replace("{}", &count.to_string()));
+    }
+
+    /// Shows the target the streams share, "Default" when none is set and nothing when they differ.
+    fn update_target(&self) {
+        let imp = self.imp();
+        let manager = PwvucontrolManager::default();
+
+        let targets: Vec<Option<u32>> = self
+            .group()
+            .streams()
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .map(|node| node.default_target().map(|target| target.boundid()))
+            .collect();
+
+        let position = match targets.first() {
+            Some(first) if targets.iter().all(|target| target == first) => match first {
+                Some(id) => manager.sink_model().get_node_pos_from_id(*id).map_or(gtk::INVALID_LIST_POSITION, |pos| pos + 1),
+                None => 0,
+            },
+            _ => gtk::INVALID_LIST_POSITION,
+        };
+
+        let blocked = imp.block_signal.replace(true);
+        imp.target_dropdown.set_selected(position);
+        imp.block_signal.set(blocked);
+    }
+}
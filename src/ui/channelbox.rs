@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::{backend::PwChannelObject, ui::PwvucontrolWindow};
+use crate::{backend::{NodeControl, PwChannelObject, PwvucontrolManager}, ui::nodebindings};
 use std::cell::RefCell;
 use gtk::{prelude::*, subclass::prelude::*};
 use glib::clone;
@@ -14,6 +14,8 @@ mod imp {
     pub struct PwChannelBox {
         #[property(get, set, construct_only)]
         channel_object: RefCell<Option<PwChannelObject>>,
+        /// Handler on the window settings, which outlive the row.
+        overamplification_handler: RefCell<Option<glib::SignalHandlerId>>,
 
         // Template widgets
         #[template_child]
@@ -43,15 +45,6 @@ mod imp {
     impl ObjectImpl for PwChannelBox {
 
         fn constructed(&self) {
-            fn linear_to_cubic(_binding: &glib::Binding, i: f32) -> Option<f64> {
-                Some(i.cbrt() as f64)
-            }
-
-            fn cubic_to_linear(_binding: &glib::Binding, i: f64) -> Option<f32> {
-                Some((i * i * i) as f32)
-            }
-
-
             self.parent_constructed();
 
             let item = self.channel_object.borrow();
@@ -60,37 +53,28 @@ mod imp {
             item.bind_property("volume", &self.scale.adjustment(), "value")
                 .sync_create()
                 .bidirectional()
-                .transform_to(linear_to_cubic)
-                .transform_from(cubic_to_linear)
+                .transform_to(nodebindings::linear_to_cubic)
+                .transform_from(nodebindings::cubic_to_linear)
                 .build();
+            self.scale.connect_change_value(clone!(@weak item => @default-return glib::Propagation::Proceed, move |_, _, _| {
+                if let Some(node) = item.node_object() {
+                    PwvucontrolManager::default().history().record_nodes_later(NodeControl::ChannelVolumes, &[node]);
+                }
+                glib::Propagation::Proceed
+            }));
 
             item.bind_property("name", &self.label.get(), "label")
                 .sync_create()
                 .build();
 
-            fn update_overamplification(volume_scale: &gtk::Scale) {
-                let window: PwvucontrolWindow = PwvucontrolWindow::default();
-                let enable_overamplification = window.imp().settings.boolean("enable-overamplification");
-                
-                volume_scale.clear_marks();
-                volume_scale.add_mark(0.0, gtk::PositionType::Bottom, Some("Silence"));
-                volume_scale.add_mark(1.0, gtk::PositionType::Bottom, Some("100%"));
-    
+            let handler = nodebindings::watch_overamplification(&self.scale, nodebindings::update_volume_range);
+            self.overamplification_handler.replace(Some(handler));
+        }
 
-                if enable_overamplification {
-                    volume_scale.add_mark(1.525, gtk::PositionType::Bottom, Some("150%"));
-                    volume_scale.set_range(0.0, 1.525);
-                } else {
-                    volume_scale.set_range(0.0, 1.0);
-                }
+        fn dispose(&self) {
+            if let Some(handler) = self.overamplification_handler.take() {
+                nodebindings::unwatch_overamplification(handler);
             }
-
-            update_overamplification(&self.scale);
-
-            let window = PwvucontrolWindow::default();
-            window.imp().settings.connect_changed(Some("enable-overamplification"), clone!(@weak self as widget => move |_,_| {
-                update_overamplification(&widget.scale);
-            }));
         }
 
 
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Debug;
+
+use pipewire::{properties, spa};
+
+use crate::ui::PwStream;
+
+const RATE: u32 = 48000;
+const STRIDE: usize = std::mem::size_of::<f32>();
+/// One clap per second.
+const CLAP_INTERVAL: u64 = RATE as u64;
+/// Each clap is a 10 ms decaying tone burst.
+const CLAP_LENGTH: u64 = RATE as u64 / 100;
+const CLAP_FREQUENCY: f32 = 1000.0;
+
+/// Plays a clap once per second to a sink, for calibrating its latency offset against a visual cue.
+pub struct ClapPlayer {
+    _stream: PwStream<u64>,
+}
+
+impl Debug for ClapPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ClapPlayer")
+    }
+}
+
+impl ClapPlayer {
+    /// `on_clap` is called with the delay in milliseconds from now until the clap
+    /// leaves our stream, not counting the latency of the sink.
+    pub fn new(id: u32, on_clap: impl Fn(f64) + 'static) -> Result<Self, anyhow::Error> {
+        let props = properties! {
+            "media.type" => "Audio",
+            "media.category" => "Playback",
+            "media.role" => "Production",
+            "node.name" => "pwvucontrol-clap-test",
+            "media.name" => "Latency calibration",
+            "node.latency" => "256/48000"
+        };
+
+        let stream = PwStream::new("clap-test", props, spa::Direction::Output, id, RATE, &[spa::sys::SPA_AUDIO_CHANNEL_MONO], |stream| {
+            stream.add_local_listener::<u64>()
+            .process(move |stream, position| {
+                let Some(mut buffer) = stream.dequeue_buffer() else {
+                    return;
+                };
+
+                let datas = buffer.datas_mut();
+                let data = &mut datas[0];
+
+                let n_frames = if let Some(slice) = data.data() {
+                    let n_frames = slice.len() / STRIDE;
+
+                    for frame in 0..n_frames {
+                        let pos = (*position + frame as u64) % CLAP_INTERVAL;
+                        if pos == 0 {
+                            on_clap(frame as f64 * 1000.0 / RATE as f64);
+                        }
+
+                        let sample = if pos < CLAP_LENGTH {
+                            let t = pos as f32 / RATE as f32;
+                            let envelope = 1.0 - pos as f32 / CLAP_LENGTH as f32;
+                            (2.0 * std::f32::consts::PI * CLAP_FREQUENCY * t).sin() * envelope * 0.8
+                        } else {
+                            0.0
+                        };
+
+                        slice[frame * STRIDE..(frame + 1) * STRIDE].copy_from_slice(&sample.to_le_bytes());
+                    }
+
+                    *position += n_frames as u64;
+                    n_frames
+                } else {
+                    0
+                };
+
+                let chunk = data.chunk_mut();
+                *chunk.offset_mut() = 0;
+                *chunk.stride_mut() = STRIDE as _;
+                *chunk.size_mut() = (STRIDE * n_frames) as _;
+            })
+            .register()
+        })?;
+
+        Ok(Self { _stream: stream })
+    }
+}
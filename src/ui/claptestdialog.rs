@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{backend::PwNodeObject, macros::*, ui::ClapPlayer};
+use adw::subclass::prelude::*;
+use glib::clone;
+use gtk::prelude::*;
+use std::{cell::RefCell, time::Duration};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/claptestdialog.ui")]
+    #[properties(wrapper_type = super::PwClapTestDialog)]
+    pub struct PwClapTestDialog {
+        #[property(get, set, construct_only)]
+        pub(super) node_object: RefCell<Option<PwNodeObject>>,
+
+        #[template_child]
+        pub flash: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub latencyoffset_spin: TemplateChild<gtk::SpinButton>,
+
+        pub(super) player: RefCell<Option<ClapPlayer>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwClapTestDialog {
+        const NAME: &'static str = "PwClapTestDialog";
+        type Type = super::PwClapTestDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwClapTestDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let item = self.obj().node_object().expect("Node object");
+
+            item.bind_property("latencyoffset", &self.latencyoffset_spin.adjustment(), "value")
+                .sync_create()
+                .bidirectional()
+                .build();
+
+            let obj = self.obj();
+            match ClapPlayer::new(item.boundid(), clone!(@weak obj => move |delay| obj.schedule_flash(delay))) {
+                Ok(player) => {
+                    self.player.replace(Some(player));
+                },
+                Err(e) => {
+                    pwvucontrol_warning!("Cannot start clap test on {}: {e}", item.name());
+                }
+            }
+        }
+
+        fn dispose(&self) {
+            self.player.take();
+        }
+    }
+    impl WidgetImpl for PwClapTestDialog {}
+    impl WindowImpl for PwClapTestDialog {
+        fn close_request(&self) -> glib::Propagation {
+            self.player.take();
+            self.parent_close_request()
+        }
+    }
+    impl AdwWindowImpl for PwClapTestDialog {}
+}
+
+glib::wrapper! {
+    pub struct PwClapTestDialog(ObjectSubclass<imp::PwClapTestDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwClapTestDialog {
+    pub(crate) fn new(parent: &impl IsA<gtk::Window>, node_object: &PwNodeObject) -> Self {
+        glib::Object::builder()
+            .property("transient-for", parent)
+            .property("node-object", node_object)
+            .build()
+    }
+
+    /// Flashes at the moment a sync-aware player would show the frame belonging to the clap,
+    /// i.e. after the reported latency of the sink, which includes the latency offset.
+    fn schedule_flash(&self, delay: f64) {
+        let Some(item) = self.node_object() else {
+            return;
+        };
+
+        let delay = (delay + item.latency()).max(0.0);
+
+        glib::timeout_add_local_once(Duration::from_secs_f64(delay / 1000.0), clone!(@weak self as obj => move || {
+            obj.imp().flash.add_css_class("active");
+
+            glib::timeout_add_local_once(Duration::from_millis(100), clone!(@weak obj => move || {
+                obj.imp().flash.remove_css_class("active");
+            }));
+        }));
+    }
+}
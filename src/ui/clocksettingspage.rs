@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{backend::PwvucontrolManager, macros::*};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use glib::{clone, closure_local};
+use std::cell::{Cell, RefCell};
+use wireplumber as wp;
+
+/// Quantum sizes offered when forcing the graph quantum.
+const QUANTUMS: [u32; 9] = [32, 64, 128, 256, 512, 1024, 2048, 4096, 8192];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/clocksettingspage.ui")]
+    pub struct PwClockSettingsPage {
+        #[template_child]
+        pub rate_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub allowed_rates_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub quantum_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub min_quantum_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub max_quantum_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub force_rate_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub force_quantum_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub reset_button: TemplateChild<gtk::Button>,
+
+        /// Rates currently listed in the force rate row, after the "Not forced" entry.
+        pub(super) rates: RefCell<Vec<u32>>,
+        pub(super) block_signal: Cell<bool>,
+        /// The settings metadata followed, with the handler of its `changed` signal.
+        pub(super) metadata_handler: RefCell<Option<(wp::pw::Metadata, glib::SignalHandlerId)>>,
+        pub(super) manager_handler: RefCell<Option<glib::SignalHandlerId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwClockSettingsPage {
+        const NAME: &'static str = "PwClockSettingsPage";
+        type Type = super::PwClockSettingsPage;
+        type ParentType = adw::PreferencesPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PwClockSettingsPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let mut quantums = vec![gettext("Not forced")];
+            quantums.extend(QUANTUMS.iter().map(|quantum| quantum.to_string()));
+            let quantums: Vec<&str> = quantums.iter().map(String::as_str).collect();
+            self.force_quantum_row.set_model(Some(&gtk::StringList::new(&quantums)));
+
+            self.force_rate_row.connect_selected_notify(clone!(@weak self as widget => move |row| {
+                if widget.block_signal.get() {
+                    return;
+                }
+                let rate = match row.selected() {
+                    0 | gtk::INVALID_LIST_POSITION => 0,
+                    n => widget.rates.borrow().get(n as usize - 1).copied().unwrap_or_default(),
+                };
+                pwvucontrol_info!("Forcing clock rate to {rate}");
+                PwvucontrolManager::default().set_setting("clock.force-rate", Some(&rate.to_string()));
+            }));
+
+            self.force_quantum_row.connect_selected_notify(clone!(@weak self as widget => move |row| {
+                if widget.block_signal.get() {
+                    return;
+                }
+                let quantum = match row.selected() {
+                    0 | gtk::INVALID_LIST_POSITION => 0,
+                    n => QUANTUMS.get(n as usize - 1).copied().unwrap_or_default(),
+                };
+                pwvucontrol_info!("Forcing clock quantum to {quantum}");
+                PwvucontrolManager::default().set_setting("clock.force-quantum", Some(&quantum.to_string()));
+            }));
+
+            self.reset_button.connect_clicked(|_| {
+                let manager = PwvucontrolManager::default();
+                manager.set_setting("clock.force-rate", Some("0"));
+                manager.set_setting("clock.force-quantum", Some("0"));
+            });
+
+            // The settings metadata may only appear after the page is built.
+            let manager = PwvucontrolManager::default();
+            let handler = manager.connect_settings_metadata_notify(clone!(@weak self as widget => move |_| {
+                widget.obj().follow_settings_metadata();
+            }));
+            self.manager_handler.replace(Some(handler));
+
+            self.obj().follow_settings_metadata();
+        }
+
+        fn dispose(&self) {
+            if let Some(handler) = self.manager_handler.take() {
+                PwvucontrolManager::default().disconnect(handler);
+            }
+            if let Some((metadata, handler)) = self.metadata_handler.take() {
+                metadata.disconnect(handler);
+            }
+        }
+    }
+    impl WidgetImpl for PwClockSettingsPage {}
+    impl PreferencesPageImpl for PwClockSettingsPage {}
+}
+
+glib::wrapper! {
+    pub struct PwClockSettingsPage(ObjectSubclass<imp::PwClockSettingsPage>)
+        @extends gtk::Widget, adw::PreferencesPage;
+}
+
+impl PwClockSettingsPage {
+    /// Listens to changes of the current settings metadata, instead of the one before.
+    fn follow_settings_metadata(&self) {
+        let imp = self.imp();
+
+        if let Some((metadata, handler)) = imp.metadata_handler.take() {
+            metadata.disconnect(handler);
+        }
+
+        if let Some(metadata) = PwvucontrolManager::default().settings_metadata() {
+            let widget = self;
+            let changed_closure = closure_local!(@watch widget =>
+                move |_obj: &wp::pw::Metadata, id: u32, key: Option<String>, _type: Option<String>, _value: Option<String>| {
+                if id == 0 && key.unwrap_or_default().starts_with("clock.") {
+                    widget.update_values();
+                }
+            });
+            let handler = metadata.connect_closure("changed", false, changed_closure);
+            imp.metadata_handler.replace(Some((metadata, handler)));
+        } else {
+            pwvucontrol_warning!("Cannot get settings metadata object");
+        }
+
+        self.update_values();
+    }
+
+    fn update_values(&self) {
+        let imp = self.imp();
+        let manager = PwvucontrolManager::default();
+
+        let setting = |key: &str| manager.setting(key).and_then(|value| value.trim().parse::<u32>().ok());
+
+        let rate = setting("clock.rate");
+        let mut rates = manager
+            .setting("clock.allowed-rates")
+            .map(|value| parse_rates(&value))
+            .unwrap_or_default();
+        if rates.is_empty() {
+            rates.extend(rate);
+        }
+
+        let unknown = || "—".to_string();
+
+        imp.rate_label.set_label(&rate.map_or_else(unknown, |rate| format!("{rate} Hz")));
+        imp.allowed_rates_label.set_label(&if rates.is_empty() {
+            unknown()
+        } else {
+            rates.iter().map(u32::to_string).collect::<Vec<_>>().join(", ")
+        });
+
+        let format_quantum = |quantum: Option<u32>| {
+            quantum.map_or_else(unknown, |quantum| match rate {
+                Some(rate) if rate > 0 => format!("{quantum} ({:.1} ms)", quantum as f64 * 1000.0 / rate as f64),
+                _ => quantum.to_string(),
+            })
+        };
+        imp.quantum_label.set_label(&format_quantum(setting("clock.quantum")));
+        imp.min_quantum_label.set_label(&format_quantum(setting("clock.min-quantum")));
+        imp.max_quantum_label.set_label(&format_quantum(setting("clock.max-quantum")));
+
+        let force_rate = setting("clock.force-rate").unwrap_or_default();
+        if force_rate != 0 && !rates.contains(&force_rate) {
+            rates.push(force_rate);
+        }
+        let force_quantum = setting("clock.force-quantum").unwrap_or_default();
+
+        imp.block_signal.set(true);
+
+        if *imp.rates.borrow() != rates {
+            let mut items = vec![gettext("Not forced")];
+            items.extend(rates.iter().map(|rate| format!("{rate} Hz")));
+            let items: Vec<&str> = items.iter().map(String::as_str).collect();
+            imp.force_rate_row.set_model(Some(&gtk::StringList::new(&items)));
+            imp.rates.replace(rates);
+        }
+
+        let position = imp.rates.borrow().iter().position(|rate| *rate == force_rate);
+        imp.force_rate_row.set_selected(position.map_or(0, |pos| pos as u32 + 1));
+
+        let position = QUANTUMS.iter().position(|quantum| *quantum == force_quantum);
+        imp.force_quantum_row.set_selected(position.map_or(0, |pos| pos as u32 + 1));
+
+        imp.block_signal.set(false);
+    }
+}
+
+/// Parses `clock.allowed-rates`, which is a SPA JSON array such as `[ 44100 48000 ]`.
+fn parse_rates(value: &str) -> Vec<u32> {
+    value
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|rate| rate.parse().ok())
+        .collect()
+}
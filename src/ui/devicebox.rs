@@ -1,8 +1,9 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::{backend::PwDeviceObject, ui::PwProfileDropDown};
+use crate::{backend::{HistoryChange, PwDeviceObject, PwProfileObject, PwvucontrolManager, RouteDirection}, ui::{PwInspectorDialog, PwProfileDropDown, PwvucontrolWindow}};
+use glib::{clone, closure_local};
 use gtk::{prelude::*, subclass::prelude::*};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 mod imp {
     use super::*;
@@ -14,12 +15,50 @@ mod imp {
         #[template_child]
         pub icon: TemplateChild<gtk::Image>,
         #[template_child]
-        pub label: TemplateChild<gtk::Label>,
+        pub label: TemplateChild<gtk::EditableLabel>,
+        #[template_child]
+        pub reset_name_button: TemplateChild<gtk::Button>,
         #[template_child]
         pub profile_dropdown: TemplateChild<PwProfileDropDown>,
+        #[template_child]
+        pub remember_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub properties_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub disable_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub codec_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub battery_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub battery_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub battery_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub output_route_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub output_route_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub output_route_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub output_route_mute: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub input_route_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub input_route_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub input_route_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub input_route_mute: TemplateChild<gtk::ToggleButton>,
 
         #[property(get, set, construct_only)]
         pub deviceobject: RefCell<Option<PwDeviceObject>>,
+
+        pub(super) block_codec: Cell<bool>,
+        pub(super) route_bindings: RefCell<Vec<glib::Binding>>,
+        /// Set while the route controls are rebound, so their old values are not written to the new routes.
+        pub(super) block_route: Cell<bool>,
+        pub(super) overamplification_handler: RefCell<Option<glib::SignalHandlerId>>,
     }
 
     #[glib::object_subclass]
@@ -48,16 +87,83 @@ mod imp {
             let deviceobject = obj.deviceobject().expect("Device object");
 
             deviceobject
-                .bind_property("name", &self.label.get(), "label")
+                .bind_property("name", &self.label.get(), "text")
                 .sync_create()
                 .build();
 
+            deviceobject
+                .bind_property("renamed", &self.reset_name_button.get(), "visible")
+                .sync_create()
+                .build();
+
+            self.label.connect_editing_notify(clone!(@weak deviceobject => move |label| {
+                if label.is_editing() {
+                    return;
+                }
+                let text = label.text();
+                if text.is_empty() {
+                    deviceobject.rename(None);
+                } else if deviceobject.name().as_deref() != Some(text.as_str()) {
+                    deviceobject.rename(Some(&text));
+                }
+            }));
+
+            self.reset_name_button.connect_clicked(clone!(@weak deviceobject => move |_| deviceobject.rename(None)));
+
             deviceobject
                 .bind_property("icon-name", &self.icon.get(), "icon-name")
                 .sync_create()
                 .build();
 
             self.profile_dropdown.set_deviceobject(obj.deviceobject());
+
+            deviceobject
+                .bind_property("remember", &self.remember_button.get(), "active")
+                .sync_create()
+                .bidirectional()
+                .build();
+
+            self.properties_button.connect_clicked(clone!(@weak deviceobject => move |_| {
+                let window = PwvucontrolWindow::default();
+                PwInspectorDialog::new(&window, &deviceobject.wpdevice()).present();
+            }));
+
+            self.disable_button.connect_clicked(clone!(@weak deviceobject => move |_| deviceobject.disable()));
+
+            self.setup_route_controls(RouteDirection::Output);
+            self.setup_route_controls(RouteDirection::Input);
+
+            deviceobject.connect_local(
+                "post-update-route",
+                false,
+                clone!(@weak obj => @default-return None, move |_| {
+                    obj.imp().bind_routes();
+
+                    None
+                }),
+            );
+            deviceobject.connect_route_index_output_notify(clone!(@weak obj => move |_| obj.imp().bind_routes()));
+            deviceobject.connect_route_index_input_notify(clone!(@weak obj => move |_| obj.imp().bind_routes()));
+            self.bind_routes();
+
+            let settings = &PwvucontrolWindow::default().imp().settings;
+            let handler = settings.connect_changed(Some("enable-overamplification"), clone!(@weak obj => move |_, _| {
+                obj.imp().bind_routes();
+            }));
+            self.overamplification_handler.replace(Some(handler));
+
+            if deviceobject.is_bluetooth() {
+                self.setup_codec_dropdown(&deviceobject);
+
+                deviceobject.connect_battery_notify(clone!(@weak obj => move |deviceobject| obj.imp().update_battery(deviceobject)));
+                self.update_battery(&deviceobject);
+            }
+        }
+
+        fn dispose(&self) {
+            if let Some(handler) = self.overamplification_handler.take() {
+                PwvucontrolWindow::default().imp().settings.disconnect(handler);
+            }
         }
     }
     impl WidgetImpl for PwDeviceBox {}
@@ -65,6 +171,189 @@ mod imp {
 
     #[gtk::template_callbacks]
     impl PwDeviceBox {}
+
+    impl PwDeviceBox {
+        fn setup_codec_dropdown(&self, deviceobject: &PwDeviceObject) {
+            let codecmodel = deviceobject.codecmodel();
+
+            let expression = gtk::PropertyExpression::new(PwProfileObject::static_type(), gtk::Expression::NONE, "codec")
+                .chain_closure::<String>(closure_local!(|_: Option<glib::Object>, codec: Option<String>| {
+                    codec.unwrap_or_default().to_uppercase()
+                }));
+            self.codec_dropdown.set_expression(Some(expression));
+
+            self.block_codec.set(true);
+            self.codec_dropdown.set_model(Some(&codecmodel));
+            self.block_codec.set(false);
+
+            let widget = self.obj();
+            deviceobject.connect_local(
+                "pre-update-codec",
+                false,
+                clone!(@weak widget => @default-return None, move |_| {
+                    widget.imp().block_codec.set(true);
+
+                    None
+                }),
+            );
+            deviceobject.connect_local(
+                "post-update-codec",
+                false,
+                clone!(@weak widget => @default-return None, move |_| {
+                    widget.imp().block_codec.set(false);
+                    widget.imp().update_codec();
+
+                    None
+                }),
+            );
+            deviceobject.connect_codec_notify(clone!(@weak widget => move |_| widget.imp().update_codec()));
+            self.update_codec();
+
+            self.codec_dropdown.connect_selected_item_notify(clone!(@weak widget => move |dropdown| {
+                if widget.imp().block_codec.get() {
+                    return;
+                }
+                let (Some(deviceobject), Some(profile)) = (widget.deviceobject(), dropdown.selected_item().and_downcast::<PwProfileObject>()) else {
+                    return;
+                };
+                if profile.index() != deviceobject.profile_index() {
+                    PwvucontrolManager::default().history().record(HistoryChange::profile(&deviceobject, profile.index() as i32));
+                    deviceobject.set_profile(profile.index() as i32);
+                }
+            }));
+        }
+
+        fn update_codec(&self) {
+            let deviceobject = self.obj().deviceobject().expect("Device object");
+            let codecmodel = deviceobject.codecmodel();
+
+            self.codec_dropdown.set_visible(codecmodel.n_items() > 1);
+
+            let position = (0u32..)
+                .zip(codecmodel.iter::<PwProfileObject>().map_while(Result::ok))
+                .find(|(_, profile)| profile.index() == deviceobject.profile_index())
+                .or_else(|| {
+                    (0u32..)
+                        .zip(codecmodel.iter::<PwProfileObject>().map_while(Result::ok))
+                        .find(|(_, profile)| deviceobject.codec().is_some() && profile.codec() == deviceobject.codec())
+                })
+                .map_or(gtk::INVALID_LIST_POSITION, |(position, _)| position);
+
+            let blocked = self.block_codec.replace(true);
+            self.codec_dropdown.set_selected(position);
+            self.block_codec.set(blocked);
+        }
+
+        fn route_widgets(&self, direction: RouteDirection) -> (gtk::Box, gtk::Label, gtk::Scale, gtk::ToggleButton) {
+            match direction {
+                RouteDirection::Output => (
+                    self.output_route_box.get(),
+                    self.output_route_label.get(),
+                    self.output_route_scale.get(),
+                    self.output_route_mute.get(),
+                ),
+                _ => (
+                    self.input_route_box.get(),
+                    self.input_route_label.get(),
+                    self.input_route_scale.get(),
+                    self.input_route_mute.get(),
+                ),
+            }
+        }
+
+        /// Changes coming from the bound route are recognized by comparing with its current values,
+        /// so only user changes are written back.
+        fn setup_route_controls(&self, direction: RouteDirection) {
+            let (_, _, scale, mute) = self.route_widgets(direction);
+            let widget = self.obj();
+
+            scale.connect_value_changed(clone!(@weak widget => move |scale| {
+                if widget.imp().block_route.get() {
+                    return;
+                }
+                let Some(deviceobject) = widget.deviceobject() else {
+                    return;
+                };
+                let Some(route) = deviceobject.active_route(direction) else {
+                    return;
+                };
+
+                let volume = scale.value().powi(3) as f32;
+                if (volume - route.volume()).abs() > f32::EPSILON {
+                    deviceobject.set_route_volume(direction, volume);
+                }
+            }));
+
+            mute.connect_toggled(clone!(@weak widget => move |mute| {
+                if widget.imp().block_route.get() {
+                    return;
+                }
+                let Some(deviceobject) = widget.deviceobject() else {
+                    return;
+                };
+                let Some(route) = deviceobject.active_route(direction) else {
+                    return;
+                };
+
+                if mute.is_active() != route.mute() {
+                    deviceobject.set_route_mute(direction, mute.is_active());
+                }
+            }));
+        }
+
+        fn bind_routes(&self) {
+            for binding in self.route_bindings.take() {
+                binding.unbind();
+            }
+
+            let deviceobject = self.obj().deviceobject().expect("Device object");
+            let mut bindings = Vec::new();
+            let blocked = self.block_route.replace(true);
+            let upper = if PwvucontrolWindow::default().imp().settings.boolean("enable-overamplification") { 1.525 } else { 1.0 };
+
+            for direction in [RouteDirection::Output, RouteDirection::Input] {
+                let (routebox, label, scale, mute) = self.route_widgets(direction);
+
+                let Some(route) = deviceobject.active_route(direction) else {
+                    routebox.set_visible(false);
+                    continue;
+                };
+
+                bindings.push(route.bind_property("hasvolume", &routebox, "visible").sync_create().build());
+                bindings.push(route.bind_property("description", &label, "label").sync_create().build());
+                scale.set_range(0.0, upper.max(route.volume().cbrt() as f64));
+                bindings.push(
+                    route
+                        .bind_property("volume", &scale.adjustment(), "value")
+                        .transform_to(clone!(@weak scale => @default-return None, move |_, volume: f32| {
+                            // A volume above the range, set elsewhere, widens it rather than being clamped
+                            // and written back lowered.
+                            let value = volume.cbrt() as f64;
+                            if value > scale.adjustment().upper() {
+                                scale.adjustment().set_upper(value);
+                            }
+                            Some(value)
+                        }))
+                        .sync_create()
+                        .build(),
+                );
+                bindings.push(route.bind_property("mute", &mute, "active").sync_create().build());
+            }
+
+            self.route_bindings.replace(bindings);
+            self.block_route.set(blocked);
+        }
+
+        fn update_battery(&self, deviceobject: &PwDeviceObject) {
+            let level = deviceobject.battery();
+
+            self.battery_box.set_visible(level >= 0);
+            if level >= 0 {
+                self.battery_icon.set_icon_name(Some(&format!("battery-level-{}-symbolic", level / 10 * 10)));
+                self.battery_label.set_label(&format!("{level}%"));
+            }
+        }
+    }
 }
 
 glib::wrapper! {
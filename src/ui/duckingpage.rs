@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::config::APP_ID;
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::gio;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/duckingpage.ui")]
+    pub struct PwDuckingPage {
+        #[template_child]
+        pub enabled_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub triggers_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub amount_spin: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub attack_spin: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub release_spin: TemplateChild<gtk::SpinButton>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwDuckingPage {
+        const NAME: &'static str = "PwDuckingPage";
+        type Type = super::PwDuckingPage;
+        type ParentType = adw::PreferencesPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PwDuckingPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let settings = gio::Settings::new(APP_ID);
+            settings.bind("ducking-enabled", &*self.enabled_switch, "active").build();
+            for (key, spin) in [
+                ("ducking-amount", &self.amount_spin),
+                ("ducking-attack", &self.attack_spin),
+                ("ducking-release", &self.release_spin),
+            ] {
+                settings.bind(key, &spin.adjustment(), "value").build();
+            }
+
+            self.triggers_row.set_text(&settings.strv("ducking-triggers").join(", "));
+            self.triggers_row.connect_apply(move |row| {
+                let text = row.text();
+                let triggers: Vec<&str> = text.split(',').map(str::trim).filter(|x| !x.is_empty()).collect();
+                let _ = settings.set_strv("ducking-triggers", triggers);
+            });
+        }
+    }
+    impl WidgetImpl for PwDuckingPage {}
+    impl PreferencesPageImpl for PwDuckingPage {}
+}
+
+glib::wrapper! {
+    pub struct PwDuckingPage(ObjectSubclass<imp::PwDuckingPage>)
+        @extends gtk::Widget, adw::PreferencesPage;
+}
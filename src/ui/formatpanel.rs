@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{PwFormatObject, PwNodeObject};
+use gettextrs::gettext;
+use glib::clone;
+use gtk::{prelude::*, subclass::prelude::*};
+use std::cell::RefCell;
+use wireplumber as wp;
+
+/// PortConfig modes offered in the mode dropdown, in the same order.
+const MODES: [&str; 3] = ["dsp", "convert", "passthrough"];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::PwFormatPanel)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/formatpanel.ui")]
+    pub struct PwFormatPanel {
+        #[property(get, set = Self::set_nodeobject, nullable)]
+        pub(super) nodeobject: RefCell<Option<PwNodeObject>>,
+
+        #[template_child]
+        pub mode_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub layout_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub sampleformat_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub rate_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub apply_button: TemplateChild<gtk::Button>,
+
+        pub(super) sampleformats: RefCell<Vec<u32>>,
+        pub(super) rates: RefCell<Vec<u32>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwFormatPanel {
+        const NAME: &'static str = "PwFormatPanel";
+        type Type = super::PwFormatPanel;
+        type ParentType = gtk::Popover;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl PwFormatPanel {
+        fn set_nodeobject(&self, nodeobject: Option<&PwNodeObject>) {
+            self.nodeobject.replace(nodeobject.cloned());
+
+            if let Some(nodeobject) = nodeobject {
+                self.layout_dropdown.set_model(Some(&nodeobject.formatmodel()));
+            } else {
+                self.layout_dropdown.set_model(gtk::gio::ListModel::NONE);
+            }
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwFormatPanel {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let modes = [gettext("DSP"), gettext("Convert"), gettext("Passthrough")];
+            let modes: Vec<&str> = modes.iter().map(String::as_str).collect();
+            self.mode_dropdown.set_model(Some(&gtk::StringList::new(&modes)));
+
+            let expression = gtk::PropertyExpression::new(PwFormatObject::static_type(), gtk::Expression::NONE, "description");
+            self.layout_dropdown.set_expression(Some(expression));
+
+            self.layout_dropdown.connect_selected_item_notify(clone!(@weak self as widget => move |dropdown| {
+                widget.obj().update_layout(dropdown.selected_item().and_downcast::<PwFormatObject>());
+            }));
+
+            self.apply_button.connect_clicked(clone!(@weak self as widget => move |_| {
+                widget.obj().apply();
+            }));
+        }
+    }
+
+    impl WidgetImpl for PwFormatPanel {}
+    impl PopoverImpl for PwFormatPanel {}
+}
+
+glib::wrapper! {
+    pub struct PwFormatPanel(ObjectSubclass<imp::PwFormatPanel>)
+        @extends gtk::Widget, gtk::Popover,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::ShortcutManager;
+}
+
+impl PwFormatPanel {
+    fn update_layout(&self, layout: Option<PwFormatObject>) {
+        let imp = self.imp();
+
+        let (sampleformats, rates) = layout
+            .as_ref()
+            .map(|layout| (layout.formats(), layout.rates()))
+            .unwrap_or_default();
+
+        let t_format = wp::spa::SpaIdTable::from_name("Spa:Enum:AudioFormat").expect("audio format type");
+        let mut items = vec![gettext("Any")];
+        items.extend(sampleformats.iter().map(|format| {
+            t_format
+                .find_value(*format)
+                .and_then(|x| x.short_name())
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| format.to_string())
+        }));
+        let items: Vec<&str> = items.iter().map(String::as_str).collect();
+        imp.sampleformat_dropdown.set_model(Some(&gtk::StringList::new(&items)));
+
+        let mut items = vec![gettext("Any")];
+        items.extend(rates.iter().map(|rate| format!("{rate} Hz")));
+        let items: Vec<&str> = items.iter().map(String::as_str).collect();
+        imp.rate_dropdown.set_model(Some(&gtk::StringList::new(&items)));
+
+        imp.sampleformats.replace(sampleformats);
+        imp.rates.replace(rates);
+
+        if layout.map_or(false, |layout| layout.mediasubtype() == "iec958") {
+            imp.mode_dropdown.set_selected(2);
+        }
+    }
+
+    fn apply(&self) {
+        let imp = self.imp();
+
+        let Some(nodeobject) = self.nodeobject() else {
+            return;
+        };
+        let Some(layout) = imp.layout_dropdown.selected_item().and_downcast::<PwFormatObject>() else {
+            return;
+        };
+
+        let mode = MODES.get(imp.mode_dropdown.selected() as usize).copied().unwrap_or("dsp");
+        let pick = |list: &RefCell<Vec<u32>>, selected: u32| match selected {
+            0 | gtk::INVALID_LIST_POSITION => None,
+            n => list.borrow().get(n as usize - 1).copied(),
+        };
+        let sampleformat = pick(&imp.sampleformats, imp.sampleformat_dropdown.selected());
+        let rate = pick(&imp.rates, imp.rate_dropdown.selected());
+
+        nodeobject.set_port_config(mode, &layout, sampleformat, rate);
+        self.popdown();
+    }
+}
+
+impl Default for PwFormatPanel {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
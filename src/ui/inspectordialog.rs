@@ -0,0 +1,294 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::PwPropertyObject;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::{clone, SignalHandlerId};
+use gtk::{gdk, gio, prelude::*};
+use std::cell::RefCell;
+use wireplumber as wp;
+use wp::pw::{GlobalProxyExt, PipewireObjectExt, PipewireObjectExt2};
+
+/// Whether the key or value of a property, or of any property below it, contains `text`.
+fn property_matches(property: &PwPropertyObject, text: &str) -> bool {
+    property.key().to_lowercase().contains(text)
+        || property.value().to_lowercase().contains(text)
+        || property
+            .children()
+            .is_some_and(|children| children.iter::<PwPropertyObject>().map_while(Result::ok).any(|child| property_matches(&child, text)))
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/inspectordialog.ui")]
+    #[properties(wrapper_type = super::PwInspectorDialog)]
+    pub struct PwInspectorDialog {
+        #[property(get, set, construct_only)]
+        pub(super) proxy: RefCell<Option<wp::pw::GlobalProxy>>,
+
+        #[template_child]
+        pub search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub copy_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub columnview: TemplateChild<gtk::ColumnView>,
+        #[template_child]
+        pub key_column: TemplateChild<gtk::ColumnViewColumn>,
+        #[template_child]
+        pub value_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        /// Top level sections, in display order.
+        pub(super) sections: RefCell<Vec<PwPropertyObject>>,
+        pub(super) filtered: RefCell<Option<gtk::FilterListModel>>,
+        pub(super) handlers: RefCell<Vec<SignalHandlerId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwInspectorDialog {
+        const NAME: &'static str = "PwInspectorDialog";
+        type Type = super::PwInspectorDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwInspectorDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let proxy = obj.proxy().expect("Proxy");
+
+            let mut sections = vec![
+                PwPropertyObject::new_parent(&gettext("Global Properties"), "", &[]),
+                PwPropertyObject::new_parent(&gettext("Properties"), "", &[]),
+            ];
+            sections.extend(obj.param_ids().iter().map(|id| PwPropertyObject::new_parent(id, "", &[])));
+
+            let root = gio::ListStore::new::<PwPropertyObject>();
+            root.extend_from_slice(&sections);
+            self.sections.replace(sections);
+
+            let tree = gtk::TreeListModel::new(root, false, true, |item| {
+                item.downcast_ref::<PwPropertyObject>()
+                    .and_then(|x| x.children())
+                    .map(|x| x.upcast::<gio::ListModel>())
+            });
+
+            let filter = gtk::CustomFilter::new(clone!(@weak self as imp => @default-return true, move |item| {
+                let text = imp.search_entry.text().to_lowercase();
+                if text.is_empty() {
+                    return true;
+                }
+                let Some(property) = item.downcast_ref::<gtk::TreeListRow>().and_then(|x| x.item()).and_downcast::<PwPropertyObject>() else {
+                    return true;
+                };
+                property_matches(&property, &text)
+            }));
+            let filtered = gtk::FilterListModel::new(Some(tree.clone()), Some(filter.clone()));
+            self.columnview.set_model(Some(&gtk::NoSelection::new(Some(filtered.clone()))));
+            self.filtered.replace(Some(filtered));
+
+            // Keys in collapsed sections are only found with the rows expanded.
+            self.search_entry.connect_search_changed(move |entry| {
+                tree.set_autoexpand(!entry.text().is_empty());
+                filter.changed(gtk::FilterChange::Different);
+            });
+
+            self.key_column.set_factory(Some(&key_factory()));
+            self.value_column.set_factory(Some(&value_factory()));
+
+            self.copy_button.connect_clicked(clone!(@weak obj => move |_| obj.copy_to_clipboard()));
+
+            obj.set_title(Some(&format!("{} #{}", gettext("Properties"), proxy.bound_id())));
+
+            let mut handlers = vec![
+                proxy.connect_notify_local(Some("global-properties"), clone!(@weak obj => move |_, _| obj.update_global_properties())),
+            ];
+            if let Some(pwobject) = proxy.dynamic_cast_ref::<wp::pw::PipewireObject>() {
+                handlers.push(pwobject.connect_properties_notify(clone!(@weak obj => move |_| obj.update_properties())));
+                handlers.push(pwobject.connect_params_changed(clone!(@weak obj => move |_, what| obj.update_param(what))));
+            }
+            self.handlers.replace(handlers);
+
+            obj.update_global_properties();
+            obj.update_properties();
+            for id in obj.param_ids() {
+                obj.update_param(id);
+            }
+        }
+
+        fn dispose(&self) {
+            if let Some(proxy) = self.obj().proxy() {
+                for handler in self.handlers.take() {
+                    proxy.disconnect(handler);
+                }
+            }
+        }
+    }
+    impl WidgetImpl for PwInspectorDialog {}
+    impl WindowImpl for PwInspectorDialog {}
+    impl AdwWindowImpl for PwInspectorDialog {}
+
+    fn key_factory() -> gtk::SignalListItemFactory {
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().expect("ListItem");
+            let label = gtk::Label::builder().xalign(0.0).build();
+            let expander = gtk::TreeExpander::builder().child(&label).build();
+            item.set_child(Some(&expander));
+        });
+        factory.connect_bind(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().expect("ListItem");
+            let Some(row) = item.item().and_downcast::<gtk::TreeListRow>() else {
+                return;
+            };
+            let expander = item.child().and_downcast::<gtk::TreeExpander>().expect("TreeExpander");
+            let label = expander.child().and_downcast::<gtk::Label>().expect("Label");
+            let property = row.item().and_downcast::<PwPropertyObject>().expect("PwPropertyObject");
+
+            label.set_label(&property.key());
+            if row.depth() == 0 {
+                label.add_css_class("heading");
+            } else {
+                label.remove_css_class("heading");
+            }
+            expander.set_list_row(Some(&row));
+        });
+        factory
+    }
+
+    fn value_factory() -> gtk::SignalListItemFactory {
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().expect("ListItem");
+            let label = gtk::Label::builder()
+                .xalign(0.0)
+                .selectable(true)
+                .wrap(true)
+                .wrap_mode(gtk::pango::WrapMode::WordChar)
+                .build();
+            item.set_child(Some(&label));
+        });
+        factory.connect_bind(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().expect("ListItem");
+            let label = item.child().and_downcast::<gtk::Label>().expect("Label");
+            let property = item
+                .item()
+                .and_downcast::<gtk::TreeListRow>()
+                .and_then(|x| x.item())
+                .and_downcast::<PwPropertyObject>()
+                .expect("PwPropertyObject");
+            label.set_label(&property.value());
+        });
+        factory
+    }
+}
+
+glib::wrapper! {
+    pub struct PwInspectorDialog(ObjectSubclass<imp::PwInspectorDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwInspectorDialog {
+    pub(crate) fn new(parent: &impl IsA<gtk::Window>, proxy: &impl IsA<wp::pw::GlobalProxy>) -> Self {
+        glib::Object::builder()
+            .property("transient-for", parent)
+            .property("proxy", proxy.upcast_ref::<wp::pw::GlobalProxy>())
+            .build()
+    }
+
+    /// Params shown besides the properties, depending on the kind of object.
+    fn param_ids(&self) -> &'static [&'static str] {
+        match self.proxy() {
+            Some(proxy) if proxy.is::<wp::pw::Device>() => &["EnumProfile", "Profile", "EnumRoute", "Route"],
+            Some(proxy) if proxy.is::<wp::pw::Node>() => &["Props", "Format", "EnumFormat"],
+            _ => &[],
+        }
+    }
+
+    fn section(&self, index: usize) -> Option<PwPropertyObject> {
+        self.imp().sections.borrow().get(index).cloned()
+    }
+
+    fn update_global_properties(&self) {
+        let items = self
+            .proxy()
+            .and_then(|x| x.global_properties())
+            .map(|x| PwPropertyObject::from_properties(&x))
+            .unwrap_or_default();
+        if let Some(section) = self.section(0) {
+            section.set_child_items(&items);
+        }
+    }
+
+    fn update_properties(&self) {
+        let items = self
+            .proxy()
+            .and_then(|x| x.dynamic_cast::<wp::pw::PipewireObject>().ok())
+            .and_then(|x| x.properties())
+            .map(|x| PwPropertyObject::from_properties(&x))
+            .unwrap_or_default();
+        if let Some(section) = self.section(1) {
+            section.set_child_items(&items);
+        }
+    }
+
+    fn update_param(&self, id: &str) {
+        let Some(position) = self.param_ids().iter().position(|x| *x == id) else {
+            return;
+        };
+        let Some(section) = self.section(position + 2) else {
+            return;
+        };
+        let Some(pwobject) = self.proxy().and_then(|x| x.dynamic_cast::<wp::pw::PipewireObject>().ok()) else {
+            return;
+        };
+
+        let items: Vec<PwPropertyObject> = pwobject
+            .enum_params_sync(id, None)
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.get::<wp::spa::SpaPod>().ok())
+            .enumerate()
+            .map(|(i, pod)| PwPropertyObject::from_pod(&format!("[{i}]"), &pod))
+            .collect();
+        section.set_child_items(&items);
+    }
+
+    /// Copies the visible entries as indented `key = value` lines.
+    fn copy_to_clipboard(&self) {
+        let Some(model) = self.imp().filtered.borrow().clone() else {
+            return;
+        };
+
+        let text: String = model
+            .iter::<gtk::TreeListRow>()
+            .map_while(Result::ok)
+            .filter_map(|row| {
+                let property = row.item().and_downcast::<PwPropertyObject>()?;
+                let indent = "  ".repeat(row.depth() as usize);
+                Some(if property.value().is_empty() {
+                    format!("{indent}{}\n", property.key())
+                } else {
+                    format!("{indent}{} = {}\n", property.key(), property.value())
+                })
+            })
+            .collect();
+
+        gdk::Display::default()
+            .expect("display")
+            .clipboard()
+            .set_text(&text);
+    }
+}
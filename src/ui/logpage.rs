@@ -0,0 +1,148 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{EventCategory, PwEventObject, PwvucontrolManager},
+    macros::*,
+};
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::{gio, prelude::*};
+use std::cell::RefCell;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/logpage.ui")]
+    pub struct PwLogPage {
+        #[template_child]
+        pub search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub category_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub export_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub clear_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub columnview: TemplateChild<gtk::ColumnView>,
+        #[template_child]
+        pub time_column: TemplateChild<gtk::ColumnViewColumn>,
+        #[template_child]
+        pub category_column: TemplateChild<gtk::ColumnViewColumn>,
+        #[template_child]
+        pub message_column: TemplateChild<gtk::ColumnViewColumn>,
+
+        pub(super) filtered: RefCell<Option<gtk::FilterListModel>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwLogPage {
+        const NAME: &'static str = "PwLogPage";
+        type Type = super::PwLogPage;
+        type ParentType = gtk::Box;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PwLogPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let manager = PwvucontrolManager::default();
+
+            let mut categories = vec![gettext("All")];
+            categories.extend(EventCategory::ALL.iter().map(|category| category.title()));
+            let categories: Vec<&str> = categories.iter().map(String::as_str).collect();
+            self.category_dropdown.set_model(Some(&gtk::StringList::new(&categories)));
+
+            let filter = gtk::CustomFilter::new(clone!(@weak self as imp => @default-return true, move |item| {
+                let Some(event) = item.downcast_ref::<PwEventObject>() else {
+                    return true;
+                };
+                // Position 0 is "All", the categories follow in order.
+                let selected = imp.category_dropdown.selected();
+                if selected > 0 && EventCategory::ALL.get(selected as usize - 1) != Some(&event.category()) {
+                    return false;
+                }
+                let text = imp.search_entry.text().to_lowercase();
+                text.is_empty() || event.message().to_lowercase().contains(&text)
+            }));
+            let filtered = gtk::FilterListModel::new(Some(manager.event_model()), Some(filter.clone()));
+            self.columnview.set_model(Some(&gtk::NoSelection::new(Some(filtered.clone()))));
+            self.filtered.replace(Some(filtered));
+
+            self.search_entry.connect_search_changed(clone!(@weak filter => move |_| filter.changed(gtk::FilterChange::Different)));
+            self.category_dropdown.connect_selected_notify(move |_| filter.changed(gtk::FilterChange::Different));
+
+            self.time_column.set_factory(Some(&label_factory(|event| event.time())));
+            self.category_column.set_factory(Some(&label_factory(|event| event.category().title())));
+            self.message_column.set_factory(Some(&label_factory(|event| event.message())));
+
+            self.export_button.connect_clicked(clone!(@weak obj => move |_| obj.export()));
+            self.clear_button.connect_clicked(clone!(@weak manager => move |_| manager.event_model().remove_all()));
+        }
+    }
+    impl WidgetImpl for PwLogPage {}
+    impl BoxImpl for PwLogPage {}
+
+    fn label_factory(text: fn(&PwEventObject) -> String) -> gtk::SignalListItemFactory {
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().expect("ListItem");
+            let label = gtk::Label::builder()
+                .xalign(0.0)
+                .wrap(true)
+                .wrap_mode(gtk::pango::WrapMode::WordChar)
+                .build();
+            item.set_child(Some(&label));
+        });
+        factory.connect_bind(move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().expect("ListItem");
+            let label = item.child().and_downcast::<gtk::Label>().expect("Label");
+            let event = item.item().and_downcast::<PwEventObject>().expect("PwEventObject");
+            label.set_label(&text(&event));
+        });
+        factory
+    }
+}
+
+glib::wrapper! {
+    pub struct PwLogPage(ObjectSubclass<imp::PwLogPage>)
+        @extends gtk::Widget, gtk::Box,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Orientable;
+}
+
+impl PwLogPage {
+    /// Saves the events passing the current filter as tab separated text, oldest first.
+    fn export(&self) {
+        let Some(filtered) = self.imp().filtered.borrow().clone() else {
+            return;
+        };
+        let mut lines: Vec<String> = filtered.iter::<PwEventObject>().map_while(Result::ok).map(|event| event.to_line()).collect();
+        lines.reverse();
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Log"))
+            .initial_name("pwvucontrol-log.txt")
+            .build();
+        let window = self.root().and_downcast::<gtk::Window>();
+        dialog.save(window.as_ref(), gio::Cancellable::NONE, move |result| {
+            let Ok(file) = result else {
+                return;
+            };
+            if let Err(e) = file.replace_contents(contents.as_bytes(), None, false, gio::FileCreateFlags::NONE, gio::Cancellable::NONE) {
+                pwvucontrol_warning!("Cannot export log: {e}");
+            }
+        });
+    }
+}
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{
+    micfilters::{MicFilter, MicFilterKind},
+    PwNodeObject, PwvucontrolManager,
+};
+use adw::{prelude::*, subclass::prelude::*};
+use glib::clone;
+use std::cell::RefCell;
+
+/// Filters in the order of the combo row model.
+const KINDS: [Option<MicFilterKind>; 3] = [None, Some(MicFilterKind::EchoCancel), Some(MicFilterKind::NoiseSuppression)];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/micfilterdialog.ui")]
+    #[properties(wrapper_type = super::PwMicFilterDialog)]
+    pub struct PwMicFilterDialog {
+        #[property(get, set, construct_only)]
+        pub(super) node_object: RefCell<Option<PwNodeObject>>,
+
+        #[template_child]
+        pub cancel_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub apply_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub kind_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub default_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub echo_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub gain_control_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub noise_suppression_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub high_pass_filter_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub noise_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub vad_threshold_spin: TemplateChild<gtk::SpinButton>,
+
+        /// Filters offered, by position in the combo row.
+        pub(super) kinds: RefCell<Vec<Option<MicFilterKind>>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwMicFilterDialog {
+        const NAME: &'static str = "PwMicFilterDialog";
+        type Type = super::PwMicFilterDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwMicFilterDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let item = obj.node_object().expect("Node object");
+            let source: String = item.node_property("node.name");
+
+            let filter = PwvucontrolManager::default().mic_filters().remove(&source);
+            let defaults = MicFilter::new(MicFilterKind::EchoCancel, &source, &item.name());
+            let current = filter.as_ref().unwrap_or(&defaults);
+
+            // Filters that are not installed are left out, unless they are already in use.
+            let current_kind = filter.as_ref().map(|x| x.kind);
+            let model = self.kind_row.model().and_downcast::<gtk::StringList>().expect("string list");
+            let mut kinds = Vec::new();
+            for (index, kind) in KINDS.iter().enumerate().rev() {
+                if kind.is_some_and(|kind| !kind.is_available()) && *kind != current_kind {
+                    model.remove(index as u32);
+                } else {
+                    kinds.insert(0, *kind);
+                }
+            }
+
+            let position = kinds.iter().position(|x| *x == current_kind).unwrap_or_default();
+            self.kinds.replace(kinds);
+            self.kind_row.set_selected(position as u32);
+            self.gain_control_switch.set_active(current.gain_control);
+            self.noise_suppression_switch.set_active(current.noise_suppression);
+            self.high_pass_filter_switch.set_active(current.high_pass_filter);
+            self.vad_threshold_spin.set_value(current.vad_threshold);
+
+            self.kind_row.connect_selected_notify(clone!(@weak obj => move |_| obj.update_groups()));
+            obj.update_groups();
+
+            self.cancel_button.connect_clicked(clone!(@weak obj => move |_| obj.close()));
+            self.apply_button.connect_clicked(clone!(@weak obj => move |_| {
+                obj.apply();
+                obj.close();
+            }));
+        }
+    }
+    impl WidgetImpl for PwMicFilterDialog {}
+    impl WindowImpl for PwMicFilterDialog {}
+    impl AdwWindowImpl for PwMicFilterDialog {}
+}
+
+glib::wrapper! {
+    pub struct PwMicFilterDialog(ObjectSubclass<imp::PwMicFilterDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwMicFilterDialog {
+    pub(crate) fn new(parent: &impl IsA<gtk::Window>, node_object: &PwNodeObject) -> Self {
+        glib::Object::builder()
+            .property("transient-for", parent)
+            .property("node-object", node_object)
+            .build()
+    }
+
+    fn selected_kind(&self) -> Option<MicFilterKind> {
+        self.imp().kinds.borrow().get(self.imp().kind_row.selected() as usize).copied().flatten()
+    }
+
+    fn update_groups(&self) {
+        let imp = self.imp();
+        let kind = self.selected_kind();
+
+        imp.echo_group.set_visible(kind == Some(MicFilterKind::EchoCancel));
+        imp.noise_group.set_visible(kind == Some(MicFilterKind::NoiseSuppression));
+        imp.default_switch.set_sensitive(kind.is_some());
+    }
+
+    fn apply(&self) {
+        let imp = self.imp();
+        let Some(item) = self.node_object() else {
+            return;
+        };
+        let source: String = item.node_property("node.name");
+        let manager = PwvucontrolManager::default();
+
+        let Some(kind) = self.selected_kind() else {
+            manager.remove_mic_filter(&source);
+            return;
+        };
+
+        let filter = MicFilter {
+            gain_control: imp.gain_control_switch.is_active(),
+            noise_suppression: imp.noise_suppression_switch.is_active(),
+            high_pass_filter: imp.high_pass_filter_switch.is_active(),
+            vad_threshold: imp.vad_threshold_spin.value(),
+            ..MicFilter::new(kind, &source, &item.name())
+        };
+        manager.set_mic_filter(filter, imp.default_switch.is_active());
+    }
+}
@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{NodeType, PwNodeObject, PwvucontrolManager},
+    ui::{nodebindings, LevelbarProvider, PwStreamDropDown},
+};
+use glib::{clone, closure_local};
+use gtk::{prelude::*, subclass::prelude::*};
+use std::cell::{Cell, OnceCell, RefCell};
+use wireplumber as wp;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/mixerstrip.ui")]
+    #[properties(wrapper_type = super::PwMixerStrip)]
+    pub struct PwMixerStrip {
+        #[property(get, set, construct_only)]
+        pub(super) node_object: RefCell<Option<PwNodeObject>>,
+
+        levelbarprovider: OnceCell<LevelbarProvider>,
+        pub(super) default_node: Cell<u32>,
+        pub(super) block_default_toggle: Cell<bool>,
+        /// Handlers on the manager and the settings, which outlive the strip.
+        metadata_changed_handler: RefCell<Option<glib::SignalHandlerId>>,
+        overamplification_handler: RefCell<Option<glib::SignalHandlerId>>,
+
+        #[template_child]
+        pub icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub name_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub volume_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub level_bar: TemplateChild<gtk::LevelBar>,
+        #[template_child]
+        pub mutebtn: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub target_dropdown: TemplateChild<PwStreamDropDown>,
+        #[template_child]
+        pub default_toggle: TemplateChild<gtk::ToggleButton>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwMixerStrip {
+        const NAME: &'static str = "PwMixerStrip";
+        type Type = super::PwMixerStrip;
+        type ParentType = gtk::Box;
+
+        fn class_init(klass: &mut Self::Class) {
+            PwStreamDropDown::ensure_type();
+
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwMixerStrip {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let item = obj.node_object().expect("nodeobj");
+
+            item.bind_property("iconname", &self.icon.get(), "icon-name").sync_create().build();
+            item.bind_property("name", &self.name_label.get(), "label").sync_create().build();
+            item.bind_property("name", &self.name_label.get(), "tooltip-text").sync_create().build();
+
+            nodebindings::bind_mute(&item, &self.mutebtn);
+            nodebindings::bind_volume(&item, &self.volume_scale);
+
+            self.volume_scale.set_format_value_func(|_scale, value| format!("{:.0}%", value * 100.0));
+
+            let handler = nodebindings::watch_overamplification(&self.volume_scale, |volume_scale, enabled| {
+                volume_scale.clear_marks();
+                volume_scale.add_mark(1.0, gtk::PositionType::Left, None);
+                volume_scale.set_range(0.0, if enabled { nodebindings::OVERAMPLIFIED_VOLUME } else { 1.0 });
+            });
+            self.overamplification_handler.replace(Some(handler));
+
+            let manager = PwvucontrolManager::default();
+            let media_class = match item.nodetype() {
+                NodeType::Source | NodeType::StreamInput => "Audio/Source",
+                _ => "Audio/Sink",
+            };
+
+            match item.nodetype() {
+                NodeType::StreamOutput | NodeType::StreamInput => {
+                    self.target_dropdown.set_visible(true);
+                    self.target_dropdown.set_nodeobj(Some(&item));
+
+                    let handler = nodebindings::connect_target_changed(&item, clone!(@weak obj => move || obj.update_target()));
+                    self.metadata_changed_handler.replace(Some(handler));
+                },
+                _ => {
+                    self.default_toggle.set_visible(true);
+                    self.default_toggle.connect_toggled(clone!(@weak self as widget, @weak item => move |toggle| {
+                        if !widget.block_default_toggle.get() {
+                            nodebindings::set_default_node(&item, toggle.is_active());
+                        }
+                    }));
+                },
+            }
+
+            let defaultnodesapi = manager.default_nodes_api();
+            let defaultnodesapi_closure = closure_local!(@watch obj => move |defaultnodesapi: wp::plugin::Plugin| {
+                let id: u32 = defaultnodesapi.emit_by_name("get-default-node", &[&media_class]);
+                obj.imp().default_node.set(id);
+                obj.update_target();
+            });
+            defaultnodesapi_closure.invoke::<()>(&[&defaultnodesapi]);
+            defaultnodesapi.connect_closure("changed", false, defaultnodesapi_closure);
+
+            self.level_bar.set_min_value(0.0);
+            self.level_bar.set_max_value(1.0);
+            self.level_bar.add_offset_value(gtk::LEVEL_BAR_OFFSET_LOW, 0.0);
+            self.level_bar.add_offset_value(gtk::LEVEL_BAR_OFFSET_HIGH, 0.0);
+            self.level_bar.add_offset_value(gtk::LEVEL_BAR_OFFSET_FULL, 1.0);
+
+            // Monitoring ourselves cause an infinite loop.
+            let level_bar = self.level_bar.get();
+            let on_level = clone!(@weak level_bar => move |level: f32| level_bar.set_value(level as f64));
+            let provider = (item.name() != "pwvucontrol-peak-detect")
+                .then(|| LevelbarProvider::new(on_level, item.boundid()).ok())
+                .flatten();
+            match provider {
+                Some(provider) => self.levelbarprovider.set(provider).expect("Provider not set already"),
+                None => self.level_bar.set_visible(false),
+            }
+        }
+
+        fn dispose(&self) {
+            if let Some(handler) = self.metadata_changed_handler.take() {
+                PwvucontrolManager::default().disconnect(handler);
+            }
+            if let Some(handler) = self.overamplification_handler.take() {
+                nodebindings::unwatch_overamplification(handler);
+            }
+        }
+    }
+    impl WidgetImpl for PwMixerStrip {}
+    impl BoxImpl for PwMixerStrip {}
+}
+
+glib::wrapper! {
+    /// A node shown as a vertical mixer strip with fader, meter, mute and target.
+    pub struct PwMixerStrip(ObjectSubclass<imp::PwMixerStrip>)
+        @extends gtk::Widget, gtk::Box,
+        @implements gtk::Orientable;
+}
+
+impl PwMixerStrip {
+    pub(crate) fn new(node_object: &impl glib::IsA<PwNodeObject>) -> Self {
+        glib::Object::builder().property("node-object", node_object).build()
+    }
+
+    /// Syncs the target dropdown of streams, or the default toggle of devices.
+    fn update_target(&self) {
+        let imp = self.imp();
+        let item = self.node_object().expect("nodeobj");
+
+        match item.nodetype() {
+            NodeType::StreamOutput | NodeType::StreamInput => {
+                imp.target_dropdown.update_selected(imp.default_node.get());
+            },
+            _ => {
+                imp.block_default_toggle.set(true);
+                imp.default_toggle.set_active(item.boundid() == imp.default_node.get());
+                imp.block_default_toggle.set(false);
+            },
+        }
+    }
+}
@@ -1,5 +1,6 @@
 mod channelbox;
 mod levelprovider;
+mod pwstream;
 mod volumebox;
 mod window;
 mod withdefaultlistmodel;
@@ -10,6 +11,26 @@ mod profile_dropdown;
 mod devicebox;
 mod profilerow;
 mod route_dropdown;
+mod preferenceswindow;
+mod clocksettingspage;
+mod formatpanel;
+mod clapplayer;
+mod claptestdialog;
+mod rulespage;
+mod rulerow;
+mod inspectordialog;
+mod propsdialog;
+mod micfilterdialog;
+mod networkpage;
+mod logpage;
+mod notificationspage;
+mod appgrouprow;
+mod mixerstrip;
+mod nodebindings;
+mod sleeptimerdialog;
+mod duckingpage;
+mod speakertestplayer;
+mod speakertestdialog;
 
 pub use window::PwvucontrolWindow;
 pub use window::PwvucontrolWindowView;
@@ -20,6 +41,26 @@ pub use stream_dropdown::PwStreamDropDown;
 pub use sinkbox::PwSinkBox;
 pub use channelbox::PwChannelBox;
 pub use levelprovider::LevelbarProvider;
+pub use pwstream::PwStream;
 pub use streambox::PwStreamBox;
 pub use profilerow::PwProfileRow;
 pub use route_dropdown::PwRouteDropDown;
+pub use preferenceswindow::PwvucontrolPreferencesWindow;
+pub use clocksettingspage::PwClockSettingsPage;
+pub use formatpanel::PwFormatPanel;
+pub use clapplayer::ClapPlayer;
+pub use claptestdialog::PwClapTestDialog;
+pub use rulespage::PwRulesPage;
+pub use rulerow::PwRuleRow;
+pub use inspectordialog::PwInspectorDialog;
+pub use propsdialog::PwPropsDialog;
+pub use micfilterdialog::PwMicFilterDialog;
+pub use networkpage::PwNetworkPage;
+pub use logpage::PwLogPage;
+pub use notificationspage::PwNotificationsPage;
+pub use appgrouprow::PwAppGroupRow;
+pub use mixerstrip::PwMixerStrip;
+pub use sleeptimerdialog::PwSleepTimerDialog;
+pub use duckingpage::PwDuckingPage;
+pub use speakertestplayer::{SpeakerTestPlayer, TestSignal};
+pub use speakertestdialog::PwSpeakerTestDialog;
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{
+        networksessions::{self, NetworkSession, NetworkSessionKind},
+        PwvucontrolManager,
+    },
+    config::APP_ID,
+};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use glib::clone;
+use gtk::gio;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, gtk::CompositeTemplate)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/networkpage.ui")]
+    pub struct PwNetworkPage {
+        #[template_child]
+        pub sessions_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub add_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub kind_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub label_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub address_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub port_spin: TemplateChild<gtk::SpinButton>,
+
+        pub settings: gio::Settings,
+    }
+
+    impl Default for PwNetworkPage {
+        fn default() -> Self {
+            Self {
+                sessions_list: TemplateChild::default(),
+                add_button: TemplateChild::default(),
+                kind_row: TemplateChild::default(),
+                label_row: TemplateChild::default(),
+                address_row: TemplateChild::default(),
+                port_spin: TemplateChild::default(),
+                settings: gio::Settings::new(APP_ID),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwNetworkPage {
+        const NAME: &'static str = "PwNetworkPage";
+        type Type = super::PwNetworkPage;
+        type ParentType = adw::PreferencesPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PwNetworkPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let titles: Vec<String> = NetworkSessionKind::ALL.iter().map(|kind| kind.title()).collect();
+            let titles: Vec<&str> = titles.iter().map(String::as_str).collect();
+            self.kind_row.set_model(Some(&gtk::StringList::new(&titles)));
+            self.kind_row.connect_selected_notify(clone!(@weak obj => move |_| obj.kind_changed()));
+            obj.kind_changed();
+
+            self.add_button.connect_clicked(clone!(@weak obj => move |_| obj.add_session()));
+
+            self.settings.connect_changed(Some("network-sessions"), clone!(@weak obj => move |_, _| obj.update_sessions()));
+            obj.update_sessions();
+        }
+    }
+    impl WidgetImpl for PwNetworkPage {}
+    impl PreferencesPageImpl for PwNetworkPage {}
+}
+
+glib::wrapper! {
+    pub struct PwNetworkPage(ObjectSubclass<imp::PwNetworkPage>)
+        @extends gtk::Widget, adw::PreferencesPage;
+}
+
+impl PwNetworkPage {
+    fn selected_kind(&self) -> NetworkSessionKind {
+        NetworkSessionKind::ALL
+            .get(self.imp().kind_row.selected() as usize)
+            .copied()
+            .unwrap_or(NetworkSessionKind::RtpSend)
+    }
+
+    fn kind_changed(&self) {
+        let imp = self.imp();
+        let kind = self.selected_kind();
+
+        imp.port_spin.set_value(kind.default_port() as f64);
+        imp.address_row.set_text(if kind.is_sender() { "127.0.0.1" } else { "0.0.0.0" });
+        imp.address_row.set_title(&if kind.is_sender() { gettext("Remote address") } else { gettext("Listen address") });
+    }
+
+    fn add_session(&self) {
+        let imp = self.imp();
+        let kind = self.selected_kind();
+
+        let address = imp.address_row.text();
+        if address.trim().is_empty() {
+            imp.address_row.add_css_class("error");
+            return;
+        }
+        imp.address_row.remove_css_class("error");
+
+        let label = match imp.label_row.text() {
+            label if label.trim().is_empty() => format!("{} {}", kind.title(), address),
+            label => label.to_string(),
+        };
+
+        let session = NetworkSession::new(kind, &label, address.trim(), imp.port_spin.value_as_int() as u32);
+        PwvucontrolManager::default().add_network_session(session);
+
+        imp.label_row.set_text("");
+    }
+
+    fn update_sessions(&self) {
+        let imp = self.imp();
+
+        while let Some(row) = imp.sessions_list.first_child() {
+            imp.sessions_list.remove(&row);
+        }
+
+        let sessions = networksessions::network_sessions();
+        for session in sessions.values() {
+            let row = adw::ActionRow::builder()
+                .title(&session.label)
+                .subtitle(format!("{} – {}:{}", session.kind.title(), session.address, session.port))
+                .build();
+
+            let button = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Remove session"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            let id = session.id.clone();
+            button.connect_clicked(move |_| PwvucontrolManager::default().remove_network_session(&id));
+            row.add_suffix(&button);
+
+            imp.sessions_list.append(&row);
+        }
+
+        imp.sessions_list.set_visible(!sessions.is_empty());
+    }
+}
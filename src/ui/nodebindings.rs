@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{HistoryChange, NodeControl, PwNodeObject, PwvucontrolManager},
+    ui::PwvucontrolWindow,
+};
+use glib::clone;
+use gtk::{prelude::*, subclass::prelude::*};
+
+// Bindings between a node and the controls showing it, shared by the volume boxes and mixer strips.
+
+/// Upper end of volume scales with overamplification enabled, 150% on the cubic scale.
+pub(crate) const OVERAMPLIFIED_VOLUME: f64 = 1.525;
+
+/// Transform for bindings from a linear volume to the cubic one shown on scales.
+pub(crate) fn linear_to_cubic(_binding: &glib::Binding, i: f32) -> Option<f64> {
+    Some(i.cbrt() as f64)
+}
+
+/// Transform for bindings from the cubic volume shown on scales back to a linear one.
+pub(crate) fn cubic_to_linear(_binding: &glib::Binding, i: f64) -> Option<f32> {
+    Some((i * i * i) as f32)
+}
+
+/// Binds a scale to the volume of the node, recording changes made with it in the history.
+pub(crate) fn bind_volume(item: &PwNodeObject, scale: &gtk::Scale) {
+    item.bind_property("volume", &scale.adjustment(), "value")
+        .sync_create()
+        .bidirectional()
+        .transform_to(linear_to_cubic)
+        .transform_from(cubic_to_linear)
+        .build();
+    scale.connect_change_value(clone!(@weak item => @default-return glib::Propagation::Proceed, move |_, _, _| {
+        PwvucontrolManager::default().history().record_nodes_later(NodeControl::Volume, &[item]);
+        glib::Propagation::Proceed
+    }));
+}
+
+/// Binds a toggle button to the mute state of the node, recording clicks in the history.
+pub(crate) fn bind_mute(item: &PwNodeObject, button: &gtk::ToggleButton) {
+    item.bind_property("mute", button, "active")
+        .sync_create()
+        .build();
+    button.connect_clicked(clone!(@weak item => move |button| {
+        let history = PwvucontrolManager::default().history();
+        history.record_nodes(NodeControl::Mute, &[item.clone()], || item.set_mute(button.is_active()));
+    }));
+}
+
+/// Calls `update_range` with whether overamplification is enabled, now and whenever the setting
+/// changes. The handler is connected to the window settings, disconnect it when the scale goes away.
+pub(crate) fn watch_overamplification(scale: &gtk::Scale, update_range: fn(&gtk::Scale, bool)) -> glib::SignalHandlerId {
+    let settings = PwvucontrolWindow::default().imp().settings.clone();
+    update_range(scale, settings.boolean("enable-overamplification"));
+
+    settings.connect_changed(Some("enable-overamplification"), clone!(@weak scale => move |settings, key| {
+        update_range(&scale, settings.boolean(key));
+    }))
+}
+
+/// Range and marks of the volume scales in the lists, for `watch_overamplification`.
+pub(crate) fn update_volume_range(volume_scale: &gtk::Scale, overamplification: bool) {
+    volume_scale.clear_marks();
+    volume_scale.add_mark(0.0, gtk::PositionType::Bottom, Some("Silence"));
+    volume_scale.add_mark(1.0, gtk::PositionType::Bottom, Some("100%"));
+
+    if overamplification {
+        volume_scale.add_mark(OVERAMPLIFIED_VOLUME, gtk::PositionType::Bottom, Some("150%"));
+        volume_scale.set_range(0.0, OVERAMPLIFIED_VOLUME);
+    } else {
+        volume_scale.set_range(0.0, 1.0);
+    }
+}
+
+/// Disconnects a handler returned by `watch_overamplification`.
+pub(crate) fn unwatch_overamplification(handler: glib::SignalHandlerId) {
+    PwvucontrolWindow::default().imp().settings.disconnect(handler);
+}
+
+/// Makes the node the configured default, or clears the configured default, recording it in the history.
+pub(crate) fn set_default_node(item: &PwNodeObject, default: bool) {
+    let node_name: String = if default {
+        item.node_property("node.name")
+    } else {
+        "".to_string()
+    };
+
+    let manager = PwvucontrolManager::default();
+    manager.history().record(HistoryChange::default_node(item.nodetype(), &node_name));
+    manager.set_default_configured_node_name(item.nodetype(), &node_name);
+}
+
+/// Calls `f` when the target of the stream changes. The handler is connected to the manager,
+/// disconnect it when the widget goes away.
+pub(crate) fn connect_target_changed(item: &PwNodeObject, f: impl Fn() + 'static) -> glib::SignalHandlerId {
+    let boundid = item.boundid();
+    PwvucontrolManager::default().connect_local("metadata-changed", false, move |values| {
+        let subject: u32 = values[1].get().expect("subject");
+        let key: String = values[2].get().expect("key");
+        if subject == boundid && key.starts_with("target.") {
+            f();
+        }
+        None
+    })
+}
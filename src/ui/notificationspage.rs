@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::config::APP_ID;
+use adw::subclass::prelude::*;
+use gtk::{gio, prelude::*};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/notificationspage.ui")]
+    pub struct PwNotificationsPage {
+        #[template_child]
+        pub new_devices_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub default_changes_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub headphones_switch: TemplateChild<gtk::Switch>,
+        #[template_child]
+        pub stream_moves_switch: TemplateChild<gtk::Switch>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwNotificationsPage {
+        const NAME: &'static str = "PwNotificationsPage";
+        type Type = super::PwNotificationsPage;
+        type ParentType = adw::PreferencesPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PwNotificationsPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let settings = gio::Settings::new(APP_ID);
+            for (key, switch) in [
+                ("notify-new-devices", &self.new_devices_switch),
+                ("notify-default-changes", &self.default_changes_switch),
+                ("notify-headphones", &self.headphones_switch),
+                ("notify-stream-moves", &self.stream_moves_switch),
+            ] {
+                settings.bind(key, &**switch, "active").build();
+            }
+        }
+    }
+    impl WidgetImpl for PwNotificationsPage {}
+    impl PreferencesPageImpl for PwNotificationsPage {}
+}
+
+glib::wrapper! {
+    pub struct PwNotificationsPage(ObjectSubclass<imp::PwNotificationsPage>)
+        @extends gtk::Widget, adw::PreferencesPage;
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::ui::{PwClockSettingsPage, PwDuckingPage, PwNotificationsPage, PwRulesPage};
+use adw::subclass::prelude::*;
+use gtk::prelude::*;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/preferences-window.ui")]
+    pub struct PwvucontrolPreferencesWindow {}
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwvucontrolPreferencesWindow {
+        const NAME: &'static str = "PwvucontrolPreferencesWindow";
+        type Type = super::PwvucontrolPreferencesWindow;
+        type ParentType = adw::PreferencesWindow;
+
+        fn class_init(klass: &mut Self::Class) {
+            PwClockSettingsPage::ensure_type();
+            PwRulesPage::ensure_type();
+            PwNotificationsPage::ensure_type();
+            PwDuckingPage::ensure_type();
+
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PwvucontrolPreferencesWindow {}
+    impl WidgetImpl for PwvucontrolPreferencesWindow {}
+    impl WindowImpl for PwvucontrolPreferencesWindow {}
+    impl AdwWindowImpl for PwvucontrolPreferencesWindow {}
+    impl PreferencesWindowImpl for PwvucontrolPreferencesWindow {}
+}
+
+glib::wrapper! {
+    pub struct PwvucontrolPreferencesWindow(ObjectSubclass<imp::PwvucontrolPreferencesWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window, adw::PreferencesWindow,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwvucontrolPreferencesWindow {
+    pub fn new(parent: &impl IsA<gtk::Window>) -> Self {
+        glib::Object::builder()
+            .property("transient-for", parent)
+            .build()
+    }
+}
@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::{
-    backend::{PwDeviceObject, PwProfileObject},
+    backend::{HistoryChange, PwDeviceObject, PwProfileObject, PwvucontrolManager},
     macros::*,
     ui::PwProfileRow,
 };
@@ -72,12 +72,47 @@ mod imp {
             None
         }
 
+        /// Groups the profiles of bluetooth devices under A2DP and HSP/HFP headers.
+        fn set_sectioned_model(&self, deviceobject: &PwDeviceObject) {
+            let sorter = gtk::CustomSorter::new(|a, b| {
+                let a = a.downcast_ref::<PwProfileObject>().expect("PwProfileObject");
+                let b = b.downcast_ref::<PwProfileObject>().expect("PwProfileObject");
+
+                a.group().cmp(&b.group()).into()
+            });
+
+            let model = gtk::SortListModel::new(Some(deviceobject.profilemodel()), Some(sorter.clone()));
+            model.set_section_sorter(Some(&sorter));
+
+            let header_factory = gtk::SignalListItemFactory::new();
+            header_factory.connect_setup(|_, header| {
+                let header: &gtk::ListHeader = header.downcast_ref().expect("ListHeader");
+                let label = gtk::Label::builder().xalign(0.0).css_classes(["heading"]).build();
+                header.set_child(Some(&label));
+            });
+            header_factory.connect_bind(|_, header| {
+                let header: &gtk::ListHeader = header.downcast_ref().expect("ListHeader");
+                let label = header.child().and_downcast::<gtk::Label>().expect("Label child");
+                if let Some(profile) = header.item().and_downcast::<PwProfileObject>() {
+                    label.set_label(&profile.group().title());
+                }
+            });
+
+            self.profile_dropdown.set_model(Some(&model));
+            self.profile_dropdown.set_header_factory(Some(&header_factory));
+        }
+
         pub fn set_deviceobject(&self, new_deviceobject: Option<&PwDeviceObject>) {
             self.deviceobject.replace(new_deviceobject.cloned());
 
             if let Some(deviceobject) = new_deviceobject {
                 self.block_signal.set(true);
-                self.profile_dropdown.set_model(Some(&deviceobject.profilemodel()));
+                if deviceobject.is_bluetooth() {
+                    self.set_sectioned_model(deviceobject);
+                } else {
+                    self.profile_dropdown.set_model(Some(&deviceobject.profilemodel()));
+                    self.profile_dropdown.set_header_factory(gtk::ListItemFactory::NONE);
+                }
                 self.update_selected();
                 self.block_signal.set(false);
 
@@ -181,6 +216,7 @@ mod imp {
 
                     if let Some(item) = dropdown.selected_item().and_downcast::<PwProfileObject>() {
                         pwvucontrol_critical!("Setting profile to dropdown index = {} profile index = {}", dropdown.selected(), item.index());
+                        PwvucontrolManager::default().history().record(HistoryChange::profile(&deviceobject, item.index() as i32));
                         deviceobject.set_profile(item.index() as i32);
                     }
 
@@ -16,6 +16,8 @@ mod imp {
         #[template_child]
         pub unavailable_icon: TemplateChild<gtk::Image>,
         #[template_child]
+        pub saved_icon: TemplateChild<gtk::Image>,
+        #[template_child]
         pub checkmark_icon: TemplateChild<gtk::Image>,
 
         pub(super) signalid: RefCell<Option<glib::SignalHandlerId>>,
@@ -72,6 +74,10 @@ impl PwProfileRow {
             .chain_property::<Type>("availability")
             .chain_closure::<bool>(icon_closure)
             .bind(&unavailable_icon, "visible", glib::Object::NONE);
+
+        item.property_expression("item")
+            .chain_property::<Type>("saved")
+            .bind(&self.imp().saved_icon.get(), "visible", glib::Object::NONE);
     }
 
     pub fn set_selected(&self, selected: bool) {
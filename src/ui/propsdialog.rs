@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{PropValueType, PwNodeObject, PwPropInfoObject};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use glib::clone;
+use std::cell::RefCell;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/propsdialog.ui")]
+    #[properties(wrapper_type = super::PwPropsDialog)]
+    pub struct PwPropsDialog {
+        #[property(get, set, construct_only)]
+        pub(super) node_object: RefCell<Option<PwNodeObject>>,
+
+        #[template_child]
+        pub props_group: TemplateChild<adw::PreferencesGroup>,
+
+        pub(super) rows: RefCell<Vec<adw::ActionRow>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwPropsDialog {
+        const NAME: &'static str = "PwPropsDialog";
+        type Type = super::PwPropsDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwPropsDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let item = obj.node_object().expect("Node object");
+
+            obj.set_title(Some(&format!("{} – {}", gettext("Parameters"), item.name())));
+
+            item.propinfomodel().connect_items_changed(clone!(@weak obj => move |_, _, _, _| obj.update_rows()));
+            obj.update_rows();
+        }
+    }
+    impl WidgetImpl for PwPropsDialog {}
+    impl WindowImpl for PwPropsDialog {}
+    impl AdwWindowImpl for PwPropsDialog {}
+}
+
+glib::wrapper! {
+    pub struct PwPropsDialog(ObjectSubclass<imp::PwPropsDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwPropsDialog {
+    pub(crate) fn new(parent: &impl IsA<gtk::Window>, node_object: &PwNodeObject) -> Self {
+        glib::Object::builder()
+            .property("transient-for", parent)
+            .property("node-object", node_object)
+            .build()
+    }
+
+    fn update_rows(&self) {
+        let imp = self.imp();
+        let Some(item) = self.node_object() else {
+            return;
+        };
+
+        for row in imp.rows.take() {
+            imp.props_group.remove(&row);
+        }
+
+        let rows: Vec<adw::ActionRow> = item
+            .propinfomodel()
+            .iter::<PwPropInfoObject>()
+            .map_while(Result::ok)
+            .map(|info| {
+                let row = adw::ActionRow::builder()
+                    .title(info.key())
+                    .subtitle(info.description())
+                    .build();
+                let control = control_for(&info);
+                row.add_suffix(&control);
+                row
+            })
+            .collect();
+
+        for row in &rows {
+            imp.props_group.add(row);
+        }
+        imp.rows.replace(rows);
+    }
+}
+
+fn control_for(info: &PwPropInfoObject) -> gtk::Widget {
+    let valuetype = info.valuetype();
+
+    if valuetype == PropValueType::Bool {
+        let switch = gtk::Switch::builder().valign(gtk::Align::Center).build();
+        info.bind_property("boolean", &switch, "active").sync_create().bidirectional().build();
+        return switch.upcast();
+    }
+
+    if valuetype == PropValueType::String {
+        let entry = gtk::Entry::builder().valign(gtk::Align::Center).build();
+        info.bind_property("text", &entry.buffer(), "text").sync_create().build();
+        entry.connect_activate(clone!(@weak info => move |entry| info.set_text(entry.text().as_str())));
+        return entry.upcast();
+    }
+
+    if info.is_enum() {
+        let dropdown = gtk::DropDown::builder()
+            .model(&info.labels())
+            .valign(gtk::Align::Center)
+            .build();
+        info.bind_property("selected", &dropdown, "selected").sync_create().bidirectional().build();
+        return dropdown.upcast();
+    }
+
+    let integer = !matches!(valuetype, PropValueType::Float | PropValueType::Double);
+    let (min, max) = if info.has_range() {
+        (info.min(), info.max())
+    } else {
+        (i32::MIN as f64, i32::MAX as f64)
+    };
+    let step = match info.step() {
+        step if step > 0.0 => step,
+        _ if integer => 1.0,
+        _ if info.has_range() => (max - min) / 100.0,
+        _ => 0.01,
+    };
+
+    let adjustment = gtk::Adjustment::new(info.number(), min, max, step, step * 10.0, 0.0);
+    let spin = gtk::SpinButton::builder()
+        .adjustment(&adjustment)
+        .digits(if integer { 0 } else { 3 })
+        .numeric(true)
+        .valign(gtk::Align::Center)
+        .build();
+    info.bind_property("number", &adjustment, "value").sync_create().bidirectional().build();
+    spin.upcast()
+}
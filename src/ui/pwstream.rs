@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{fmt::Debug, time::Duration};
+
+use glib::ControlFlow;
+use pipewire::{properties::Properties, spa, stream::*, Context, Loop};
+use std::os::fd::AsRawFd;
+
+/// A PipeWire stream on a connection of its own, dispatched from the GLib main loop.
+///
+/// The stream carries F32 samples and is disconnected when this is dropped.
+pub struct PwStream<D> {
+    _loop: Loop,
+    _context: Context<pipewire::Loop>,
+    stream: Option<Stream>,
+    _listener: StreamListener<D>,
+    source: Option<glib::SourceId>,
+}
+
+impl<D> Debug for PwStream<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PwStream")
+    }
+}
+
+impl<D> PwStream<D> {
+    /// Creates the stream, lets `listen` register its listener and connects it to node `id`.
+    /// `positions` is the channel map the stream is created with.
+    pub fn new(
+        name: &str,
+        props: Properties,
+        direction: spa::Direction,
+        id: u32,
+        rate: u32,
+        positions: &[u32],
+        listen: impl FnOnce(&Stream) -> Result<StreamListener<D>, pipewire::Error>,
+    ) -> Result<Self, anyhow::Error> {
+        let loop_ = Loop::new()?;
+        let context = Context::new(&loop_)?;
+        let core = context.connect(None)?;
+
+        let fd = loop_.fd();
+
+        let source = glib::source::unix_fd_add_local(fd.as_raw_fd(), glib::IOCondition::all(), {
+            let loop_ = loop_.clone();
+            move |_, _| {
+                loop_.iterate(Duration::ZERO);
+
+                ControlFlow::Continue
+            }
+        });
+
+        let stream: Stream = Stream::new(&core, name, props)?;
+        let listener = listen(&stream)?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let fmtpod = create_audio_format_pod(&mut buffer, rate, positions);
+
+        stream.connect(
+            direction,
+            Some(id),
+            StreamFlags::AUTOCONNECT | StreamFlags::MAP_BUFFERS | StreamFlags::RT_PROCESS | StreamFlags::DONT_RECONNECT,
+            &mut [fmtpod],
+        )?;
+
+        Ok(Self {
+            _loop: loop_,
+            _context: context,
+            stream: Some(stream),
+            _listener: listener,
+            source: Some(source),
+        })
+    }
+}
+
+impl<D> Drop for PwStream<D> {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            stream.disconnect().unwrap();
+        }
+        if let Some(source) = self.source.take() {
+            source.remove();
+        }
+    }
+}
+
+fn create_audio_format_pod<'a>(buffer: &'a mut Vec<u8>, rate: u32, positions: &[u32]) -> &'a spa::pod::Pod {
+    let mut position = [spa::sys::SPA_AUDIO_CHANNEL_UNKNOWN; 64];
+    for (target, source) in position.iter_mut().zip(positions) {
+        *target = *source;
+    }
+
+    let mut audio_info = spa::param::audio::AudioInfoRaw::new();
+    audio_info.set_format(spa::param::audio::AudioFormat::F32LE);
+    audio_info.set_rate(rate);
+    audio_info.set_channels(positions.len() as u32);
+    audio_info.set_position(position);
+
+    let values = spa::pod::serialize::PodSerializer::serialize(
+        std::io::Cursor::new(buffer),
+        &spa::pod::Value::Object(pipewire::spa::pod::Object {
+            type_: spa::sys::SPA_TYPE_OBJECT_Format,
+            id: spa::sys::SPA_PARAM_EnumFormat,
+            properties: audio_info.into(),
+        }),
+    )
+    .unwrap()
+    .0
+    .into_inner();
+
+    spa::pod::Pod::from_bytes(values).unwrap()
+}
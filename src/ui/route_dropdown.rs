@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::{
-    backend::{NodeType, PwNodeObject, PwRouteFilterModel, PwRouteObject},
+    backend::{HistoryChange, NodeType, PwNodeObject, PwRouteFilterModel, PwRouteObject, PwvucontrolManager},
     macros::*,
     ui::PwProfileRow,
 };
@@ -190,6 +190,9 @@ mod imp {
                     pwvucontrol_critical!("Setting route to {}", dropdown.selected());
 
                     if let Some(routeobject) = dropdown.selected_item().and_downcast::<PwRouteObject>() {
+                        if let Some(change) = HistoryChange::node_route(&nodeobject, &routeobject) {
+                            PwvucontrolManager::default().history().record(change);
+                        }
                         nodeobject.set_route(&routeobject);
                     }
 
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{PwRuleObject, PwvucontrolManager, RuleAction, RuleTrigger};
+use adw::{prelude::*, subclass::prelude::*};
+use glib::clone;
+use std::cell::RefCell;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/rulerow.ui")]
+    #[properties(wrapper_type = super::PwRuleRow)]
+    pub struct PwRuleRow {
+        #[property(get, set, construct_only)]
+        pub(super) rule: RefCell<Option<PwRuleObject>>,
+
+        #[template_child]
+        pub name_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub trigger_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub pattern_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub action_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub argument_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub remove_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwRuleRow {
+        const NAME: &'static str = "PwRuleRow";
+        type Type = super::PwRuleRow;
+        type ParentType = adw::ExpanderRow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwRuleRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let rule = obj.rule().expect("Rule object");
+
+            let triggers: Vec<String> = RuleTrigger::ALL.iter().map(RuleTrigger::title).collect();
+            let triggers: Vec<&str> = triggers.iter().map(String::as_str).collect();
+            self.trigger_row.set_model(Some(&gtk::StringList::new(&triggers)));
+
+            let actions: Vec<String> = RuleAction::ALL.iter().map(RuleAction::title).collect();
+            let actions: Vec<&str> = actions.iter().map(String::as_str).collect();
+            self.action_row.set_model(Some(&gtk::StringList::new(&actions)));
+
+            rule.bind_property("name", &*obj, "title").sync_create().build();
+            rule.bind_property("trigger", &*obj, "subtitle")
+                .transform_to(|_, trigger: RuleTrigger| Some(trigger.title()))
+                .sync_create()
+                .build();
+            rule.bind_property("enabled", &*obj, "enable-expansion").sync_create().bidirectional().build();
+
+            rule.bind_property("name", &self.name_row.get(), "text").sync_create().bidirectional().build();
+            rule.bind_property("pattern", &self.pattern_row.get(), "text").sync_create().bidirectional().build();
+            rule.bind_property("argument", &self.argument_row.get(), "text").sync_create().bidirectional().build();
+
+            rule.bind_property("trigger", &self.trigger_row.get(), "selected")
+                .transform_to(|_, trigger: RuleTrigger| Some(trigger as u32))
+                .transform_from(|_, selected: u32| Some(RuleTrigger::from(selected)))
+                .sync_create()
+                .bidirectional()
+                .build();
+            rule.bind_property("action", &self.action_row.get(), "selected")
+                .transform_to(|_, action: RuleAction| Some(action as u32))
+                .transform_from(|_, selected: u32| Some(RuleAction::from(selected)))
+                .sync_create()
+                .bidirectional()
+                .build();
+
+            rule.connect_notify_local(None, |_, _| PwvucontrolManager::default().save_rules());
+
+            self.remove_button.connect_clicked(clone!(@weak rule => move |_| {
+                let rule_model = PwvucontrolManager::default().rule_model();
+                let position = (0u32..)
+                    .zip(rule_model.iter::<PwRuleObject>().map_while(Result::ok))
+                    .find(|(_, item)| *item == rule)
+                    .map(|(position, _)| position);
+                if let Some(position) = position {
+                    rule_model.remove(position);
+                }
+            }));
+        }
+    }
+    impl WidgetImpl for PwRuleRow {}
+    impl ListBoxRowImpl for PwRuleRow {}
+    impl PreferencesRowImpl for PwRuleRow {}
+    impl ExpanderRowImpl for PwRuleRow {}
+}
+
+glib::wrapper! {
+    pub struct PwRuleRow(ObjectSubclass<imp::PwRuleRow>)
+        @extends gtk::Widget, gtk::ListBoxRow, adw::PreferencesRow, adw::ExpanderRow,
+        @implements gtk::Actionable;
+}
+
+impl PwRuleRow {
+    pub(crate) fn new(rule: &PwRuleObject) -> Self {
+        glib::Object::builder().property("rule", rule).build()
+    }
+}
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{PwRuleObject, PwvucontrolManager},
+    ui::PwRuleRow,
+};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/rulespage.ui")]
+    pub struct PwRulesPage {
+        #[template_child]
+        pub add_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub rules_list: TemplateChild<gtk::ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwRulesPage {
+        const NAME: &'static str = "PwRulesPage";
+        type Type = super::PwRulesPage;
+        type ParentType = adw::PreferencesPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PwRulesPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let rule_model = PwvucontrolManager::default().rule_model();
+
+            self.rules_list.bind_model(Some(&rule_model), |item| {
+                let rule = item.downcast_ref::<PwRuleObject>().expect("PwRuleObject");
+                PwRuleRow::new(rule).upcast::<gtk::Widget>()
+            });
+
+            rule_model.connect_items_changed(|_, _, _, _| PwvucontrolManager::default().save_rules());
+
+            self.add_button.connect_clicked(|_| {
+                let rule = PwRuleObject::new(&gettext("New rule"));
+                PwvucontrolManager::default().rule_model().append(&rule);
+            });
+        }
+    }
+    impl WidgetImpl for PwRulesPage {}
+    impl PreferencesPageImpl for PwRulesPage {}
+}
+
+glib::wrapper! {
+    pub struct PwRulesPage(ObjectSubclass<imp::PwRulesPage>)
+        @extends gtk::Widget, adw::PreferencesPage;
+}
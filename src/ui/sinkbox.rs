@@ -2,14 +2,19 @@
 
 use super::volumebox::PwVolumeBoxExt;
 use crate::{
-    backend::{NodeType, PwNodeObject, PwvucontrolManager},
+    backend::{loopbacks::Loopback, NodeType, PwNodeObject, PwvucontrolManager},
     pwvucontrol_info,
-    ui::{PwRouteDropDown, PwVolumeBox, PwVolumeBoxImpl},
+    ui::{nodebindings, PwFormatPanel, PwMicFilterDialog, PwRouteDropDown, PwSpeakerTestDialog, PwVolumeBox, PwVolumeBoxImpl, PwvucontrolWindow, WithDefaultListModel},
 };
-use glib::clone;
+use gettextrs::gettext;
+use glib::{clone, closure_local};
 use gtk::{prelude::*, subclass::prelude::*};
-use std::cell::Cell;
+use std::{cell::{Cell, RefCell}, time::Duration};
 use wireplumber as wp;
+use wp::pw::PipewireObjectExt2;
+
+/// Time without further changes before a new loopback latency is applied.
+const LATENCY_APPLY_DELAY: Duration = Duration::from_millis(500);
 
 mod imp {
     use super::*;
@@ -18,12 +23,44 @@ mod imp {
     #[template(resource = "/com/saivert/pwvucontrol/gtk/sinkbox.ui")]
     pub struct PwSinkBox {
         pub(super) block_default_node_toggle_signal: Cell<bool>,
+        /// Binds the monitor volume scale to the loopback playback stream.
+        pub(super) monitor_binding: RefCell<Option<glib::Binding>>,
+        pub(super) monitor_latency_timeout: RefCell<Option<glib::SourceId>>,
+        /// Follows the node model of the manager, which outlives the row.
+        pub(super) node_model_handler: RefCell<Option<glib::SignalHandlerId>>,
 
         #[template_child]
         pub default_sink_toggle: TemplateChild<gtk::ToggleButton>,
 
         #[template_child]
         pub route_dropdown: TemplateChild<PwRouteDropDown>,
+
+        #[template_child]
+        pub format_button: TemplateChild<gtk::MenuButton>,
+
+        #[template_child]
+        pub format_panel: TemplateChild<PwFormatPanel>,
+
+        #[template_child]
+        pub speaker_test_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub mic_filter_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub monitor_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub monitor_toggle: TemplateChild<gtk::ToggleButton>,
+
+        #[template_child]
+        pub monitor_sink_dropdown: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub monitor_latency_spin: TemplateChild<gtk::SpinButton>,
+
+        #[template_child]
+        pub monitor_volume_scale: TemplateChild<gtk::Scale>,
     }
 
     #[glib::object_subclass]
@@ -62,7 +99,42 @@ mod imp {
                 }
             }));
 
-            pwvucontrol_info!("sinkbox set_nodeobject {}", self.obj().node_object().expect("Node object").name());
+            let node = obj.node_object().expect("Node object");
+            self.format_panel.set_nodeobject(Some(&node));
+
+            let formatmodel = node.formatmodel();
+            self.format_button.set_visible(formatmodel.n_items() > 0);
+            formatmodel.connect_items_changed(clone!(@weak self as widget => move |model, _, _, _| {
+                widget.format_button.set_visible(model.n_items() > 0);
+            }));
+
+            self.speaker_test_button.set_visible(node.nodetype() == NodeType::Sink);
+            self.speaker_test_button.connect_clicked(clone!(@weak node => move |_| {
+                let window = PwvucontrolWindow::default();
+                PwSpeakerTestDialog::new(&window, &node).present();
+            }));
+
+            let node_name: String = node.node_property("node.name");
+            self.mic_filter_button.set_visible(node.nodetype() == NodeType::Source && !PwvucontrolManager::default().is_mic_filter_node(&node_name));
+            self.mic_filter_button.connect_clicked(clone!(@weak node => move |_| {
+                let window = PwvucontrolWindow::default();
+                PwMicFilterDialog::new(&window, &node).present();
+            }));
+
+            if node.nodetype() == NodeType::Source {
+                obj.setup_monitor();
+            }
+
+            pwvucontrol_info!("sinkbox set_nodeobject {}", node.name());
+        }
+
+        fn dispose(&self) {
+            if let Some(handler) = self.node_model_handler.take() {
+                PwvucontrolManager::default().node_model().disconnect(handler);
+            }
+            if let Some(timeout) = self.monitor_latency_timeout.take() {
+                timeout.remove();
+            }
         }
     }
     impl WidgetImpl for PwSinkBox {}
@@ -72,32 +144,14 @@ mod imp {
     #[gtk::template_callbacks]
     impl PwSinkBox {
         #[template_callback]
-        fn default_sink_toggle_toggled(&self, _togglebutton: &gtk::ToggleButton) {
+        fn default_sink_toggle_toggled(&self, togglebutton: &gtk::ToggleButton) {
             if self.block_default_node_toggle_signal.get() {
                 return;
             }
             let obj = self.obj();
             let parent: &PwVolumeBox = obj.upcast_ref();
             let node = parent.node_object().expect("nodeobj");
-            let node_name: String = if _togglebutton.is_active() {
-                node.node_property("node.name")
-            } else {
-                "".to_string()
-            };
-
-            let manager = PwvucontrolManager::default();
-
-            let core = manager.imp().wp_core.get().expect("Core");
-            let defaultnodesapi = wp::plugin::Plugin::find(core, "default-nodes-api").expect("Get mixer-api");
-
-            let type_name = match node.nodetype() {
-                NodeType::Sink => "Audio/Sink",
-                NodeType::Source => "Audio/Source",
-                _ => unreachable!(),
-            };
-
-            let result: bool = defaultnodesapi.emit_by_name("set-default-configured-node-name", &[&type_name, &node_name]);
-            wp::info!("set-default-configured-node-name result: {result:?}");
+            nodebindings::set_default_node(&node, togglebutton.is_active());
         }
     }
 }
@@ -122,4 +176,108 @@ impl PwSinkBox {
         self.imp().default_sink_toggle.set_active(node.boundid() == id);
         imp.block_default_node_toggle_signal.set(false);
     }
+
+    /// Sets up the loopback controls that let the user hear a source.
+    fn setup_monitor(&self) {
+        let imp = self.imp();
+        let manager = PwvucontrolManager::default();
+        let source = self.monitor_source();
+        imp.monitor_box.set_visible(true);
+
+        let sinks = WithDefaultListModel::new(Some(&manager.sink_model()));
+        sinks.set_default_text(&gettext("Default output"));
+        imp.monitor_sink_dropdown.set_expression(Some(gtk::ClosureExpression::new::<String>(
+            gtk::Expression::NONE,
+            closure_local!(move |item: glib::Object| {
+                if let Some(item) = item.downcast_ref::<PwNodeObject>() {
+                    item.name()
+                } else {
+                    item.downcast_ref::<gtk::StringObject>().map(|item| item.string().to_string()).unwrap_or_default()
+                }
+            }),
+        )));
+        imp.monitor_sink_dropdown.set_model(Some(&sinks));
+
+        let loopback = manager.loopback(&source);
+        imp.monitor_toggle.set_active(loopback.is_some());
+        let loopback = loopback.unwrap_or_else(|| Loopback::new(&source, &self.node_object().expect("nodeobj").name()));
+        imp.monitor_latency_spin.set_value(f64::from(loopback.latency));
+        let sink_pos = manager
+            .sink_model()
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .position(|sink| sink.wpnode().pw_property::<String>("node.name").is_ok_and(|x| x == loopback.sink));
+        imp.monitor_sink_dropdown.set_selected(sink_pos.map_or(0, |pos| pos as u32 + 1));
+
+        imp.monitor_volume_scale.set_format_value_func(|_scale, value| format!("{:.0}%", value * 100.0));
+
+        imp.monitor_toggle.connect_toggled(clone!(@weak self as obj => move |_| obj.update_loopback()));
+        imp.monitor_sink_dropdown.connect_selected_notify(clone!(@weak self as obj => move |_| obj.update_loopback()));
+        imp.monitor_latency_spin.connect_value_changed(clone!(@weak self as obj => move |_| {
+            let timeout = glib::timeout_add_local_once(LATENCY_APPLY_DELAY, clone!(@weak obj => move || {
+                obj.imp().monitor_latency_timeout.take();
+                obj.update_loopback();
+            }));
+            if let Some(old) = obj.imp().monitor_latency_timeout.replace(Some(timeout)) {
+                old.remove();
+            }
+        }));
+
+        // The playback stream of the loopback appears some time after it is created.
+        let handler = manager.node_model().connect_items_changed(clone!(@weak self as obj => move |_, _, _, _| obj.bind_monitor_volume()));
+        imp.node_model_handler.replace(Some(handler));
+        self.bind_monitor_volume();
+    }
+
+    fn monitor_source(&self) -> String {
+        self.node_object().expect("nodeobj").node_property("node.name")
+    }
+
+    /// Creates, replaces or removes the loopback as set up in the monitor controls.
+    fn update_loopback(&self) {
+        let imp = self.imp();
+        let manager = PwvucontrolManager::default();
+        let source = self.monitor_source();
+
+        if imp.monitor_toggle.is_active() {
+            let mut loopback = Loopback::new(&source, &self.node_object().expect("nodeobj").name());
+            loopback.latency = imp.monitor_latency_spin.value_as_int() as u32;
+            if let Some(sink) = imp.monitor_sink_dropdown.selected_item().and_downcast::<PwNodeObject>() {
+                loopback.sink = sink.node_property("node.name");
+            }
+
+            if manager.loopback(&source).as_ref() != Some(&loopback) {
+                manager.set_loopback(loopback);
+            }
+        } else {
+            manager.remove_loopback(&source);
+        }
+
+        self.bind_monitor_volume();
+    }
+
+    fn bind_monitor_volume(&self) {
+        let imp = self.imp();
+        let node = PwvucontrolManager::default().loopback_node(&self.monitor_source());
+        imp.monitor_volume_scale.set_sensitive(node.is_some());
+
+        let bound = imp.monitor_binding.borrow().as_ref().and_then(|binding| binding.source());
+        if bound == node.clone().map(|node| node.upcast()) {
+            return;
+        }
+
+        if let Some(binding) = imp.monitor_binding.take() {
+            binding.unbind();
+        }
+
+        let binding = node.map(|node| {
+            node.bind_property("volume", &imp.monitor_volume_scale.adjustment(), "value")
+                .sync_create()
+                .bidirectional()
+                .transform_to(nodebindings::linear_to_cubic)
+                .transform_from(nodebindings::cubic_to_linear)
+                .build()
+        });
+        imp.monitor_binding.replace(binding);
+    }
 }
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{PwNodeObject, PwvucontrolManager},
+    config::APP_ID,
+    ui::WithDefaultListModel,
+};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use glib::{clone, closure_local};
+use gtk::gio;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/sleeptimerdialog.ui")]
+    pub struct PwSleepTimerDialog {
+        #[template_child]
+        pub cancel_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub start_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub running_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub remaining_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub stop_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub target_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub duration_spin: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub fade_spin: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub pause_switch: TemplateChild<gtk::Switch>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwSleepTimerDialog {
+        const NAME: &'static str = "PwSleepTimerDialog";
+        type Type = super::PwSleepTimerDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PwSleepTimerDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let manager = PwvucontrolManager::default();
+            let timer = manager.sleep_timer();
+            let settings = gio::Settings::new(APP_ID);
+
+            let targets = WithDefaultListModel::new(Some(&manager.sink_model()));
+            targets.set_default_text(&gettext("All playback streams"));
+            self.target_row.set_expression(Some(gtk::ClosureExpression::new::<String>(
+                gtk::Expression::NONE,
+                closure_local!(move |item: glib::Object| {
+                    if let Some(item) = item.downcast_ref::<PwNodeObject>() {
+                        item.name()
+                    } else {
+                        item.downcast_ref::<gtk::StringObject>().map(|item| item.string().to_string()).unwrap_or_default()
+                    }
+                }),
+            )));
+            self.target_row.set_model(Some(&targets));
+            if let Some(pos) = timer.target().and_then(|target| manager.sink_model().get_node_pos_from_id(target.boundid())) {
+                self.target_row.set_selected(pos + 1);
+            }
+
+            self.duration_spin.set_value(f64::from(settings.uint("sleep-timer-minutes")));
+            self.fade_spin.set_value(f64::from(settings.uint("sleep-timer-fade-minutes")));
+            self.pause_switch.set_active(settings.boolean("sleep-timer-pause-players"));
+
+            timer.connect_remaining_notify(clone!(@weak obj => move |_| obj.update_remaining()));
+            timer.connect_running_notify(clone!(@weak obj => move |_| obj.update_remaining()));
+            obj.update_remaining();
+
+            self.stop_button.connect_clicked(clone!(@weak timer => move |_| timer.cancel()));
+            self.cancel_button.connect_clicked(clone!(@weak obj => move |_| obj.close()));
+            self.start_button.connect_clicked(clone!(@weak obj => move |_| {
+                obj.start();
+                obj.close();
+            }));
+        }
+    }
+    impl WidgetImpl for PwSleepTimerDialog {}
+    impl WindowImpl for PwSleepTimerDialog {}
+    impl AdwWindowImpl for PwSleepTimerDialog {}
+}
+
+glib::wrapper! {
+    pub struct PwSleepTimerDialog(ObjectSubclass<imp::PwSleepTimerDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwSleepTimerDialog {
+    pub(crate) fn new(parent: &impl IsA<gtk::Window>) -> Self {
+        glib::Object::builder().property("transient-for", parent).build()
+    }
+
+    fn update_remaining(&self) {
+        let imp = self.imp();
+        let timer = PwvucontrolManager::default().sleep_timer();
+
+        imp.running_group.set_visible(timer.running());
+        imp.remaining_row.set_subtitle(&format_remaining(timer.remaining()));
+        imp.start_button.set_label(&if timer.running() { gettext("_Restart") } else { gettext("_Start") });
+    }
+
+    fn start(&self) {
+        let imp = self.imp();
+        let settings = gio::Settings::new(APP_ID);
+
+        let minutes = imp.duration_spin.value_as_int() as u32;
+        let fade_minutes = imp.fade_spin.value_as_int() as u32;
+        let pause_players = imp.pause_switch.is_active();
+        let _ = settings.set_uint("sleep-timer-minutes", minutes);
+        let _ = settings.set_uint("sleep-timer-fade-minutes", fade_minutes);
+        let _ = settings.set_boolean("sleep-timer-pause-players", pause_players);
+
+        let target = imp.target_row.selected_item().and_downcast::<PwNodeObject>();
+        PwvucontrolManager::default().sleep_timer().start(target.as_ref(), minutes * 60, fade_minutes * 60, pause_players);
+    }
+}
+
+/// Formats seconds as `m:ss`, or `h:mm:ss` from an hour on.
+pub(crate) fn format_remaining(seconds: u32) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
@@ -0,0 +1,303 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{channel_names, PwNodeObject},
+    macros::*,
+    ui::{SpeakerTestPlayer, TestSignal},
+};
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+use std::{
+    cell::{Cell, RefCell},
+    time::Duration,
+};
+
+/// How long each speaker plays when testing all of them.
+const CYCLE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Grid cell of a speaker in the layout, seen from above with the front at the top.
+fn speaker_cell(name: &str) -> Option<(i32, i32)> {
+    match name {
+        "FL" => Some((0, 0)),
+        "FC" | "MONO" => Some((1, 0)),
+        "FR" => Some((2, 0)),
+        "SL" => Some((0, 1)),
+        "SR" => Some((2, 1)),
+        "RL" => Some((0, 2)),
+        "RC" => Some((1, 2)),
+        "RR" => Some((2, 2)),
+        "LFE" => Some((1, 3)),
+        _ => None,
+    }
+}
+
+fn speaker_title(name: &str) -> String {
+    match name {
+        "FL" => gettext("Front Left"),
+        "FR" => gettext("Front Right"),
+        "FC" => gettext("Front Center"),
+        "LFE" => gettext("Subwoofer"),
+        "RL" => gettext("Rear Left"),
+        "RR" => gettext("Rear Right"),
+        "RC" => gettext("Rear Center"),
+        "SL" => gettext("Side Left"),
+        "SR" => gettext("Side Right"),
+        "MONO" => gettext("Mono"),
+        _ => name.to_string(),
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[template(resource = "/com/saivert/pwvucontrol/gtk/speakertestdialog.ui")]
+    #[properties(wrapper_type = super::PwSpeakerTestDialog)]
+    pub struct PwSpeakerTestDialog {
+        #[property(get, set, construct_only)]
+        pub(super) node_object: RefCell<Option<PwNodeObject>>,
+
+        #[template_child]
+        pub tone_toggle: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub noise_toggle: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub voice_toggle: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub status_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub speaker_grid: TemplateChild<gtk::Grid>,
+        #[template_child]
+        pub cycle_toggle: TemplateChild<gtk::ToggleButton>,
+
+        pub(super) player: RefCell<Option<SpeakerTestPlayer>>,
+        /// Channel map the player was started with.
+        pub(super) positions: RefCell<Vec<u32>>,
+        /// Speaker buttons and titles, by channel index.
+        pub(super) speakers: RefCell<Vec<(gtk::Button, String)>>,
+        pub(super) channel: Cell<Option<usize>>,
+        pub(super) cycle_source: RefCell<Option<glib::SourceId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwSpeakerTestDialog {
+        const NAME: &'static str = "PwSpeakerTestDialog";
+        type Type = super::PwSpeakerTestDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwSpeakerTestDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let item = obj.node_object().expect("Node object");
+
+            for (toggle, signal) in [
+                (&self.tone_toggle, TestSignal::Tone),
+                (&self.noise_toggle, TestSignal::PinkNoise),
+                (&self.voice_toggle, TestSignal::Voice),
+            ] {
+                toggle.connect_toggled(clone!(@weak obj => move |toggle| {
+                    if toggle.is_active() {
+                        if let Some(player) = obj.imp().player.borrow().as_ref() {
+                            player.set_signal(signal);
+                        }
+                    }
+                }));
+            }
+
+            self.cycle_toggle.connect_toggled(clone!(@weak obj => move |toggle| obj.set_cycling(toggle.is_active())));
+
+            // The channel layout of a sink may only be known once its format is.
+            item.connect_local("format", false, clone!(@weak obj => @default-return None, move |_| {
+                obj.setup_speakers();
+                None
+            }));
+            obj.setup_speakers();
+        }
+
+        fn dispose(&self) {
+            if let Some(source) = self.cycle_source.take() {
+                source.remove();
+            }
+            self.player.take();
+        }
+    }
+    impl WidgetImpl for PwSpeakerTestDialog {}
+    impl WindowImpl for PwSpeakerTestDialog {
+        fn close_request(&self) -> glib::Propagation {
+            self.obj().set_cycling(false);
+            self.player.take();
+            self.parent_close_request()
+        }
+    }
+    impl AdwWindowImpl for PwSpeakerTestDialog {}
+}
+
+glib::wrapper! {
+    pub struct PwSpeakerTestDialog(ObjectSubclass<imp::PwSpeakerTestDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwSpeakerTestDialog {
+    pub(crate) fn new(parent: &impl IsA<gtk::Window>, node_object: &PwNodeObject) -> Self {
+        glib::Object::builder()
+            .property("transient-for", parent)
+            .property("node-object", node_object)
+            .build()
+    }
+
+    /// Lays out a button per channel and starts a player with the channel map of the sink.
+    fn setup_speakers(&self) {
+        let imp = self.imp();
+        let item = self.node_object().expect("Node object");
+
+        let Some(format) = item.format() else {
+            imp.status_label.set_label(&gettext("Waiting for the channel layout of the device…"));
+            imp.cycle_toggle.set_sensitive(false);
+            return;
+        };
+
+        let positions = &format.positions[..format.channels.clamp(0, 64) as usize];
+        if imp.player.borrow().is_some() && *imp.positions.borrow() == positions {
+            return;
+        }
+        imp.positions.replace(positions.to_vec());
+
+        imp.cycle_toggle.set_active(false);
+        imp.player.take();
+        imp.channel.set(None);
+        imp.speakers.take();
+        while let Some(child) = imp.speaker_grid.first_child() {
+            imp.speaker_grid.remove(&child);
+        }
+
+        let names: Vec<String> = positions
+            .iter()
+            .map(|position| channel_names(&[*position]).pop().unwrap_or_else(|| position.to_string()))
+            .collect();
+
+        match SpeakerTestPlayer::new(item.boundid(), positions, &names) {
+            Ok(player) => {
+                imp.voice_toggle.set_sensitive(player.has_voice_samples());
+                if imp.voice_toggle.is_active() && !player.has_voice_samples() {
+                    imp.tone_toggle.set_active(true);
+                }
+                player.set_signal(self.signal());
+                imp.player.replace(Some(player));
+            },
+            Err(e) => {
+                pwvucontrol_warning!("Cannot start speaker test on {}: {e}", item.name());
+                imp.status_label.set_label(&gettext("Cannot play to this device."));
+                imp.cycle_toggle.set_sensitive(false);
+                return;
+            },
+        }
+
+        let listener = gtk::Image::from_icon_name("avatar-default-symbolic");
+        listener.set_pixel_size(48);
+        imp.speaker_grid.attach(&listener, 1, 1, 1, 1);
+
+        let mut taken = vec![(1, 1)];
+        let mut extra = 0;
+        let mut speakers = Vec::new();
+        for (index, name) in names.iter().enumerate() {
+            let title = speaker_title(name);
+            let content = gtk::Box::new(gtk::Orientation::Vertical, 6);
+            let icon = gtk::Image::from_icon_name("audio-speakers-symbolic");
+            icon.set_pixel_size(32);
+            content.append(&icon);
+            content.append(&gtk::Label::new(Some(&title)));
+
+            let button = gtk::Button::builder().child(&content).tooltip_text(name.as_str()).build();
+            button.connect_clicked(clone!(@weak self as obj => move |_| {
+                obj.imp().cycle_toggle.set_active(false);
+                let channel = (obj.imp().channel.get() != Some(index)).then_some(index);
+                obj.play(channel);
+            }));
+
+            // Channels without a place in the layout go below it.
+            let cell = speaker_cell(name).filter(|cell| !taken.contains(cell)).unwrap_or_else(|| {
+                let cell = (extra % 3, 4 + extra / 3);
+                extra += 1;
+                cell
+            });
+            taken.push(cell);
+            imp.speaker_grid.attach(&button, cell.0, cell.1, 1, 1);
+            speakers.push((button, title));
+        }
+        imp.speakers.replace(speakers);
+
+        imp.cycle_toggle.set_sensitive(true);
+        self.play(None);
+    }
+
+    fn signal(&self) -> TestSignal {
+        let imp = self.imp();
+        if imp.noise_toggle.is_active() {
+            TestSignal::PinkNoise
+        } else if imp.voice_toggle.is_active() {
+            TestSignal::Voice
+        } else {
+            TestSignal::Tone
+        }
+    }
+
+    /// Plays on the channel with the given index, or stops playing.
+    fn play(&self, channel: Option<usize>) {
+        let imp = self.imp();
+        imp.channel.set(channel);
+        if let Some(player) = imp.player.borrow().as_ref() {
+            player.play_channel(channel);
+        }
+
+        let speakers = imp.speakers.borrow();
+        for (index, (button, _)) in speakers.iter().enumerate() {
+            if Some(index) == channel {
+                button.add_css_class("suggested-action");
+            } else {
+                button.remove_css_class("suggested-action");
+            }
+        }
+
+        match channel.and_then(|index| speakers.get(index)) {
+            Some((_, title)) => imp.status_label.set_label(&gettext("Playing on {}").replace("{}", title)),
+            None => imp.status_label.set_label(&gettext("Click a speaker to play the test signal on it.")),
+        }
+    }
+
+    /// Starts or stops playing on each channel in turn.
+    fn set_cycling(&self, cycling: bool) {
+        let imp = self.imp();
+        if let Some(source) = imp.cycle_source.take() {
+            source.remove();
+            self.play(None);
+        }
+
+        let n_channels = imp.speakers.borrow().len();
+        if !cycling || n_channels == 0 {
+            return;
+        }
+
+        self.play(Some(0));
+        let source = glib::timeout_add_local(CYCLE_INTERVAL, clone!(@weak self as obj => @default-return glib::ControlFlow::Break, move || {
+            let next = obj.imp().channel.get().map_or(0, |index| (index + 1) % n_channels);
+            obj.play(Some(next));
+            glib::ControlFlow::Continue
+        }));
+        imp.cycle_source.replace(Some(source));
+    }
+}
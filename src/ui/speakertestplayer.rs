@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{cell::RefCell, fmt::Debug, fs, path::Path, rc::Rc};
+
+use pipewire::{properties, spa};
+
+use crate::ui::PwStream;
+
+const RATE: u32 = 48000;
+const STRIDE: usize = std::mem::size_of::<f32>();
+const TONE_FREQUENCY: f32 = 440.0;
+/// Subwoofers do not reproduce the normal test tone.
+const LFE_FREQUENCY: f32 = 60.0;
+const AMPLITUDE: f32 = 0.3;
+/// Voice samples are repeated after this much silence.
+const VOICE_PAUSE: usize = RATE as usize;
+/// Voice samples shipped with alsa-utils for its speaker-test.
+const VOICE_SAMPLE_DIR: &str = "/usr/share/sounds/alsa";
+
+/// What is played on the channel under test.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum TestSignal {
+    #[default]
+    Tone,
+    PinkNoise,
+    /// A voice naming the speaker, or the tone for channels without a sample.
+    Voice,
+}
+
+/// Short channel name, like `FL`, and file name of its voice sample.
+const VOICE_SAMPLES: [(&str, &str); 8] = [
+    ("FL", "Front_Left.wav"),
+    ("FR", "Front_Right.wav"),
+    ("FC", "Front_Center.wav"),
+    ("RL", "Rear_Left.wav"),
+    ("RR", "Rear_Right.wav"),
+    ("RC", "Rear_Center.wav"),
+    ("SL", "Side_Left.wav"),
+    ("SR", "Side_Right.wav"),
+];
+
+#[derive(Default)]
+struct Channel {
+    lfe: bool,
+    voice: Option<Vec<f32>>,
+}
+
+/// Generates the test signal, shared between the player and the process callback.
+struct Generator {
+    channels: Vec<Channel>,
+    signal: TestSignal,
+    channel: Option<usize>,
+    position: usize,
+    /// State of the pink noise filter and its random number generator.
+    pink: [f32; 7],
+    seed: u32,
+}
+
+impl Generator {
+    fn sample(&mut self, channel: &Channel) -> f32 {
+        let position = self.position;
+        self.position += 1;
+
+        match (self.signal, &channel.voice) {
+            (TestSignal::Voice, Some(voice)) => voice.get(position % (voice.len() + VOICE_PAUSE)).copied().unwrap_or(0.0),
+            (TestSignal::PinkNoise, _) => self.pink_noise() * AMPLITUDE,
+            _ => {
+                let frequency = if channel.lfe { LFE_FREQUENCY } else { TONE_FREQUENCY };
+                let t = position as f32 / RATE as f32;
+                (2.0 * std::f32::consts::PI * frequency * t).sin() * AMPLITUDE
+            },
+        }
+    }
+
+    /// White noise through Paul Kellet's pink filter.
+    fn pink_noise(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        let white = self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+
+        let b = &mut self.pink;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.1538520;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+
+        pink * 0.11
+    }
+
+    /// Fills interleaved frames, silence on all but the channel under test.
+    fn fill(&mut self, frames: &mut [u8], n_frames: usize) {
+        let n_channels = self.channels.len();
+        frames[..n_frames * n_channels * STRIDE].fill(0);
+
+        let Some(index) = self.channel.filter(|x| *x < n_channels) else {
+            return;
+        };
+
+        let channel = std::mem::take(&mut self.channels[index]);
+        for frame in 0..n_frames {
+            let offset = (frame * n_channels + index) * STRIDE;
+            frames[offset..offset + STRIDE].copy_from_slice(&self.sample(&channel).to_le_bytes());
+        }
+        self.channels[index] = channel;
+    }
+}
+
+/// Plays test signals on the individual channels of a sink.
+pub struct SpeakerTestPlayer {
+    _stream: PwStream<Rc<RefCell<Generator>>>,
+    generator: Rc<RefCell<Generator>>,
+}
+
+impl Debug for SpeakerTestPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SpeakerTestPlayer")
+    }
+}
+
+impl SpeakerTestPlayer {
+    /// `positions` is the channel map of the sink, `names` the matching short channel names.
+    pub fn new(id: u32, positions: &[u32], names: &[String]) -> Result<Self, anyhow::Error> {
+        anyhow::ensure!(!positions.is_empty(), "No channels to test");
+
+        let generator = Rc::new(RefCell::new(Generator {
+            channels: names
+                .iter()
+                .map(|name| Channel {
+                    lfe: name == "LFE",
+                    voice: load_voice_sample(name),
+                })
+                .collect(),
+            signal: TestSignal::default(),
+            channel: None,
+            position: 0,
+            pink: [0.0; 7],
+            seed: 0x9e3779b9,
+        }));
+
+        let props = properties! {
+            "media.type" => "Audio",
+            "media.category" => "Playback",
+            "media.role" => "Production",
+            "node.name" => "pwvucontrol-speaker-test",
+            "media.name" => "Speaker test",
+            "node.latency" => "1024/48000",
+            // Each channel must reach exactly the speaker it is meant for.
+            "stream.dont-remix" => "true"
+        };
+
+        let n_channels = positions.len();
+
+        let stream = PwStream::new("speaker-test", props, spa::Direction::Output, id, RATE, positions, |stream| {
+            stream
+                .add_local_listener_with_user_data(generator.clone())
+                .process(move |stream, generator| {
+                    let Some(mut buffer) = stream.dequeue_buffer() else {
+                        return;
+                    };
+
+                    let datas = buffer.datas_mut();
+                    let data = &mut datas[0];
+
+                    let n_frames = if let Some(slice) = data.data() {
+                        let n_frames = slice.len() / (STRIDE * n_channels);
+                        generator.borrow_mut().fill(slice, n_frames);
+                        n_frames
+                    } else {
+                        0
+                    };
+
+                    let chunk = data.chunk_mut();
+                    *chunk.offset_mut() = 0;
+                    *chunk.stride_mut() = (STRIDE * n_channels) as _;
+                    *chunk.size_mut() = (STRIDE * n_channels * n_frames) as _;
+                })
+                .register()
+        })?;
+
+        Ok(Self {
+            _stream: stream,
+            generator,
+        })
+    }
+
+    pub fn set_signal(&self, signal: TestSignal) {
+        let mut generator = self.generator.borrow_mut();
+        generator.signal = signal;
+        generator.position = 0;
+    }
+
+    /// Plays on the channel with the given index, or nowhere.
+    pub fn play_channel(&self, channel: Option<usize>) {
+        let mut generator = self.generator.borrow_mut();
+        generator.channel = channel;
+        generator.position = 0;
+    }
+
+    /// Whether a voice sample was found for any channel.
+    pub fn has_voice_samples(&self) -> bool {
+        self.generator.borrow().channels.iter().any(|channel| channel.voice.is_some())
+    }
+}
+
+fn load_voice_sample(channel_name: &str) -> Option<Vec<f32>> {
+    let (_, file) = VOICE_SAMPLES.iter().find(|(name, _)| *name == channel_name)?;
+    let bytes = fs::read(Path::new(VOICE_SAMPLE_DIR).join(file)).ok()?;
+    decode_wav(&bytes)
+}
+
+/// Decodes 16 bit PCM WAV data to mono samples at `RATE`.
+fn decode_wav(bytes: &[u8]) -> Option<Vec<f32>> {
+    let u16_at = |offset: usize| Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?));
+    let u32_at = |offset: usize| Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?));
+
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32_at(offset + 4)? as usize;
+        let body = offset + 8;
+
+        if id == b"fmt " {
+            // PCM, channels, rate, bits per sample.
+            format = Some((u16_at(body)?, u16_at(body + 2)?, u32_at(body + 4)?, u16_at(body + 14)?));
+        } else if id == b"data" {
+            let (1, channels, rate, 16) = format? else {
+                return None;
+            };
+            let channels = usize::from(channels.max(1));
+            let data = bytes.get(body..(body + size).min(bytes.len()))?;
+            let samples: Vec<f32> = data
+                .chunks_exact(2 * channels)
+                .map(|frame| i16::from_le_bytes([frame[0], frame[1]]) as f32 / i16::MAX as f32)
+                .collect();
+            return Some(resample(&samples, rate));
+        }
+
+        offset = body + size + size % 2;
+    }
+
+    None
+}
+
+/// Linear resampling, good enough for speech.
+fn resample(samples: &[f32], rate: u32) -> Vec<f32> {
+    if rate == RATE || rate == 0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let n = (samples.len() as u64 * RATE as u64 / rate as u64) as usize;
+    (0..n)
+        .map(|i| {
+            let pos = i as f64 * rate as f64 / RATE as f64;
+            let index = pos as usize;
+            let frac = (pos - index as f64) as f32;
+            let a = samples[index.min(samples.len() - 1)];
+            let b = samples[(index + 1).min(samples.len() - 1)];
+            a + (b - a) * frac
+        })
+        .collect()
+}
@@ -2,16 +2,17 @@
 
 use crate::{
     ui::WithDefaultListModel,
+    backend::NodeControl,
+    backend::NodeType,
     backend::PwNodeObject,
     backend::PwvucontrolManager,
+    macros::*,
 };
 use glib::closure_local;
 use gtk::{self, prelude::*, subclass::prelude::*};
 use std::cell::{Cell, RefCell};
 
 mod imp {
-    use crate::{backend::NodeType, pwvucontrol_info, pwvucontrol_warning};
-
     use super::*;
 
     #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
@@ -147,14 +148,15 @@ mod imp {
                     pwvucontrol_info!("signal blocked");
                     return;
                 }
+                let history = PwvucontrolManager::default().history();
                 if dropdown.selected() == 0 {
-                    nodeobj.unset_default_target();
+                    history.record_nodes(NodeControl::Target, &[nodeobj.clone()], || nodeobj.unset_default_target());
                     return;
                 }
                 if let Some(item) = dropdown.selected_item() {
                     if let Some(item) = item.downcast_ref::<PwNodeObject>() {
                         pwvucontrol_info!("setting item {:?}", item.name());
-                        nodeobj.set_default_target(item);
+                        history.record_nodes(NodeControl::Target, &[nodeobj.clone()], || nodeobj.set_default_target(item));
                     }
                 }
             });
@@ -186,6 +188,38 @@ impl PwStreamDropDown {
         imp.block_signal.set(false);
     }
 
+    /// Shows the explicit target of the stream, or "Default" naming the default node `default_id`.
+    pub fn update_selected(&self, default_id: u32) {
+        let manager = PwvucontrolManager::default();
+        let Some(nodeobj) = self.nodeobj() else {
+            return;
+        };
+
+        let string = if let Some(node) = manager.get_node_by_id(default_id) {
+            format!("Default ({})", node.name())
+        } else {
+            "Default".to_string()
+        };
+        self.set_default_text(&string);
+
+        if let Some(deftarget) = nodeobj.default_target() {
+            let model = match nodeobj.nodetype() {
+                NodeType::StreamInput => manager.source_model(),
+                _ => manager.sink_model(),
+            };
+            if let Some(pos) = model.get_node_pos_from_id(deftarget.boundid()) {
+                pwvucontrol_info!(
+                    "switching to preferred target pos={pos} boundid={} serial={}",
+                    deftarget.boundid(),
+                    deftarget.serial()
+                );
+                self.set_selected_no_send(pos + 1);
+            }
+        } else {
+            self.set_selected_no_send(0);
+        }
+    }
+
     pub fn set_default_text(&self, text: &str) {
         let imp = self.imp();
 
@@ -2,12 +2,11 @@
 
 use crate::{
     backend::{PwNodeObject, PwvucontrolManager},
-    macros::*,
-    ui::{PwStreamDropDown, PwVolumeBox, PwVolumeBoxImpl},
+    ui::{nodebindings, PwStreamDropDown, PwVolumeBox, PwVolumeBoxImpl},
 };
-use glib::{clone, closure_local};
+use glib::clone;
 use gtk::{prelude::*, subclass::prelude::*};
-use wireplumber as wp;
+use std::cell::RefCell;
 
 use super::volumebox::PwVolumeBoxExt;
 
@@ -17,6 +16,8 @@ mod imp {
     #[derive(Default, gtk::CompositeTemplate)]
     #[template(resource = "/com/saivert/pwvucontrol/gtk/streambox.ui")]
     pub struct PwStreamBox {
+        /// Handler on the manager, which outlives the row.
+        metadata_changed_handler: RefCell<Option<glib::SignalHandlerId>>,
         #[template_child]
         pub output_dropdown: TemplateChild<PwStreamDropDown>,
     }
@@ -38,8 +39,6 @@ mod imp {
 
     impl ObjectImpl for PwStreamBox {
         fn constructed(&self) {
-            let manager = PwvucontrolManager::default();
-
             let obj = self.obj();
             let item = obj.node_object().expect("nodeobj");
 
@@ -49,19 +48,8 @@ mod imp {
 
             self.parent_constructed();
 
-            if let Some(metadata) = manager.metadata() {
-                let boundid = item.boundid();
-                let widget = self.obj();
-                let changed_closure = closure_local!(@watch widget =>
-                    move |_obj: &wp::pw::Metadata, id: u32, key: Option<String>, _type: Option<String>, _value: Option<String>| {
-                    let key = key.unwrap_or_default();
-                    if id == boundid && key.contains("target.") {
-                        pwvucontrol_info!("metadata changed handler id: {boundid} {key:?} {_value:?}!");
-                        widget.update_output_device_dropdown();
-                    }
-                });
-                metadata.connect_closure("changed", false, changed_closure);
-            }
+            let handler = nodebindings::connect_target_changed(&item, clone!(@weak obj => move || obj.update_output_device_dropdown()));
+            self.metadata_changed_handler.replace(Some(handler));
 
             // Create our custom output dropdown widget and add it to the layout
             self.output_dropdown.set_nodeobj(Some(&item));
@@ -70,6 +58,12 @@ mod imp {
                 widget.obj().update_output_device_dropdown();
             }));
         }
+
+        fn dispose(&self) {
+            if let Some(handler) = self.metadata_changed_handler.take() {
+                PwvucontrolManager::default().disconnect(handler);
+            }
+        }
     }
     impl WidgetImpl for PwStreamBox {}
     impl ListBoxRowImpl for PwStreamBox {}
@@ -90,37 +84,6 @@ impl PwStreamBox {
     }
 
     pub(crate) fn update_output_device_dropdown(&self) {
-        let manager = PwvucontrolManager::default();
-
-        let item = self.node_object().expect("nodeobj");
-
-        let sinkmodel = manager.get_model_for_nodetype(item.nodetype());
-
-        let imp = self.imp();
-
-        let output_dropdown = imp.output_dropdown.get();
-
-        let id = self.default_node();
-
-        let string = if let Some(node) = manager.get_node_by_id(id) {
-            format!("Default ({})", node.name())
-        } else {
-            "Default".to_string()
-        };
-        output_dropdown.set_default_text(&string);
-
-
-        if let Some(deftarget) = item.default_target() {
-            if let Some(pos) = sinkmodel.get_node_pos_from_id(deftarget.boundid()) {
-                pwvucontrol_info!(
-                    "switching to preferred target pos={pos} boundid={} serial={}",
-                    deftarget.boundid(),
-                    deftarget.serial()
-                );
-                output_dropdown.set_selected_no_send(pos + 1);
-            }
-        } else {
-            output_dropdown.set_selected_no_send(0);
-        }
+        self.imp().output_dropdown.update_selected(self.default_node());
     }
 }
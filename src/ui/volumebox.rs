@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::{backend::{PwChannelObject, PwNodeObject, PwvucontrolManager}, ui::{LevelbarProvider, PwChannelBox, PwvucontrolWindow}};
+use crate::{backend::{NodeType, PwChannelObject, PwNodeObject, PwvucontrolManager}, ui::{nodebindings, LevelbarProvider, PwChannelBox, PwClapTestDialog, PwInspectorDialog, PwPropsDialog, PwvucontrolWindow}};
+
+use gettextrs::gettext;
 
 use glib::{clone, closure_local, ControlFlow, SignalHandlerId};
 use gtk::{prelude::*, subclass::prelude::*};
@@ -20,6 +22,7 @@ mod imp {
 
         metadata_changed_event: Cell<Option<SignalHandlerId>>,
         levelbarprovider: OnceCell<LevelbarProvider>,
+        overamplification_handler: RefCell<Option<SignalHandlerId>>,
         timeoutid: Cell<Option<glib::SourceId>>,
         pub(super) level: Cell<f32>,
         pub(super) default_node: Cell<u32>,
@@ -31,6 +34,12 @@ mod imp {
         #[template_child]
         pub title_label: TemplateChild<gtk::Label>,
         #[template_child]
+        pub title_edit_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub title_editable: TemplateChild<gtk::EditableLabel>,
+        #[template_child]
+        pub reset_name_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub subtitle_label: TemplateChild<gtk::Label>,
         #[template_child]
         pub volume_scale: TemplateChild<gtk::Scale>,
@@ -52,6 +61,32 @@ mod imp {
         pub monitorvolumescale: TemplateChild<gtk::Scale>,
         #[template_child]
         pub container: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub latency_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub latency_title: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub latency_spin: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub latency_buttons: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub latency_apply: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub latency_reset: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub latencyoffset_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub latencyoffset_spin: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub calibrate_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub properties_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub network_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub sandbox_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub params_button: TemplateChild<gtk::Button>,
     }
 
     #[glib::object_subclass]
@@ -74,14 +109,6 @@ mod imp {
     #[glib::derived_properties]
     impl ObjectImpl for PwVolumeBox {
         fn constructed(&self) {
-            fn linear_to_cubic(_binding: &glib::Binding, i: f32) -> Option<f64> {
-                Some(i.cbrt() as f64)
-            }
-
-            fn cubic_to_linear(_binding: &glib::Binding, i: f64) -> Option<f32> {
-                Some((i * i * i) as f32)
-            }
-
             self.parent_constructed();
 
             let item = self.node_object.borrow();
@@ -90,29 +117,52 @@ mod imp {
             self.icon.set_icon_name(Some(&item.iconname()));
 
             item.bind_property("name", &self.title_label.get(), "label").sync_create().build();
-
-            item.bind_property("description", &self.subtitle_label.get(), "label")
+            item.bind_property("network", &self.network_icon.get(), "visible").sync_create().build();
+            item.bind_property("sandbox", &self.sandbox_icon.get(), "visible")
                 .sync_create()
+                .transform_to(|_, sandbox: String| Some(!sandbox.is_empty()))
                 .build();
-
-            item.bind_property("mute", &self.mutebtn.get(), "active")
+            item.bind_property("sandbox", &self.sandbox_icon.get(), "tooltip-text")
                 .sync_create()
-                .bidirectional()
+                .transform_to(|_, sandbox: String| Some(gettext("Sandboxed ({})").replace("{}", &sandbox)))
                 .build();
 
-            item.bind_property("volume", &self.volume_scale.adjustment(), "value")
+            // Sinks and sources can be renamed inline.
+            if matches!(item.nodetype(), NodeType::Sink | NodeType::Source) {
+                self.title_label.set_visible(false);
+                self.title_edit_box.set_visible(true);
+
+                item.bind_property("name", &self.title_editable.get(), "text").sync_create().build();
+                item.bind_property("renamed", &self.reset_name_button.get(), "visible").sync_create().build();
+
+                self.title_editable.connect_editing_notify(clone!(@weak item => move |label| {
+                    if label.is_editing() {
+                        return;
+                    }
+                    let text = label.text();
+                    if text.is_empty() {
+                        item.rename(None);
+                    } else if item.name() != text.as_str() {
+                        item.rename(Some(&text));
+                    }
+                }));
+
+                self.reset_name_button.connect_clicked(clone!(@weak item => move |_| item.rename(None)));
+            }
+
+            item.bind_property("description", &self.subtitle_label.get(), "label")
                 .sync_create()
-                .bidirectional()
-                .transform_to(linear_to_cubic)
-                .transform_from(cubic_to_linear)
                 .build();
 
+            nodebindings::bind_mute(&item, &self.mutebtn);
+            nodebindings::bind_volume(&item, &self.volume_scale);
+
             #[rustfmt::skip]
             item.bind_property("monitorvolume", &self.monitorvolumescale.adjustment(), "value")
                 .sync_create()
                 .bidirectional()
-                .transform_to(linear_to_cubic)
-                .transform_from(cubic_to_linear)
+                .transform_to(nodebindings::linear_to_cubic)
+                .transform_from(nodebindings::cubic_to_linear)
                 .build();
 
             self.volume_scale.set_format_value_func(|_scale, value| {
@@ -129,36 +179,76 @@ mod imp {
                 .bidirectional()
                 .build();
 
+            self.properties_button.connect_clicked(clone!(@weak item => move |_| {
+                let window = PwvucontrolWindow::default();
+                PwInspectorDialog::new(&window, &item.wpnode()).present();
+            }));
+
+            let propinfomodel = item.propinfomodel();
+            self.params_button.set_visible(propinfomodel.n_items() > 0);
+            propinfomodel.connect_items_changed(clone!(@weak self as widget => move |model, _, _, _| {
+                widget.params_button.set_visible(model.n_items() > 0);
+            }));
+            self.params_button.connect_clicked(clone!(@weak item => move |_| {
+                let window = PwvucontrolWindow::default();
+                PwPropsDialog::new(&window, &item).present();
+            }));
+
             item.bind_property("mainvolume", &self.mainvolumescale.adjustment(), "value")
                 .sync_create()
                 .bidirectional()
-                .transform_to(linear_to_cubic)
-                .transform_from(cubic_to_linear)
+                .transform_to(nodebindings::linear_to_cubic)
+                .transform_from(nodebindings::cubic_to_linear)
                 .build();
 
-            fn update_overamplification(volume_scale: &gtk::Scale) {
-                let window: PwvucontrolWindow = PwvucontrolWindow::default();
-                let enable_overamplification = window.imp().settings.boolean("enable-overamplification");
-                
-                volume_scale.clear_marks();
-                volume_scale.add_mark(0.0, gtk::PositionType::Bottom, Some("Silence"));
-                volume_scale.add_mark(1.0, gtk::PositionType::Bottom, Some("100%"));
-    
-
-                if enable_overamplification {
-                    volume_scale.add_mark(1.525, gtk::PositionType::Bottom, Some("150%"));
-                    volume_scale.set_range(0.0, 1.525);
-                } else {
-                    volume_scale.set_range(0.0, 1.0);
-                }
+            item.bind_property("latencystr", &self.latency_label.get(), "label").sync_create().build();
+
+            match item.nodetype() {
+                NodeType::Sink | NodeType::Source => {
+                    self.latency_buttons.set_visible(false);
+                    // Shown for reference only, the latency offset is what users adjust on devices.
+                    self.latency_spin.set_sensitive(false);
+                    item.bind_property("processlatency", &self.latency_spin.adjustment(), "value")
+                        .sync_create()
+                        .build();
+                    item.bind_property("latencyoffset", &self.latencyoffset_spin.adjustment(), "value")
+                        .sync_create()
+                        .bidirectional()
+                        .build();
+
+                    self.calibrate_button.set_visible(item.nodetype() == NodeType::Sink);
+                    self.calibrate_button.connect_clicked(clone!(@weak item => move |_| {
+                        let window = PwvucontrolWindow::default();
+                        PwClapTestDialog::new(&window, &item).present();
+                    }));
+                },
+                _ => {
+                    self.latencyoffset_box.set_visible(false);
+                    self.latency_title.set_label(&gettext("Requested latency (samples)"));
+                    let adjustment = self.latency_spin.adjustment();
+                    adjustment.set_lower(16.0);
+                    adjustment.set_upper(8192.0);
+                    adjustment.set_step_increment(16.0);
+                    adjustment.set_page_increment(256.0);
+
+                    let (quantum, _) = item.quantum_and_rate();
+                    let requested = item.requested_latency()
+                        .and_then(|x| x.split_once('/').and_then(|(num, _)| num.parse::<f64>().ok()));
+                    adjustment.set_value(requested.unwrap_or(quantum as f64));
+
+                    self.latency_apply.connect_clicked(clone!(@weak self as widget, @weak item => move |_| {
+                        item.set_requested_latency(Some(widget.latency_spin.value_as_int() as u32));
+                    }));
+                    self.latency_reset.connect_clicked(clone!(@weak self as widget, @weak item => move |_| {
+                        item.set_requested_latency(None);
+                        let (quantum, _) = item.quantum_and_rate();
+                        widget.latency_spin.set_value(quantum as f64);
+                    }));
+                },
             }
 
-            update_overamplification(&self.volume_scale);
-
-            let window = PwvucontrolWindow::default();
-            window.imp().settings.connect_changed(Some("enable-overamplification"), clone!(@weak self as widget => move |_,_| {
-                update_overamplification(&widget.volume_scale);
-            }));
+            let handler = nodebindings::watch_overamplification(&self.volume_scale, nodebindings::update_volume_range);
+            self.overamplification_handler.replace(Some(handler));
 
             let manager = PwvucontrolManager::default();
 
@@ -202,7 +292,9 @@ mod imp {
             // Monitoring ourselves cause an infinite loop.
             if item.name() != "pwvucontrol-peak-detect" {
 
-                if let Ok(provider) = LevelbarProvider::new(&self.obj(), item.boundid()) {
+                let obj = self.obj();
+                let on_level = clone!(@weak obj => move |level| obj.set_level(level));
+                if let Ok(provider) = LevelbarProvider::new(on_level, item.boundid()) {
                     self.levelbarprovider.set(provider).expect("Provider not set already");
 
                     self.timeoutid.set(Some(glib::timeout_add_local(
@@ -229,6 +321,9 @@ mod imp {
             if let Some(t) = self.timeoutid.take() {
                 t.remove();
             }
+            if let Some(handler) = self.overamplification_handler.take() {
+                nodebindings::unwatch_overamplification(handler);
+            }
         }
     }
     impl WidgetImpl for PwVolumeBox {}
@@ -3,13 +3,15 @@
 use crate::macros::*;
 use crate::{
     application::PwvucontrolApplication,
-    backend::{PwDeviceObject, PwNodeObject, PwvucontrolManager},
+    backend::{disableddevices, PwAppGroupModel, PwAppGroupObject, PwDeviceObject, PwNodeObject, PwNodeSearchFilter, PwvucontrolManager},
     config::{APP_ID, PROFILE},
-    ui::{devicebox::PwDeviceBox, PwStreamBox, PwSinkBox, PwVolumeBox},
+    ui::{devicebox::PwDeviceBox, PwAppGroupRow, PwLogPage, PwMixerStrip, PwNetworkPage, PwSleepTimerDialog, PwStreamBox, PwSinkBox, PwVolumeBox, sleeptimerdialog::format_remaining},
 };
 use adw::subclass::prelude::*;
+use gettextrs::gettext;
 use glib::clone;
 use gtk::{gio, prelude::*};
+use std::cell::{Cell, OnceCell, RefCell};
 
 pub enum PwvucontrolWindowView {
     Connected,
@@ -36,11 +38,58 @@ mod imp {
         #[template_child]
         pub cardlist: TemplateChild<gtk::ListBox>,
         #[template_child]
+        pub playback_layout: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub record_layout: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub input_layout: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub output_layout: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub playbackstrips: TemplateChild<gtk::FlowBox>,
+        #[template_child]
+        pub recordstrips: TemplateChild<gtk::FlowBox>,
+        #[template_child]
+        pub inputstrips: TemplateChild<gtk::FlowBox>,
+        #[template_child]
+        pub outputstrips: TemplateChild<gtk::FlowBox>,
+        #[template_child]
+        pub disabled_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub disabledlist: TemplateChild<gtk::ListBox>,
+        #[template_child]
         pub viewstack: TemplateChild<gtk::Stack>,
         #[template_child]
         pub reconnectbtn: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub search_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub search_bar: TemplateChild<gtk::SearchBar>,
+        #[template_child]
+        pub search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub playing_toggle: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub muted_toggle: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub target_toggle: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub sleep_timer_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub sleep_timer_content: TemplateChild<adw::ButtonContent>,
 
         pub settings: gio::Settings,
+        /// Search filters of the pages listing nodes, each page keeps its own search.
+        pub(super) playback_filter: PwNodeSearchFilter,
+        pub(super) record_filter: PwNodeSearchFilter,
+        pub(super) input_filter: PwNodeSearchFilter,
+        pub(super) output_filter: PwNodeSearchFilter,
+        pub(super) block_search: Cell<bool>,
+        pub(super) playback_model: OnceCell<gtk::FilterListModel>,
+        pub(super) record_model: OnceCell<gtk::FilterListModel>,
+        pub(super) input_model: OnceCell<gtk::FilterListModel>,
+        pub(super) output_model: OnceCell<gtk::FilterListModel>,
+        pub(super) app_groups: RefCell<Option<PwAppGroupModel>>,
     }
 
     impl Default for PwvucontrolWindow {
@@ -53,9 +102,37 @@ mod imp {
                 inputlist: TemplateChild::default(),
                 outputlist: TemplateChild::default(),
                 cardlist: TemplateChild::default(),
+                playback_layout: TemplateChild::default(),
+                record_layout: TemplateChild::default(),
+                input_layout: TemplateChild::default(),
+                output_layout: TemplateChild::default(),
+                playbackstrips: TemplateChild::default(),
+                recordstrips: TemplateChild::default(),
+                inputstrips: TemplateChild::default(),
+                outputstrips: TemplateChild::default(),
+                disabled_box: TemplateChild::default(),
+                disabledlist: TemplateChild::default(),
                 viewstack: TemplateChild::default(),
                 reconnectbtn: TemplateChild::default(),
+                search_button: TemplateChild::default(),
+                search_bar: TemplateChild::default(),
+                search_entry: TemplateChild::default(),
+                playing_toggle: TemplateChild::default(),
+                muted_toggle: TemplateChild::default(),
+                target_toggle: TemplateChild::default(),
+                sleep_timer_button: TemplateChild::default(),
+                sleep_timer_content: TemplateChild::default(),
                 settings: gio::Settings::new(APP_ID),
+                playback_filter: PwNodeSearchFilter::new(),
+                record_filter: PwNodeSearchFilter::new(),
+                input_filter: PwNodeSearchFilter::new(),
+                output_filter: PwNodeSearchFilter::new(),
+                block_search: Cell::new(false),
+                playback_model: OnceCell::new(),
+                record_model: OnceCell::new(),
+                input_model: OnceCell::new(),
+                output_model: OnceCell::new(),
+                app_groups: RefCell::new(None),
             }
         }
     }
@@ -68,6 +145,8 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             PwVolumeBox::ensure_type();
+            PwNetworkPage::ensure_type();
+            PwLogPage::ensure_type();
 
             klass.bind_template();
             klass.bind_template_callbacks();
@@ -94,52 +173,46 @@ mod imp {
 
             let manager = PwvucontrolManager::default();
 
-            self.playbacklist.bind_model(
-                Some(&manager.stream_output_model()),
-                clone!(@weak self as window => @default-panic, move |item| {
-                    PwStreamBox::new(
-                        item.downcast_ref::<PwNodeObject>()
-                            .expect("RowData is of wrong type"),
-                    )
-                    .upcast::<gtk::Widget>()
-                }),
-            );
+            self.obj().setup_search();
+            let filtered = |model: &gio::ListModel, filter: &PwNodeSearchFilter| gtk::FilterListModel::new(Some(model.clone()), Some(filter.clone()));
 
-            self.recordlist.bind_model(
-                Some(&manager.stream_input_model()),
-                clone!(@weak self as window => @default-panic, move |item| {
-                    PwStreamBox::new(
-                        item.downcast_ref::<PwNodeObject>()
-                            .expect("RowData is of wrong type"),
-                    )
-                    .upcast::<gtk::Widget>()
-                }),
-            );
+            self.playback_model.set(filtered(manager.stream_output_model().upcast_ref(), &self.playback_filter)).expect("playback model set once");
+            self.record_model.set(filtered(manager.stream_input_model().upcast_ref(), &self.record_filter)).expect("record model set once");
+            self.input_model.set(filtered(manager.source_model().upcast_ref(), &self.input_filter)).expect("input model set once");
+            self.output_model.set(filtered(manager.sink_model().upcast_ref(), &self.output_filter)).expect("output model set once");
 
-            self.inputlist.bind_model(
-                Some(&manager.source_model()),
-                clone!(@weak self as window => @default-panic, move |item| {
-                    PwSinkBox::new(
-                        item.downcast_ref::<PwNodeObject>()
-                            .expect("RowData is of wrong type"),
-                    )
-                    .upcast::<gtk::Widget>()
-                }),
+            self.settings.connect_changed(
+                Some("group-streams"),
+                clone!(@weak self as window => move |_, _| window.obj().bind_playback_list()),
+            );
+            self.settings.connect_changed(
+                Some("mixer-layout"),
+                clone!(@weak self as window => move |_, _| window.obj().bind_layouts()),
             );
+            self.obj().bind_layouts();
 
-            self.outputlist.bind_model(
-                Some(&manager.sink_model()),
-                clone!(@weak self as window => @default-panic, move |item| {
-                    PwSinkBox::new(
-                        item.downcast_ref::<PwNodeObject>()
-                            .expect("RowData is of wrong type"),
-                    )
-                    .upcast::<gtk::Widget>()
+            for layout in [&self.playback_layout, &self.record_layout, &self.input_layout, &self.output_layout] {
+                self.settings.bind("mixer-layout", &**layout, "visible-child-name").get().build();
+            }
+
+            let enabled_filter = gtk::CustomFilter::new(|item| {
+                let device: &PwDeviceObject = item.downcast_ref().expect("PwDeviceObject");
+                let disabled = disableddevices::disabled_devices();
+                !device.device_name().is_some_and(|name| disabled.contains_key(&name))
+            });
+            let enabled_devices = gtk::FilterListModel::new(Some(manager.device_model()), Some(enabled_filter.clone()));
+
+            self.settings.connect_changed(
+                Some("disabled-devices"),
+                clone!(@weak self as window => move |_, _| {
+                    enabled_filter.changed(gtk::FilterChange::Different);
+                    window.obj().update_disabled_devices();
                 }),
             );
+            self.obj().update_disabled_devices();
 
             self.cardlist.bind_model(
-                Some(&manager.device_model()),
+                Some(&enabled_devices),
                 clone!(@weak self as window => @default-panic, move |item| {
                     let obj: &PwDeviceObject = item.downcast_ref().expect("PwDeviceObject");
                     PwDeviceBox::new(obj).upcast::<gtk::Widget>()
@@ -156,9 +229,44 @@ mod imp {
             let overamplification_action = self.settings.create_action("enable-overamplification");
             self.obj().add_action(&overamplification_action);
 
+            let save_device_choices_action = self.settings.create_action("save-device-choices");
+            self.obj().add_action(&save_device_choices_action);
+
+            let group_streams_action = self.settings.create_action("group-streams");
+            self.obj().add_action(&group_streams_action);
+
+            let mixer_layout_action = self.settings.create_action("mixer-layout");
+            self.obj().add_action(&mixer_layout_action);
+
+            let history = manager.history();
+            let undo_action = gio::SimpleAction::new("undo", None);
+            undo_action.connect_activate(clone!(@weak history => move |_, _| history.undo()));
+            history.bind_property("can-undo", &undo_action, "enabled").sync_create().build();
+            self.obj().add_action(&undo_action);
+
+            let redo_action = gio::SimpleAction::new("redo", None);
+            redo_action.connect_activate(clone!(@weak history => move |_, _| history.redo()));
+            history.bind_property("can-redo", &redo_action, "enabled").sync_create().build();
+            self.obj().add_action(&redo_action);
+
+            let sleep_timer_action = gio::SimpleAction::new("sleep-timer", None);
+            sleep_timer_action.connect_activate(clone!(@weak self as window => move |_, _| {
+                PwSleepTimerDialog::new(&*window.obj()).present();
+            }));
+            self.obj().add_action(&sleep_timer_action);
+
+            let sleep_timer = manager.sleep_timer();
+            sleep_timer.bind_property("running", &self.sleep_timer_button.get(), "visible").sync_create().build();
+            sleep_timer
+                .bind_property("remaining", &self.sleep_timer_content.get(), "label")
+                .transform_to(|_, remaining: u32| Some(format_remaining(remaining)))
+                .sync_create()
+                .build();
+
             self.obj().load_window_state();
         }
     }
+
     impl WidgetImpl for PwvucontrolWindow {}
     impl WindowImpl for PwvucontrolWindow {
         // save window state on delete event
@@ -188,6 +296,154 @@ impl PwvucontrolWindow {
         glib::Object::builder().property("application", application).build()
     }
 
+    /// Fills the lists of the layout selected by `mixer-layout`. The lists of the other layout are
+    /// left empty, so their rows do not monitor peak levels in the background.
+    fn bind_layouts(&self) {
+        let imp = self.imp();
+        let strips = imp.settings.string("mixer-layout") == "strips";
+        let models = [&imp.playback_model, &imp.record_model, &imp.input_model, &imp.output_model].map(|x| x.get().expect("model"));
+
+        self.bind_playback_list();
+
+        let rows = |model: &gtk::FilterListModel| (!strips).then(|| model.clone());
+        imp.recordlist.bind_model(rows(models[1]).as_ref(), |item| {
+            PwStreamBox::new(item.downcast_ref::<PwNodeObject>().expect("RowData is of wrong type")).upcast::<gtk::Widget>()
+        });
+        imp.inputlist.bind_model(rows(models[2]).as_ref(), |item| {
+            PwSinkBox::new(item.downcast_ref::<PwNodeObject>().expect("RowData is of wrong type")).upcast::<gtk::Widget>()
+        });
+        imp.outputlist.bind_model(rows(models[3]).as_ref(), |item| {
+            PwSinkBox::new(item.downcast_ref::<PwNodeObject>().expect("RowData is of wrong type")).upcast::<gtk::Widget>()
+        });
+
+        // Strips live as long as their node, like rows, rather than being recycled while scrolling.
+        let views = [&imp.playbackstrips, &imp.recordstrips, &imp.inputstrips, &imp.outputstrips];
+        for (view, model) in views.into_iter().zip(models) {
+            view.bind_model(strips.then_some(model), |item| {
+                PwMixerStrip::new(item.downcast_ref::<PwNodeObject>().expect("RowData is of wrong type")).upcast::<gtk::Widget>()
+            });
+        }
+    }
+
+    /// Lists playback streams one per row, or one row per application when grouping is enabled.
+    fn bind_playback_list(&self) {
+        let imp = self.imp();
+        let model = imp.playback_model.get().expect("playback model");
+
+        if imp.settings.string("mixer-layout") == "strips" {
+            imp.playbacklist.bind_model(None::<&gio::ListModel>, |_| unreachable!());
+            imp.app_groups.replace(None);
+        } else if imp.settings.boolean("group-streams") {
+            let groups = PwAppGroupModel::new(model);
+            imp.playbacklist.bind_model(Some(&groups.groups()), |item| {
+                PwAppGroupRow::new(item.downcast_ref::<PwAppGroupObject>().expect("RowData is of wrong type")).upcast::<gtk::Widget>()
+            });
+            imp.app_groups.replace(Some(groups));
+        } else {
+            imp.playbacklist.bind_model(Some(model), |item| {
+                PwStreamBox::new(item.downcast_ref::<PwNodeObject>().expect("RowData is of wrong type")).upcast::<gtk::Widget>()
+            });
+            imp.app_groups.replace(None);
+        }
+    }
+
+    fn setup_search(&self) {
+        let imp = self.imp();
+
+        imp.search_bar.connect_entry(&*imp.search_entry);
+        self.add_action(&gio::PropertyAction::new("search", &*imp.search_bar, "search-mode-enabled"));
+
+        imp.search_entry.connect_search_changed(clone!(@weak self as window => move |entry| {
+            if let Some(filter) = window.search_filter() {
+                filter.set_text(entry.text().to_string());
+            }
+        }));
+        for (toggle, property) in [
+            (&imp.playing_toggle, "playing-only"),
+            (&imp.muted_toggle, "muted-only"),
+            (&imp.target_toggle, "non-default-target"),
+        ] {
+            toggle.connect_toggled(clone!(@weak self as window => move |toggle| {
+                if let Some(filter) = window.search_filter() {
+                    filter.set_property(property, toggle.is_active());
+                }
+            }));
+        }
+
+        imp.stack.connect_visible_child_name_notify(clone!(@weak self as window => move |_| window.update_search_for_page()));
+        self.update_search_for_page();
+    }
+
+    /// Search filter of the visible page, if it lists nodes.
+    fn search_filter(&self) -> Option<PwNodeSearchFilter> {
+        let imp = self.imp();
+        if imp.block_search.get() {
+            return None;
+        }
+
+        let filter = match imp.stack.visible_child_name()?.as_str() {
+            "playback" => &imp.playback_filter,
+            "recording" => &imp.record_filter,
+            "inputdevices" => &imp.input_filter,
+            "outputdevices" => &imp.output_filter,
+            _ => return None,
+        };
+        Some(filter.clone())
+    }
+
+    /// Search only applies to the pages listing streams and devices. The search bar shows the search of the visible page.
+    fn update_search_for_page(&self) {
+        let imp = self.imp();
+        let page = imp.stack.visible_child_name().unwrap_or_default();
+        let streams = matches!(page.as_str(), "playback" | "recording");
+        let filter = self.search_filter();
+        let nodes = filter.is_some();
+
+        imp.search_button.set_visible(nodes);
+        imp.target_toggle.set_visible(streams);
+
+        if let Some(filter) = filter {
+            imp.block_search.set(true);
+            imp.search_entry.set_text(&filter.text());
+            imp.playing_toggle.set_active(filter.playing_only());
+            imp.muted_toggle.set_active(filter.muted_only());
+            imp.target_toggle.set_active(filter.non_default_target());
+            imp.block_search.set(false);
+            imp.search_bar.set_search_mode(imp.search_bar.is_search_mode() || !filter.text().is_empty());
+        } else {
+            imp.search_bar.set_search_mode(false);
+        }
+        imp.search_bar.set_key_capture_widget(if nodes { Some(self.upcast_ref::<gtk::Widget>()) } else { None });
+    }
+
+    fn update_disabled_devices(&self) {
+        let imp = self.imp();
+
+        while let Some(row) = imp.disabledlist.first_child() {
+            imp.disabledlist.remove(&row);
+        }
+
+        let disabled = disableddevices::disabled_devices();
+        for (name, (description, _)) in &disabled {
+            let row = adw::ActionRow::builder()
+                .title(if description.is_empty() { name } else { description })
+                .subtitle(name)
+                .build();
+
+            let button = gtk::Button::builder()
+                .label(gettext("Enable"))
+                .valign(gtk::Align::Center)
+                .build();
+            let name = name.clone();
+            button.connect_clicked(move |_| disableddevices::enable_device(&name));
+            row.add_suffix(&button);
+
+            imp.disabledlist.append(&row);
+        }
+
+        imp.disabled_box.set_visible(!disabled.is_empty());
+    }
+
     pub(crate) fn set_view(&self, view: PwvucontrolWindowView) {
         let imp = self.imp();
         match view {